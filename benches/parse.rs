@@ -0,0 +1,72 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use org_linter::{lint_document, ClockConflict, LintOptions, OrgDocument};
+use std::path::PathBuf;
+
+/// Builds a document that roughly resembles a large, long-lived org file:
+/// nested headlines, clock lines, blocks and lots of plain text.
+fn synthetic_document(headlines: usize) -> String {
+    let mut doc = String::new();
+    for i in 0..headlines {
+        let day = i % 28 + 1;
+        doc.push_str(&format!("* project {i} :work:\n"));
+        doc.push_str(&format!("** task {i}\n"));
+        doc.push_str(":LOGBOOK:\n");
+        doc.push_str(&format!(
+            "CLOCK: [2022-02-{day:0>2} Mon 10:00]--[2022-02-{day:0>2} Mon 11:30] =>  1:30\n"
+        ));
+        doc.push_str(&format!(
+            "CLOCK: [2022-02-{day:0>2} Mon 08:15]--[2022-02-{day:0>2} Mon 09:00] =>  0:45\n"
+        ));
+        doc.push_str(":END:\n");
+        for _ in 0..10 {
+            doc.push_str("Some notes about the task that are neither headlines nor clocks.\n");
+        }
+        doc.push_str("#+begin_src rust\nfn main() {}\n#+end_src\n");
+    }
+    doc
+}
+
+fn parse_benchmark(c: &mut Criterion) {
+    let content = synthetic_document(2_000);
+    c.bench_function("parse synthetic document", |b| {
+        b.iter(|| OrgDocument::parse(PathBuf::from("bench.org"), black_box(&content)))
+    });
+}
+
+/// Per-file lints alone against lints plus the conflict pass across files,
+/// i.e. what `--clock-conflicts` costs.
+fn conflict_benchmark(c: &mut Criterion) {
+    // one month per file so that, like in real data, few clocks overlap
+    let contents = (0..10)
+        .map(|i| synthetic_document(20).replace("2022-02-", &format!("2022-{:0>2}-", i + 1)))
+        .collect::<Vec<_>>();
+    let docs = contents
+        .iter()
+        .enumerate()
+        .map(|(i, content)| OrgDocument::parse(PathBuf::from(format!("bench{i}.org")), content))
+        .collect::<Vec<_>>();
+    let opts = LintOptions::default();
+    let mut group = c.benchmark_group("conflicts");
+    // the conflict pass is quadratic, keep the run time reasonable
+    group.sample_size(10);
+    group.bench_function("lint without conflicts", |b| {
+        b.iter(|| {
+            docs.iter()
+                .map(|doc| lint_document(black_box(doc), &opts).len())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("lint with conflicts", |b| {
+        b.iter(|| {
+            let findings = docs
+                .iter()
+                .map(|doc| lint_document(black_box(doc), &opts).len())
+                .sum::<usize>();
+            findings + ClockConflict::find_conflicts(black_box(&docs)).count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_benchmark, conflict_benchmark);
+criterion_main!(benches);
@@ -10,6 +10,9 @@ pub struct Block<'a> {
 
 impl<'a> Block<'a> {
     pub(crate) fn parse_end(&mut self, line: &str, line_no: usize) -> bool {
+        if !maybe_block(line) {
+            return false;
+        }
         if let Some(captures) = BLOCK_END_RE.captures(line) {
             let kind = captures.get(1).unwrap().as_str();
             if kind == self.kind {
@@ -21,6 +24,13 @@ impl<'a> Block<'a> {
     }
 }
 
+/// Cheap pre-check so that lines which cannot start or end a block skip the
+/// block regexes.
+#[inline]
+pub(crate) fn maybe_block(line: &str) -> bool {
+    line.contains("#+")
+}
+
 pub(crate) static BLOCK_START_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?i)\s*#\+begin_([^\s]+)").expect("block start re"));
 
@@ -31,6 +41,9 @@ impl<'a> TryFrom<&'a str> for Block<'a> {
     type Error = anyhow::Error;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        if !maybe_block(s) {
+            return Err(anyhow::anyhow!("not a block"));
+        }
         if let Some(captures) = BLOCK_START_RE.captures(s) {
             let kind = captures.get(1).unwrap().as_str();
             Ok(Self {
@@ -43,6 +43,76 @@ pub struct Clock<'a> {
     pub start: NaiveDateTime,
     pub end: Option<NaiveDateTime>,
     pub timestamp_type: TimestampType,
+    /// Type of the end timestamp, `None` for running clocks.
+    pub end_timestamp_type: Option<TimestampType>,
+    /// The day of week tokens as written in the file.
+    pub start_weekday: &'a str,
+    pub end_weekday: Option<&'a str>,
+    /// Language of the day of week names when the clock is written.
+    pub locale: WeekdayLocale,
+    /// Leading whitespace of the clock line.
+    pub indent: &'a str,
+    /// Line of the drawer the clock is in, e.g. `:LOGBOOK:`.
+    pub drawer: Option<usize>,
+    /// Number of plain list items the clock is nested in, by indentation,
+    /// e.g. 2 for a clock below `- a` and `  - b`. 0 outside of lists.
+    pub list_depth: usize,
+}
+
+/// Languages of day of week names in timestamps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WeekdayLocale {
+    #[default]
+    En,
+    De,
+}
+
+impl WeekdayLocale {
+    const EN: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    const DE: [&'static str; 7] = ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"];
+
+    fn names(&self) -> &'static [&'static str; 7] {
+        match self {
+            WeekdayLocale::En => &Self::EN,
+            WeekdayLocale::De => &Self::DE,
+        }
+    }
+
+    /// The abbreviated name of `weekday` as org writes it.
+    pub fn abbreviation(&self, weekday: Weekday) -> &'static str {
+        self.names()[weekday.num_days_from_monday() as usize]
+    }
+
+    /// The locale whose abbreviations contain `token`.
+    pub fn detect(token: &str) -> Option<Self> {
+        [WeekdayLocale::En, WeekdayLocale::De]
+            .into_iter()
+            .find(|locale| locale.names().iter().any(|n| n.eq_ignore_ascii_case(token)))
+    }
+}
+
+impl std::str::FromStr for WeekdayLocale {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(WeekdayLocale::En),
+            "de" => Ok(WeekdayLocale::De),
+            _ => Err(anyhow::anyhow!("unknown locale {s:?}, expected en or de")),
+        }
+    }
+}
+
+/// `HH:MM`, with `:SS` only if the timestamp has seconds so that they are
+/// kept when a clock is rewritten.
+fn time_format(
+    time: NaiveDateTime,
+) -> chrono::format::DelayedFormat<chrono::format::StrftimeItems<'static>> {
+    if time.second() == 0 {
+        time.format("%H:%M")
+    } else {
+        time.format("%H:%M:%S")
+    }
 }
 
 impl<'a> std::fmt::Display for Clock<'a> {
@@ -51,14 +121,21 @@ impl<'a> std::fmt::Display for Clock<'a> {
         let type_close = self.timestamp_type.close();
         write!(
             f,
-            "{type_open}{}{type_close}",
-            self.start.format("%Y-%m-%d %a %H:%M")
+            "{type_open}{} {} {}{type_close}",
+            self.start.format("%Y-%m-%d"),
+            self.locale.abbreviation(self.start.weekday()),
+            time_format(self.start)
         )?;
         if let Some(end) = self.end {
+            let end_type = self.end_timestamp_type.unwrap_or(self.timestamp_type);
+            let type_open = end_type.open();
+            let type_close = end_type.close();
             write!(
                 f,
-                "--{type_open}{}{type_close} => {:>5}",
-                end.format("%Y-%m-%d %a %H:%M"),
+                "--{type_open}{} {} {}{type_close} => {:>5}",
+                end.format("%Y-%m-%d"),
+                self.locale.abbreviation(end.weekday()),
+                time_format(end),
                 self.duration_formatted()
             )?;
         }
@@ -72,10 +149,19 @@ impl<'a> Clock<'a> {
     }
 
     pub fn duration(&self) -> Duration {
-        let Some(end) = self.end else {return Duration::zero()};
+        let Some(end) = self.end else {
+            return Duration::zero();
+        };
         end - self.start
     }
 
+    /// Number of calendar days between start and end date. A clock from
+    /// 23:00 to 01:00 the next day spans one day. `None` for running clocks.
+    pub fn calendar_days(&self) -> Option<i64> {
+        let end = self.end?;
+        Some((end.date() - self.start.date()).num_days())
+    }
+
     pub fn duration_formatted(&self) -> String {
         let d = self.duration();
         let negative = d < Duration::zero();
@@ -84,29 +170,157 @@ impl<'a> Clock<'a> {
         format!("{}{hours}:{minutes:0>2}", if negative { "-" } else { "" })
     }
 
-    /// Does the specified duration matche start->end?
+    /// Whether start and end are of different timestamp types, e.g.
+    /// `[...]--<...>`.
+    pub fn has_mixed_timestamp_types(&self) -> bool {
+        self.end_timestamp_type
+            .map(|end| end != self.timestamp_type)
+            .unwrap_or(false)
+    }
+
+    /// Whether a day of week token does not match its date. Tokens that are
+    /// not in one of the known locales are not checked.
+    pub fn has_wrong_weekday(&self) -> bool {
+        let wrong = |token: &str, date: NaiveDateTime| match WeekdayLocale::detect(token) {
+            Some(locale) => !locale
+                .abbreviation(date.weekday())
+                .eq_ignore_ascii_case(token),
+            None => false,
+        };
+        wrong(self.start_weekday, self.start)
+            || matches!((self.end_weekday, self.end), (Some(token), Some(end)) if wrong(token, end))
+    }
+
+    /// Whether the minutes of the `=>` total are written without zero
+    /// padding, e.g. `1:3` instead of `1:03`.
+    pub fn has_nonpadded_minutes(&self) -> bool {
+        self.duration_string
+            .and_then(|d| d.split_once(':'))
+            .map(|(_, m)| m.len() < 2)
+            .unwrap_or(false)
+    }
+
+    /// The `=>` total as written, `None` if there is none or it can't be
+    /// parsed. A day prefix like in `2d 3:30` counts 24 hours per day, too
+    /// many days to fit a [`Duration`] can't be parsed.
+    pub fn claimed_duration(&self) -> Option<Duration> {
+        let duration = self.duration_string?;
+        let negative = duration.starts_with('-');
+        let duration = duration.trim_start_matches('-');
+        let (days, duration) = match duration.split_once('d') {
+            Some((days, rest)) => (days.parse::<i64>().ok()?, rest.trim_start()),
+            None => (0, duration),
+        };
+        let (h, m) = duration.split_once(':')?;
+        let minutes = days
+            .checked_mul(24 * 60)?
+            .checked_add(h.parse::<i64>().unwrap_or(0) * 60 + m.parse::<i64>().unwrap_or(0))?;
+        let parsed = Duration::milliseconds(minutes.checked_mul(60_000)?);
+        Some(if negative { -parsed } else { parsed })
+    }
+
+    /// Does the specified duration matche start->end? The total is compared
+    /// by value, so `1:3` matches a clock of one hour and three minutes.
+    /// Timestamps with seconds are compared like org sums them: the total
+    /// has whole minutes, so `[10:00:00]--[10:01:59]` matches `0:01`. Totals
+    /// rounded to the nearest minute instead, here `0:02`, match as well, see
+    /// [`Clock::rounded_totals`]. For
+    /// clocks spanning a DST change both the elapsed and the wall clock time
+    /// match, see [`Clock::spans_dst`].
     pub fn matches_duration(&self) -> bool {
         if self.is_running() {
             return true;
         }
-        let Some(duration_string) = self.duration_string else { return false };
-        let Some((h, m)) = duration_string.split_once(':') else { return false };
-        let negative = h.starts_with('-');
-        let parsed = Duration::hours(i64::abs(h.parse().unwrap_or(0)))
-            + Duration::minutes(m.parse().unwrap_or(0));
-        let parsed = if negative { -parsed } else { parsed };
-        let (start, end) = start_end(self.start, self.end);
-        let actual = end - start;
-        parsed == actual
-    }
-
-    pub fn overlaps<'o>(&self, other: &Clock<'o>) -> bool {
-        let (start, end) = start_end(self.start, self.end);
-        let (other_start, other_end) = start_end(other.start, other.end);
-        if end <= other_start || start >= other_end {
+        let Some(claimed) = self.claimed_duration() else {
+            return false;
+        };
+        let (start, end) = self.utc_interval();
+        let actual = Duration::minutes((end - start).num_minutes());
+        claimed == actual
+            || self
+                .rounded_totals()
+                .is_some_and(|(_, rounded)| claimed == rounded)
+            || (self.spans_dst() && claimed == Duration::minutes(self.duration().num_minutes()))
+    }
+
+    /// For a finished clock whose timestamps have seconds and don't span whole
+    /// minutes: the duration rounded down and rounded to the nearest minute,
+    /// the totals org may have written depending on how it rounded.
+    pub fn rounded_totals(&self) -> Option<(Duration, Duration)> {
+        if self.is_running() {
+            return None;
+        }
+        let (start, end) = self.utc_interval();
+        let seconds = (end - start).num_seconds();
+        if seconds % 60 == 0 {
+            return None;
+        }
+        Some((
+            Duration::minutes(seconds.div_euclid(60)),
+            Duration::minutes((seconds + 30).div_euclid(60)),
+        ))
+    }
+
+    /// Whether the clock crosses a DST change, i.e. the time that passed
+    /// between start and end differs from the wall clock time.
+    pub fn spans_dst(&self) -> bool {
+        if self.is_running() {
             return false;
         }
-        true
+        let (start, end) = self.utc_interval();
+        end - start != self.duration()
+    }
+
+    /// Splits a finished clock into the part before and the part from `t`,
+    /// e.g. after switching tasks without clocking in. Both parts keep the
+    /// line of the clock and get their totals recomputed when written. `None`
+    /// if `t` is not strictly between start and end.
+    pub fn split_at(&self, t: NaiveDateTime) -> Option<(Clock<'a>, Clock<'a>)> {
+        if t <= self.start || self.end? <= t {
+            return None;
+        }
+        let mut first = self.clone();
+        first.end = Some(t);
+        first.end_weekday = Some(self.locale.abbreviation(t.weekday()));
+        first.duration_string = None;
+        let mut second = self.clone();
+        second.start = t;
+        second.start_weekday = self.locale.abbreviation(t.weekday());
+        second.duration_string = None;
+        Some((first, second))
+    }
+
+    /// Whether start and end are the same but the `=>` total claims some
+    /// time, e.g. `[10:00]--[10:00] => 1:00`. One of the timestamps is most
+    /// likely wrong.
+    pub fn has_phantom_duration(&self) -> bool {
+        !self.is_running()
+            && self.duration().is_zero()
+            && self.claimed_duration().is_some_and(|d| !d.is_zero())
+    }
+
+    /// Start and end of a finished clock as UTC instants, see
+    /// [`Clock::overlaps`]. Running clocks end at their start, see
+    /// [`Clock::utc_interval_at`].
+    pub fn utc_interval(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let tz = tz_for_date(self.start.date());
+        let end = self.end.unwrap_or(self.start);
+        (to_utc(self.start, tz), to_utc(end, tz))
+    }
+
+    /// Like [`Clock::utc_interval`] but running clocks end at `now`.
+    pub fn utc_interval_at(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        start_end(self.start, self.end, now)
+    }
+
+    /// Whether the clocks share some time. The comparison is done in UTC, so
+    /// it is correct for clocks recorded in different timezones. Clocks that
+    /// only touch don't overlap. Running clocks end at `now`, the same for
+    /// both clocks so the result doesn't depend on their order.
+    pub fn overlaps<'o>(&self, other: &Clock<'o>, now: DateTime<Utc>) -> bool {
+        let (start, end) = self.utc_interval_at(now);
+        let (other_start, other_end) = other.utc_interval_at(now);
+        !(end <= other_start || start >= other_end)
     }
 }
 
@@ -121,38 +335,66 @@ pub(crate) fn tz_for_date(d: NaiveDate) -> Tz {
     }
 }
 
+/// Resolves a naive timestamp to UTC in `tz`. Ambiguous local times (when
+/// clocks are turned back) resolve to the earlier instant, non-existent ones
+/// (when clocks are turned forward) are interpreted as UTC offset by the zone's
+/// offset one hour later.
+#[inline]
+pub(crate) fn to_utc(d: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    let local = d.and_local_timezone(tz);
+    match local.earliest() {
+        Some(d) => d.with_timezone(&Utc),
+        None => {
+            let later = (d + Duration::hours(1)).and_local_timezone(tz).earliest();
+            let offset = later.map(|later| later.offset().fix().local_minus_utc());
+            let offset = Duration::seconds(offset.unwrap_or(0).into());
+            Utc.from_utc_datetime(&(d - offset))
+        }
+    }
+}
+
+/// The start and end of a clock as UTC instants. Both timestamps are
+/// interpreted in the timezone in effect at the start date, so clocks on
+/// different sides of the timezone cutoff can be compared with each other.
+/// Running clocks end at `now`.
 #[inline]
 pub(crate) fn start_end(
     start: NaiveDateTime,
     end: Option<NaiveDateTime>,
-) -> (DateTime<Tz>, DateTime<Tz>) {
+    now: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
     let tz = tz_for_date(start.date());
-    let start = start.and_local_timezone(tz).unwrap();
-    let end = end
-        .unwrap_or_else(|| Local::now().naive_local())
-        .and_local_timezone(tz)
-        .unwrap();
+    let start = to_utc(start, tz);
+    let end = end.map(|end| to_utc(end, tz)).unwrap_or(now);
     (start, end)
 }
 
+/// Cheap pre-check so that lines which cannot be a clock skip [`CLOCK_RE`].
+#[inline]
+pub(crate) fn maybe_clock(line: &str) -> bool {
+    line.as_bytes()
+        .windows(6)
+        .any(|w| w.eq_ignore_ascii_case(b"clock:"))
+}
+
 pub(crate) static CLOCK_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
         r"(?ix)
 \s*clock:\s*                                      # CLOCK:
 ([\[<])                                           # < or [ timestamp type
 ([0-9]{4})-([0-9]{2})-([0-9]{2})                  # yyyy-mm-dd
-\s+[a-z]+\s+                                      # day of week (can be localized)
-([0-9]{2}):([0-9]{2})                             # HH:MM
+\s+([a-z]+),?\s+                                  # day of week (can be localized), maybe a comma
+([0-9]{2}):([0-9]{2})(?::([0-9]{2}))?             # HH:MM, maybe :SS
 [\]>]                                             # > or ]
 (?:\s*--\s*                                       # parse end timestamp
-[\[<]
+([\[<])                                           # < or [ timestamp type
 ([0-9]{4})-([0-9]{2})-([0-9]{2})                  # yyyy-mm-dd
-\s+[a-z]+\s+                                      # day of week (can be localized)
-([0-9]{2}):([0-9]{2})                             # HH:MM
+\s+([a-z]+),?\s+                                  # day of week (can be localized), maybe a comma
+([0-9]{2}):([0-9]{2})(?::([0-9]{2}))?             # HH:MM, maybe :SS
 [\]>]
 )?
 (?:\s*=>\s*                                       # parse duration
-(-?[0-9]{1,2}:[0-9]{2})
+(-?(?:[0-9]+d\s+)?[0-9]{1,2}:[0-9]{1,2})       # H:MM, maybe with days like 2d 3:30
 )?
 ",
     )
@@ -163,6 +405,9 @@ impl<'a> TryFrom<&'a str> for Clock<'a> {
     type Error = anyhow::Error;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        if !maybe_clock(s) {
+            return Err(anyhow::anyhow!("unable to parse as clock: {s:?}"));
+        }
         if let Some(captures) = CLOCK_RE.captures(s) {
             fn datetime(
                 year: &str,
@@ -170,6 +415,7 @@ impl<'a> TryFrom<&'a str> for Clock<'a> {
                 day: &str,
                 hour: &str,
                 min: &str,
+                sec: Option<&str>,
             ) -> anyhow::Result<NaiveDateTime> {
                 let year = year.parse()?;
                 let month = month.parse()?;
@@ -179,9 +425,10 @@ impl<'a> TryFrom<&'a str> for Clock<'a> {
                     .single()
                     .unwrap();
                 let tz = tz_for_date(local.date_naive());
-                let local = tz.with_ymd_and_hms(year, month, day, hour.parse()?, min.parse()?, 0);
+                let sec = sec.map(str::parse).transpose()?.unwrap_or(0);
+                let local = tz.with_ymd_and_hms(year, month, day, hour.parse()?, min.parse()?, sec);
                 let Some(d) = local.earliest().or_else(|| local.latest()) else {
-                    return Err(anyhow::anyhow!("unable create date"))
+                    return Err(anyhow::anyhow!("unable create date"));
                 };
                 Ok(d.naive_local())
             }
@@ -197,12 +444,18 @@ impl<'a> TryFrom<&'a str> for Clock<'a> {
                 .unwrap()
                 .into();
 
+            let end_timestamp_type = captures
+                .get(9)
+                .and_then(|c| c.as_str().chars().next())
+                .map(TimestampType::from);
+
             let start = datetime(
                 captures.get(2).unwrap().as_str(),
                 captures.get(3).unwrap().as_str(),
                 captures.get(4).unwrap().as_str(),
-                captures.get(5).unwrap().as_str(),
                 captures.get(6).unwrap().as_str(),
+                captures.get(7).unwrap().as_str(),
+                captures.get(8).map(|c| c.as_str()),
             )
             .map_err(|err| {
                 error!("error parsing start: {full:?}");
@@ -216,23 +469,29 @@ impl<'a> TryFrom<&'a str> for Clock<'a> {
                 Some(end_hour),
                 Some(end_min),
             ) = (
-                captures.get(7).map(|c| c.as_str()),
-                captures.get(8).map(|c| c.as_str()),
-                captures.get(9).map(|c| c.as_str()),
                 captures.get(10).map(|c| c.as_str()),
                 captures.get(11).map(|c| c.as_str()),
+                captures.get(12).map(|c| c.as_str()),
+                captures.get(14).map(|c| c.as_str()),
+                captures.get(15).map(|c| c.as_str()),
             ) {
+                let end_sec = captures.get(16).map(|c| c.as_str());
                 Some(
-                    datetime(end_year, end_month, end_day, end_hour, end_min).map_err(|err| {
-                        error!("error parsing end: {full:?}");
-                        anyhow::anyhow!("error parsing end: {err}")
-                    })?,
+                    datetime(end_year, end_month, end_day, end_hour, end_min, end_sec).map_err(
+                        |err| {
+                            error!("error parsing end: {full:?}");
+                            anyhow::anyhow!("error parsing end: {err}")
+                        },
+                    )?,
                 )
             } else {
                 None
             };
 
-            let duration_string = captures.get(12).map(|c| c.as_str());
+            let duration_string = captures.get(17).map(|c| c.as_str());
+            let start_weekday = captures.get(5).unwrap().as_str();
+            let end_weekday = captures.get(13).map(|c| c.as_str());
+            let locale = WeekdayLocale::detect(start_weekday).unwrap_or_default();
 
             Ok(Clock {
                 parent: 0,
@@ -241,6 +500,13 @@ impl<'a> TryFrom<&'a str> for Clock<'a> {
                 end,
                 duration_string,
                 timestamp_type,
+                end_timestamp_type,
+                start_weekday,
+                end_weekday,
+                locale,
+                indent: &s[..s.len() - s.trim_start().len()],
+                drawer: None,
+                list_depth: 0,
             })
         } else {
             Err(anyhow::anyhow!("unable to parse as clock: {s:?}"))
@@ -248,11 +514,107 @@ impl<'a> TryFrom<&'a str> for Clock<'a> {
     }
 }
 
+/// Parses `H:MM`, plain minutes (`90`) or a number with an `m` or `h` suffix
+/// (`90m`, `1.5h`).
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if let Some((h, m)) = s.split_once(':') {
+        return Ok(
+            Duration::hours(h.parse().map_err(|_| "cannot parse hours".to_string())?)
+                + Duration::minutes(m.parse().map_err(|_| "cannot parse minutes".to_string())?),
+        );
+    }
+    let (number, minutes_per_unit) = if let Some(hours) = s.strip_suffix('h') {
+        (hours, 60.0)
+    } else {
+        (s.strip_suffix('m').unwrap_or(s), 1.0)
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => {
+            Ok(Duration::minutes((n * minutes_per_unit).round() as i64))
+        }
+        _ => Err(format!(
+            "cannot parse duration {s:?}, expected H:MM, minutes or e.g. 1.5h"
+        )),
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod clock_tests {
-    use chrono::NaiveDateTime;
+    use chrono::{Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+    use proptest::prelude::*;
 
-    use super::Clock;
+    use super::{Clock, TimestampType, WeekdayLocale};
+
+    /// Clocks starting within two years around the timezone cutoff and the
+    /// DST changes of 2019, lasting from -10 to 48 hours or running.
+    fn arb_clock() -> impl Strategy<Value = String> {
+        (0..2 * 365 * 24 * 4i64, prop::option::of(-40..48 * 4i64)).prop_map(|(start, length)| {
+            let base = NaiveDate::from_ymd_opt(2018, 10, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            let start = base + Duration::minutes(start * 15);
+            let format = |d: NaiveDateTime| d.format("[%Y-%m-%d %a %H:%M]").to_string();
+            match length {
+                Some(length) => format!(
+                    "CLOCK: {}--{}",
+                    format(start),
+                    format(start + Duration::minutes(length * 15))
+                ),
+                None => format!("CLOCK: {}", format(start)),
+            }
+        })
+    }
+
+    #[test]
+    fn split_at() {
+        let clock =
+            Clock::try_from("CLOCK: [2022-12-12 Mon 22:00]--[2022-12-13 Tue 01:30] =>  3:30")
+                .unwrap();
+        let at = |d, h, m| {
+            NaiveDate::from_ymd_opt(2022, 12, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        let (first, second) = clock.split_at(at(13, 0, 15)).unwrap();
+        assert_eq!(
+            first.to_string(),
+            "[2022-12-12 Mon 22:00]--[2022-12-13 Tue 00:15] =>  2:15"
+        );
+        assert_eq!(
+            second.to_string(),
+            "[2022-12-13 Tue 00:15]--[2022-12-13 Tue 01:30] =>  1:15"
+        );
+        assert!(!first.has_wrong_weekday() && !second.has_wrong_weekday());
+
+        assert!(clock.split_at(at(12, 22, 0)).is_none());
+        assert!(clock.split_at(at(13, 1, 30)).is_none());
+        assert!(clock.split_at(at(12, 21, 0)).is_none());
+        let running = Clock::try_from("CLOCK: [2022-12-12 Mon 22:00]").unwrap();
+        assert!(running.split_at(at(12, 23, 0)).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn overlaps_is_symmetric(a in arb_clock(), b in arb_clock(), now in 0..3 * 365 * 24i64) {
+            // times in the gap of a DST change don't exist and don't parse
+            let (Ok(a), Ok(b)) = (Clock::try_from(a.as_str()), Clock::try_from(b.as_str())) else {
+                return Ok(());
+            };
+            let now = Utc.with_ymd_and_hms(2018, 10, 1, 0, 0, 0).unwrap() + Duration::hours(now);
+            prop_assert_eq!(a.overlaps(&b, now), b.overlaps(&a, now));
+
+            // reference for clocks with time: one starts within the other
+            let (s1, e1) = a.utc_interval_at(now);
+            let (s2, e2) = b.utc_interval_at(now);
+            if s1 < e1 && s2 < e2 {
+                let expected = (s1 <= s2 && s2 < e1) || (s2 <= s1 && s1 < e2);
+                prop_assert_eq!(a.overlaps(&b, now), expected);
+            }
+        }
+    }
 
     #[test]
     fn test_parse_clock() {
@@ -280,6 +642,76 @@ pub(crate) mod clock_tests {
         assert_eq!(result[2].start, expected_start);
     }
 
+    #[test]
+    fn test_maybe_clock() {
+        assert!(super::maybe_clock("CLOCK: [2021-04-18 Sun 00:57]"));
+        assert!(super::maybe_clock("  clock:[2021-04-18 Sun 00:57]"));
+        assert!(!super::maybe_clock("* clocking in"));
+        assert!(!super::maybe_clock(""));
+    }
+
+    #[test]
+    fn test_nonpadded_minutes() {
+        let clock =
+            Clock::try_from("CLOCK: [2021-04-18 Sun 00:57]--[2021-04-18 Sun 02:00] =>  1:3")
+                .expect("parse clock");
+        assert_eq!(clock.duration_string, Some("1:3"));
+        assert!(clock.matches_duration());
+        assert!(clock.has_nonpadded_minutes());
+
+        let clock =
+            Clock::try_from("CLOCK: [2021-04-18 Sun 00:57]--[2021-04-18 Sun 02:00] =>  1:03")
+                .expect("parse clock");
+        assert!(clock.matches_duration());
+        assert!(!clock.has_nonpadded_minutes());
+    }
+
+    #[test]
+    fn test_mixed_timestamp_types() {
+        let clock = Clock::try_from("CLOCK: [2021-04-18 Sun 00:57]--<2021-04-18 Sun 02:30>")
+            .expect("parse clock");
+        assert_eq!(clock.timestamp_type, TimestampType::Inactive);
+        assert_eq!(clock.end_timestamp_type, Some(TimestampType::Active));
+        assert!(clock.has_mixed_timestamp_types());
+
+        let clock = Clock::try_from("CLOCK: <2021-04-18 Sun 00:57>--<2021-04-18 Sun 02:30>")
+            .expect("parse clock");
+        assert!(!clock.has_mixed_timestamp_types());
+        let clock = Clock::try_from("CLOCK: <2021-04-18 Sun 00:57>").expect("parse clock");
+        assert!(!clock.has_mixed_timestamp_types());
+    }
+
+    #[test]
+    fn test_weekdays() {
+        let clock =
+            Clock::try_from("CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30")
+                .unwrap();
+        assert!(!clock.has_wrong_weekday());
+        let clock =
+            Clock::try_from("CLOCK: [2022-12-12 Sun 10:00]--[2022-12-12 Mon 10:30] =>  0:30")
+                .unwrap();
+        assert!(clock.has_wrong_weekday());
+
+        let mut clock =
+            Clock::try_from("CLOCK: [2022-12-12 So 10:00]--[2022-12-13 Di 10:30] => 24:30")
+                .unwrap();
+        assert_eq!(clock.locale, WeekdayLocale::De);
+        assert!(clock.has_wrong_weekday());
+        assert_eq!(
+            clock.to_string(),
+            "[2022-12-12 Mo 10:00]--[2022-12-13 Di 10:30] => 24:30"
+        );
+        clock.locale = WeekdayLocale::En;
+        assert_eq!(
+            clock.to_string(),
+            "[2022-12-12 Mon 10:00]--[2022-12-13 Tue 10:30] => 24:30"
+        );
+
+        // unknown localizations are not checked
+        let clock = Clock::try_from("CLOCK: [2022-12-12 lun 10:00]").unwrap();
+        assert!(!clock.has_wrong_weekday());
+    }
+
     #[test]
     fn test_parse_negative() {
         let clock =
@@ -288,8 +720,39 @@ pub(crate) mod clock_tests {
         assert!(clock.matches_duration());
     }
 
+    #[test]
+    fn duration_with_days() {
+        let clock =
+            Clock::try_from("CLOCK: [2023-01-09 Mon 09:00]--[2023-01-11 Wed 12:30] =>  2d 3:30")
+                .unwrap();
+        assert_eq!(clock.duration_string, Some("2d 3:30"));
+        assert_eq!(
+            clock.claimed_duration(),
+            Some(Duration::minutes(51 * 60 + 30))
+        );
+        assert!(clock.matches_duration());
+        // written back without days
+        assert_eq!(
+            clock.to_string(),
+            "[2023-01-09 Mon 09:00]--[2023-01-11 Wed 12:30] => 51:30"
+        );
+
+        let wrong =
+            Clock::try_from("CLOCK: [2023-01-09 Mon 09:00]--[2023-01-11 Wed 12:30] =>  1d 3:30")
+                .unwrap();
+        assert!(!wrong.matches_duration());
+
+        let huge = Clock::try_from(
+            "CLOCK: [2023-01-09 Mon 09:00]--[2023-01-11 Wed 12:30] =>  999999999999999d 1:00",
+        )
+        .unwrap();
+        assert_eq!(huge.claimed_duration(), None);
+        assert!(!huge.matches_duration());
+    }
+
     #[test]
     fn test_overlaps() {
+        let now = Utc::now();
         let clock1 =
             Clock::try_from("CLOCK: [2021-04-18 Sun 00:57]--[2021-04-18 Sun 01:47]").unwrap();
         let clock2 =
@@ -298,10 +761,123 @@ pub(crate) mod clock_tests {
             Clock::try_from("CLOCK: [2021-04-18 Sun 01:46]--[2021-04-18 Sun 01:48]").unwrap();
         let clock4 =
             Clock::try_from("CLOCK: [2021-04-18 Sun 01:47]--[2021-04-18 Sun 01:48]").unwrap();
-        assert!(clock1.overlaps(&clock1));
-        assert!(clock1.overlaps(&clock2));
-        assert!(clock2.overlaps(&clock1));
-        assert!(clock3.overlaps(&clock1));
-        assert!(!clock4.overlaps(&clock1));
+        assert!(clock1.overlaps(&clock1, now));
+        assert!(clock1.overlaps(&clock2, now));
+        assert!(clock2.overlaps(&clock1, now));
+        assert!(clock3.overlaps(&clock1, now));
+        assert!(!clock4.overlaps(&clock1, now));
+
+        // running clocks end at `now`
+        let running = Clock::try_from("CLOCK: [2021-04-18 Sun 01:00]").unwrap();
+        let before = Utc.with_ymd_and_hms(2021, 4, 17, 23, 10, 0).unwrap();
+        assert!(!running.overlaps(&clock2, before));
+        assert!(running.overlaps(&clock2, before + Duration::minutes(15)));
+    }
+
+    #[test]
+    fn test_overlaps_across_timezone_cutoff() {
+        let now = Utc::now();
+        // US/Pacific before the cutoff, 22:00-23:30 PDT is 05:00-06:30 UTC
+        let pacific =
+            Clock::try_from("CLOCK: [2019-04-30 Tue 22:00]--[2019-04-30 Tue 23:30]").unwrap();
+        // Europe/Berlin after the cutoff, 07:30-08:00 CEST is 05:30-06:00 UTC
+        let berlin =
+            Clock::try_from("CLOCK: [2019-05-01 Wed 07:30]--[2019-05-01 Wed 08:00]").unwrap();
+        assert!(pacific.overlaps(&berlin, now));
+        assert!(berlin.overlaps(&pacific, now));
+
+        // naively overlapping, but 06:00-09:00 UTC vs. 23:00-23:30 UTC
+        let pacific =
+            Clock::try_from("CLOCK: [2019-04-30 Tue 23:00]--[2019-05-01 Wed 02:00]").unwrap();
+        let berlin =
+            Clock::try_from("CLOCK: [2019-05-01 Wed 01:00]--[2019-05-01 Wed 01:30]").unwrap();
+        assert!(!pacific.overlaps(&berlin, now));
+        assert!(!berlin.overlaps(&pacific, now));
+    }
+
+    #[test]
+    fn test_ambiguous_local_time() {
+        // 02:30 happens twice in Berlin on that day
+        let clock =
+            Clock::try_from("CLOCK: [2022-10-30 Sun 02:30]--[2022-10-30 Sun 03:30] =>  1:00")
+                .unwrap();
+        let (start, end) = clock.utc_interval();
+        assert_eq!((end - start).num_minutes(), 120);
+    }
+
+    #[test]
+    fn parse_comma_after_weekday() {
+        let canonical =
+            Clock::try_from("CLOCK: [2021-04-18 Sun 00:57]--[2021-04-18 Sun 01:30] =>  0:33")
+                .unwrap();
+        let comma =
+            Clock::try_from("CLOCK: [2021-04-18 Sun, 00:57]--[2021-04-18 Sun,  01:30] =>  0:33")
+                .unwrap();
+        assert_eq!(comma.start, canonical.start);
+        assert_eq!(comma.end, canonical.end);
+        assert_eq!(comma.to_string(), canonical.to_string());
+    }
+
+    #[test]
+    fn dst_spanning() {
+        // clocks went forward from 02:00 to 03:00 CET, 3:00 wall clock time
+        // but only 2:00 passed
+        let wall =
+            Clock::try_from("CLOCK: [2021-03-28 Sun 01:00]--[2021-03-28 Sun 04:00] =>  3:00")
+                .unwrap();
+        assert!(wall.spans_dst());
+        assert!(wall.matches_duration());
+        let elapsed =
+            Clock::try_from("CLOCK: [2021-03-28 Sun 01:00]--[2021-03-28 Sun 04:00] =>  2:00")
+                .unwrap();
+        assert!(elapsed.matches_duration());
+        let wrong =
+            Clock::try_from("CLOCK: [2021-03-28 Sun 01:00]--[2021-03-28 Sun 04:00] =>  2:30")
+                .unwrap();
+        assert!(!wrong.matches_duration());
+
+        let normal =
+            Clock::try_from("CLOCK: [2021-03-27 Sat 01:00]--[2021-03-27 Sat 04:00] =>  3:00")
+                .unwrap();
+        assert!(!normal.spans_dst());
+    }
+
+    #[test]
+    fn parse_seconds() {
+        let clock =
+            Clock::try_from("CLOCK: [2021-04-18 Sun 00:57:30]--[2021-04-18 Sun 01:30:10] =>  0:32")
+                .unwrap();
+        assert_eq!(
+            clock.start,
+            NaiveDateTime::parse_from_str("2021-04-18 00:57:30", "%Y-%m-%d %H:%M:%S").unwrap()
+        );
+        assert_eq!(clock.duration(), Duration::seconds(32 * 60 + 40));
+        assert!(clock.matches_duration());
+        assert_eq!(
+            clock.to_string(),
+            "[2021-04-18 Sun 00:57:30]--[2021-04-18 Sun 01:30:10] =>  0:32"
+        );
+
+        for (total, matches) in [
+            ("0:32", true),
+            ("0:33", true),
+            ("0:31", false),
+            ("0:34", false),
+        ] {
+            let line =
+                format!("CLOCK: [2021-04-18 Sun 00:57:30]--[2021-04-18 Sun 01:30:10] =>  {total}");
+            let clock = Clock::try_from(line.as_str()).unwrap();
+            assert_eq!(clock.matches_duration(), matches, "{total}");
+        }
+    }
+
+    #[test]
+    fn parse_duration_formats() {
+        for input in ["90", "90m", "1.5h", "1:30"] {
+            assert_eq!(super::parse_duration(input), Ok(Duration::minutes(90)));
+        }
+        assert!(super::parse_duration("abc").is_err());
+        assert!(super::parse_duration("1:xx").is_err());
+        assert!(super::parse_duration("-5m").is_err());
     }
 }
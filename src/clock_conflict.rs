@@ -1,17 +1,31 @@
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::{hash_map::DefaultHasher, HashSet},
+    collections::{hash_map::DefaultHasher, BTreeSet, HashSet},
     hash::{Hash, Hasher},
     path::PathBuf,
 };
 
-use crate::{Clock, Headline, OrgDocument};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::CLOCK_RE, Clock, Finding, FindingKind, Headline, OrgDocument, OrgFile, ParseOptions,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum ConflictResolution {
     ShrinkEarlier,
     ShrinkLater,
+    /// Moves the end of the earlier and the start of the later clock to the
+    /// same point in the overlap, so that the earlier clock keeps
+    /// `earlier_fraction` of it.
+    SplitOverlap {
+        earlier_fraction: f64,
+    },
+    /// Moves the later clock to start where the earlier one ends, keeping its
+    /// duration. This can make it overlap the clocks after it.
+    ShiftLater,
     SplitContaining,
     RemoveInner,
     Auto,
@@ -23,6 +37,8 @@ impl ConflictResolution {
         match self {
             Self::ShrinkEarlier => "Shrink earlier timestamp",
             Self::ShrinkLater => "Shrink later timestamp",
+            Self::SplitOverlap { .. } => "Split the overlap between both timestamps",
+            Self::ShiftLater => "Shift later timestamp after the earlier one",
             Self::SplitContaining => "Split the outer timestamp",
             Self::RemoveInner => "Remove the inner timestamp",
             Self::Auto => "Merge timestamps",
@@ -80,24 +96,25 @@ impl<'a> std::hash::Hash for ClockConflict<'a> {
 }
 
 impl<'a> ClockConflict<'a> {
-    pub fn find_conflicts(
-        org_docs: &'a [OrgDocument<'a>],
-    ) -> impl Iterator<Item = ClockConflict<'a>> + 'a {
-        let mut clocks = Vec::new();
-
-        for doc in org_docs {
-            for clock in &doc.clocks {
-                let headline = &doc.headlines[clock.parent];
-                clocks.push((&doc.file, headline, clock));
-            }
-        }
+    /// The conflicts between the clocks of `org_docs`, running clocks end
+    /// now.
+    pub fn find_conflicts(org_docs: &'a [OrgDocument<'a>]) -> ClockConflictIterator<'a> {
+        Self::find_conflicts_excluding(org_docs, &[], Utc::now())
+    }
 
-        ClockConflictIterator {
-            data: clocks,
-            last_i: 0,
-            last_j: 0,
-            seen: Default::default(),
-        }
+    /// Like [`ClockConflict::find_conflicts`] but ignores clocks of headlines
+    /// that have or inherit one of `exclude_tags` and running clocks end at
+    /// `now`.
+    pub fn find_conflicts_excluding(
+        org_docs: &'a [OrgDocument<'a>],
+        exclude_tags: &[String],
+        now: DateTime<Utc>,
+    ) -> ClockConflictIterator<'a> {
+        let state = ConflictSearchState {
+            now: now.timestamp(),
+            ..Default::default()
+        };
+        ClockConflictIterator::from_state(org_docs, exclude_tags, state)
     }
 
     pub fn report(&self) -> String {
@@ -120,8 +137,35 @@ impl<'a> ClockConflict<'a> {
         )
     }
 
+    /// The conflict as a finding located at the first clock.
+    pub fn finding(&self) -> Finding {
+        let Self {
+            clock1,
+            clock2,
+            headline1,
+            headline2,
+            file1,
+            file2,
+        } = self;
+        let title1 = headline1.title;
+        let title2 = headline2.title;
+        Finding::new(
+            FindingKind::ClockConflict,
+            *file1,
+            clock1.line,
+            format!("OVERLAPPING TIME {clock1} {title1:?} with {clock2} {title2:?}"),
+        )
+        .with_related(*file2, clock2.line)
+    }
+
+    /// The two overlapping clocks.
+    pub fn clocks(&self) -> (&'a Clock<'a>, &'a Clock<'a>) {
+        (self.clock1, self.clock2)
+    }
+
+    /// Identifies the conflict, the same across runs and builds.
     pub fn hashme(&self) -> u64 {
-        let mut hasher = DefaultHasher::new();
+        let mut hasher = Fnv1aHasher::default();
         self.hash(&mut hasher);
         hasher.finish()
     }
@@ -150,15 +194,67 @@ impl<'a> ClockConflict<'a> {
 
         // intervals are not contained
         if earlier.end < later.end {
-            return vec![ShrinkEarlier, ShrinkLater, Skip];
+            return vec![
+                ShrinkEarlier,
+                ShrinkLater,
+                SplitOverlap {
+                    earlier_fraction: 0.5,
+                },
+                ShiftLater,
+                Skip,
+            ];
         }
 
         vec![RemoveInner, SplitContaining, Skip]
     }
 
-    pub fn resolve(self, resolution: ConflictResolution) -> Vec<FileChange<'a>> {
+    /// Whether one of the clocks has no end timestamp. Such conflicts can only
+    /// be resolved manually.
+    pub fn involves_running_clock(&self) -> bool {
+        self.clock1.is_running() || self.clock2.is_running()
+    }
+
+    /// The resolution picked when fixing conflicts without asking: clocks of
+    /// the same headline are merged, partially overlapping clocks shrink the
+    /// earlier one, duplicates are removed and contained clocks split the
+    /// outer one.
+    pub fn auto_resolution(&self) -> ConflictResolution {
+        use ConflictResolution::*;
+        let options = self.resolution_options();
+        if options.iter().any(|r| matches!(r, Auto)) {
+            return Auto;
+        }
+        if options.iter().any(|r| matches!(r, ShrinkEarlier)) {
+            return ShrinkEarlier;
+        }
+        if self.clock1.start == self.clock2.start && self.clock1.end == self.clock2.end {
+            return RemoveInner;
+        }
+        SplitContaining
+    }
+
+    /// The changes that apply `resolution`. Errors if the resolution doesn't
+    /// fit the conflict, e.g. [`ConflictResolution::SplitContaining`] for
+    /// clocks that only partially overlap. [`ClockConflict::resolution_options`]
+    /// only offers fitting resolutions. Also errors with [`WouldCreateNegative`]
+    /// instead of returning a change that writes a negative clock.
+    pub fn resolve(self, resolution: ConflictResolution) -> anyhow::Result<Vec<FileChange<'a>>> {
+        let changes = self.resolve_unchecked(resolution)?;
+        if let Some(finding) = changes
+            .iter()
+            .find_map(|change| change.negative_clock(resolution.explanation()))
+        {
+            return Err(WouldCreateNegative(finding).into());
+        }
+        Ok(changes)
+    }
+
+    fn resolve_unchecked(
+        self,
+        resolution: ConflictResolution,
+    ) -> anyhow::Result<Vec<FileChange<'a>>> {
         if matches!(resolution, ConflictResolution::Skip) {
-            return Default::default();
+            return Ok(Default::default());
         }
 
         let Self {
@@ -180,10 +276,10 @@ impl<'a> ClockConflict<'a> {
             };
             keep_clock.start = keep_clock.start.min(delete_clock.start);
             keep_clock.end = keep_clock.end.max(delete_clock.end);
-            return vec![
+            return Ok(vec![
                 FileChange::update(file1, keep_clock),
                 FileChange::delete(file2, delete_clock),
-            ];
+            ]);
         }
 
         let (mut earlier, earlier_file, mut later, later_file) = if clock1.start <= clock2.start {
@@ -192,18 +288,41 @@ impl<'a> ClockConflict<'a> {
             (clock2, file2, clock1, file1)
         };
 
+        let invalid =
+            || anyhow::anyhow!("resolution {resolution:?} does not apply to the conflict");
+        let running = || anyhow::anyhow!("{resolution:?} needs the end of a running clock");
+
         // intervals are not contained
         if earlier.end < later.end {
             return match resolution {
                 ConflictResolution::ShrinkEarlier => {
                     earlier.end = Some(later.start);
-                    vec![FileChange::update(earlier_file, earlier)]
+                    Ok(vec![FileChange::update(earlier_file, earlier)])
                 }
                 ConflictResolution::ShrinkLater => {
-                    later.start = earlier.end.unwrap();
-                    vec![FileChange::update(later_file, later)]
+                    later.start = earlier.end.ok_or_else(running)?;
+                    Ok(vec![FileChange::update(later_file, later)])
+                }
+                ConflictResolution::SplitOverlap { earlier_fraction } => {
+                    let overlap =
+                        (earlier.end.ok_or_else(running)? - later.start).num_minutes() as f64;
+                    let earlier_share = (overlap * earlier_fraction.clamp(0.0, 1.0)).round();
+                    let boundary = later.start + Duration::minutes(earlier_share as i64);
+                    earlier.end = Some(boundary);
+                    later.start = boundary;
+                    Ok(vec![
+                        FileChange::update(earlier_file, earlier),
+                        FileChange::update(later_file, later),
+                    ])
+                }
+                ConflictResolution::ShiftLater => {
+                    let duration = later.duration();
+                    later.start = earlier.end.ok_or_else(running)?;
+                    later.end = Some(later.start + duration);
+                    later.end_weekday = None;
+                    Ok(vec![FileChange::update(later_file, later)])
                 }
-                _ => panic!("invalid resolution {resolution:?}"),
+                _ => Err(invalid()),
             };
         }
 
@@ -212,24 +331,30 @@ impl<'a> ClockConflict<'a> {
             ConflictResolution::SplitContaining => {
                 let mut third = earlier.clone();
                 // third.line += 1;
-                third.start = later.end.unwrap();
+                third.start = later.end.ok_or_else(running)?;
                 earlier.end = Some(later.start);
-                vec![
+                Ok(vec![
                     FileChange::update(earlier_file, earlier),
                     FileChange::add(earlier_file, third),
-                ]
+                ])
             }
-            ConflictResolution::RemoveInner => {
-                vec![FileChange::delete(later_file, later)]
-            }
-            _ => panic!("invalid resolution {resolution:?}"),
+            ConflictResolution::RemoveInner => Ok(vec![FileChange::delete(later_file, later)]),
+            _ => Err(invalid()),
         }
     }
 }
 
 // -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 
-#[derive(Debug)]
+/// Whether `line` is a clock line, i.e. starts with `CLOCK:` and parses.
+fn is_clock_line(line: &str) -> bool {
+    line.trim_start()
+        .get(..6)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("clock:"))
+        && CLOCK_RE.is_match(line)
+}
+
+#[derive(Debug, Clone)]
 pub enum FileChange<'a> {
     DeletedClock { file: PathBuf, clock: Clock<'a> },
     AddedClock { file: PathBuf, clock: Clock<'a> },
@@ -237,21 +362,24 @@ pub enum FileChange<'a> {
 }
 
 impl<'a> FileChange<'a> {
-    fn add(file: impl Into<PathBuf>, clock: Clock<'a>) -> Self {
+    /// Inserts `clock` at `clock.line`, moving the line currently there down.
+    pub fn add(file: impl Into<PathBuf>, clock: Clock<'a>) -> Self {
         Self::AddedClock {
             file: file.into(),
             clock,
         }
     }
 
-    fn delete(file: impl Into<PathBuf>, clock: Clock<'a>) -> Self {
+    /// Removes the clock line at `clock.line`.
+    pub fn delete(file: impl Into<PathBuf>, clock: Clock<'a>) -> Self {
         Self::DeletedClock {
             file: file.into(),
             clock,
         }
     }
 
-    fn update(file: impl Into<PathBuf>, clock: Clock<'a>) -> Self {
+    /// Replaces the clock line at `clock.line` with `clock`.
+    pub fn update(file: impl Into<PathBuf>, clock: Clock<'a>) -> Self {
         Self::UpdateClock {
             file: file.into(),
             clock,
@@ -268,7 +396,7 @@ impl<'a> FileChange<'a> {
     }
 
     #[inline]
-    fn line(&self) -> usize {
+    pub(crate) fn line(&self) -> usize {
         self.clock().line
     }
 
@@ -281,6 +409,55 @@ impl<'a> FileChange<'a> {
         }
     }
 
+    /// A [`FindingKind::WouldCreateNegative`] finding if this change writes a
+    /// clock that ends before it starts. `by` names what proposed the change.
+    pub fn negative_clock(&self, by: &str) -> Option<Finding> {
+        let clock = match self {
+            FileChange::DeletedClock { .. } => return None,
+            FileChange::AddedClock { clock, .. } | FileChange::UpdateClock { clock, .. } => clock,
+        };
+        if clock.end? >= clock.start {
+            return None;
+        }
+        Some(Finding::new(
+            FindingKind::WouldCreateNegative,
+            self.file(),
+            clock.line,
+            format!("WOULD CREATE NEGATIVE CLOCK {clock} by {by}, not applied"),
+        ))
+    }
+
+    /// Clock conflicts the clock written by this change would have with the
+    /// clocks of `docs` other than the one it replaces. Running clocks end at
+    /// `now`.
+    pub fn new_conflicts(&self, docs: &[OrgDocument], now: DateTime<Utc>) -> Vec<Finding> {
+        let clock = match self {
+            FileChange::DeletedClock { .. } => return Vec::new(),
+            FileChange::AddedClock { clock, .. } | FileChange::UpdateClock { clock, .. } => clock,
+        };
+        let replaced = |doc: &OrgDocument, other: &Clock| {
+            matches!(self, FileChange::UpdateClock { .. })
+                && &doc.file == self.file()
+                && other.line == clock.line
+        };
+        docs.iter()
+            .flat_map(|doc| doc.clocks.iter().map(move |other| (doc, other)))
+            .filter(|(doc, other)| !replaced(doc, other) && clock.overlaps(other, now))
+            .map(|(doc, other)| {
+                Finding::new(
+                    FindingKind::ClockConflict,
+                    self.file(),
+                    clock.line,
+                    format!(
+                        "OVERLAPPING TIME {clock} with {other} {:?}",
+                        doc.headlines[other.parent].title
+                    ),
+                )
+                .with_related(&doc.file, other.line)
+            })
+            .collect()
+    }
+
     pub fn fixup_headline<'b>(&self, headline: &mut Headline<'b>) {
         if headline.line < self.line() {
             return;
@@ -316,8 +493,33 @@ impl<'a> FileChange<'a> {
         }
     }
 
-    fn modify_file_content(&self, content: Cow<str>) -> String {
+    /// Errors instead of touching the content if a delete or update doesn't
+    /// target a clock line or an add is past the end of the content, e.g.
+    /// because line numbers are out of date.
+    fn modify_file_content(&self, content: Cow<str>) -> anyhow::Result<String> {
         let target_line = self.line() - 1;
+        let line_count = content.lines().count();
+        match self {
+            FileChange::AddedClock { .. } if target_line > line_count => {
+                return Err(anyhow::anyhow!(
+                    "cannot add clock at line {}, {:?} has only {line_count} lines",
+                    self.line(),
+                    self.file()
+                ));
+            }
+            FileChange::AddedClock { .. } => {}
+            _ => {
+                let line = content.lines().nth(target_line).unwrap_or("");
+                if !is_clock_line(line) {
+                    return Err(anyhow::anyhow!(
+                        "refusing to change line {} of {:?}, it is not a clock: {line:?}",
+                        self.line(),
+                        self.file()
+                    ));
+                }
+            }
+        }
+
         let mut result = String::new();
         for (line_no, line) in content.lines().enumerate() {
             if line_no == target_line {
@@ -326,11 +528,14 @@ impl<'a> FileChange<'a> {
                         continue;
                     }
                     FileChange::UpdateClock { clock, .. } => {
+                        // keep the indentation of the replaced clock
+                        result.push_str(&line[..line.len() - line.trim_start().len()]);
                         result.push_str("CLOCK: ");
                         result.push_str(&format!("{clock}\n"));
                         continue;
                     }
                     FileChange::AddedClock { clock, .. } => {
+                        result.push_str(clock.indent);
                         result.push_str("CLOCK: ");
                         result.push_str(&format!("{clock}\n"));
                     }
@@ -339,10 +544,20 @@ impl<'a> FileChange<'a> {
             result.push_str(line);
             result.push('\n');
         }
-        result
+        if let FileChange::AddedClock { clock, .. } = self {
+            if target_line == line_count {
+                result.push_str(clock.indent);
+                result.push_str("CLOCK: ");
+                result.push_str(&format!("{clock}\n"));
+            }
+        }
+        Ok(result)
     }
 
-    pub fn apply_to_string(mut changes: Vec<Self>, file_content: &str) -> anyhow::Result<Cow<str>> {
+    pub fn apply_to_string(
+        mut changes: Vec<Self>,
+        file_content: &str,
+    ) -> anyhow::Result<Cow<'_, str>> {
         if changes.is_empty() {
             return Ok(Cow::Borrowed(file_content));
         }
@@ -376,31 +591,452 @@ impl<'a> FileChange<'a> {
 
         let mut result = file_content.to_string();
         for c in changes {
-            result = c.modify_file_content(result.into());
+            result = c.modify_file_content(result.into())?;
         }
 
         Ok(Cow::Owned(result))
     }
 
-    pub fn apply(changes: Vec<Self>) -> anyhow::Result<()> {
+    /// Applies just this change to `file_content`.
+    ///
+    /// ```
+    /// use org_linter::{Clock, FileChange};
+    ///
+    /// let content = "* task\nCLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30\n";
+    /// let mut clock =
+    ///     Clock::try_from("CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00]").unwrap();
+    /// clock.line = 2;
+    /// let change = FileChange::update("task.org", clock);
+    /// assert_eq!(
+    ///     change.apply_to_string_single(content).unwrap(),
+    ///     "* task\nCLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00\n"
+    /// );
+    /// ```
+    pub fn apply_to_string_single(self, file_content: &str) -> anyhow::Result<String> {
+        Ok(Self::apply_to_string(vec![self], file_content)?.into_owned())
+    }
+
+    /// Applies `changes` to their file on disk. The line numbers of the
+    /// changes refer to the content they were computed from, so this errors
+    /// without writing if the file no longer has `expected_hash` (see
+    /// [`OrgDocument::content_hash`]), e.g. because it was edited in the
+    /// meantime. Written with [`OrgFile::save`].
+    pub fn apply(changes: Vec<Self>, expected_hash: u64) -> anyhow::Result<()> {
         if changes.is_empty() {
             return Ok(());
         }
         let file = changes[0].file().clone();
 
-        let content = std::fs::read_to_string(&file)?;
-        let result = Self::apply_to_string(changes, &content)?;
-        std::fs::write(&file, &*result)?;
+        let mut org_file = OrgFile::from_file(&file)?;
+        if org_file.disk_hash != Some(expected_hash) {
+            return Err(anyhow::anyhow!(
+                "{file:?} changed on disk since it was parsed, not applying changes"
+            ));
+        }
+        let result = Self::apply_to_string(changes, org_file.content())?.into_owned();
+        org_file.save_content(result)
+    }
+}
+
+/// Resolves conflicts between the clocks of `org_files` with
+/// [`ClockConflict::auto_resolution`] until no conflicts are left, modifying
+/// the file contents in place. Each round resolves one conflict and re-parses
+/// the files. Errors if there are still conflicts after `max_rounds` rounds.
+/// Conflicts involving a running clock, which ends at `now`, are left alone.
+/// Returns the number of resolved conflicts.
+pub fn resolve_conflicts_automatically(
+    org_files: &mut [OrgFile],
+    max_rounds: usize,
+    exclude_tags: &[String],
+    now: DateTime<Utc>,
+) -> anyhow::Result<usize> {
+    let mut rounds = 0;
+    loop {
+        let (index, content) = {
+            let docs = org_files.iter().map(|f| f.document()).collect::<Vec<_>>();
+            let Some(conflict) = ClockConflict::find_conflicts_excluding(&docs, exclude_tags, now)
+                .find(|conflict| !conflict.involves_running_clock())
+            else {
+                return Ok(rounds);
+            };
+            if rounds >= max_rounds {
+                return Err(anyhow::anyhow!(
+                    "clock conflicts did not converge after {max_rounds} rounds"
+                ));
+            }
+            let resolution = conflict.auto_resolution();
+            debug!("resolving with {resolution:?}: {}", conflict.report());
+            let changes = conflict.resolve(resolution)?;
+            let file = changes[0].file().clone();
+            let index = org_files
+                .iter()
+                .position(|f| f.file == file)
+                .expect("conflict file is one of the org files");
+            let content = FileChange::apply_to_string(changes, &org_files[index].content)?;
+            (index, content.into_owned())
+        };
+        org_files[index].set_content(content);
+        rounds += 1;
+    }
+}
+
+/// Error of [`ClockConflict::resolve`] for a resolution that would write a
+/// clock that ends before it starts.
+#[derive(Debug, Clone)]
+pub struct WouldCreateNegative(pub Finding);
+
+impl std::fmt::Display for WouldCreateNegative {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.message)
+    }
+}
+
+impl std::error::Error for WouldCreateNegative {}
 
-        Ok(())
+/// `changes` grouped by the file they change, in the order the files first
+/// appear.
+fn changes_by_file(changes: Vec<FileChange<'_>>) -> Vec<(PathBuf, Vec<FileChange<'_>>)> {
+    let mut grouped: Vec<(PathBuf, Vec<FileChange>)> = Vec::new();
+    for change in changes {
+        match grouped.iter_mut().find(|(file, _)| file == change.file()) {
+            Some((_, group)) => group.push(change),
+            None => grouped.push((change.file().clone(), vec![change])),
+        }
     }
+    grouped
 }
 
-struct ClockConflictIterator<'a> {
+/// A resolution applied by [`resolve_all`].
+#[derive(Debug, Clone)]
+pub struct AppliedResolution {
+    /// [`ClockConflict::report`] of the resolved conflict.
+    pub conflict: String,
+    pub resolution: ConflictResolution,
+    /// The file whose content changed. A resolution that changes clocks in
+    /// two files, like [`ConflictResolution::SplitOverlap`] across files, is
+    /// applied once per file.
+    pub file: PathBuf,
+    /// The changes made to `file`, in the order they were applied.
+    pub changes: Vec<AppliedChange>,
+}
+
+/// Something [`resolve_all`] did differently than chosen, or a side effect of
+/// a chosen resolution.
+#[derive(Debug, Clone)]
+pub enum ResolveNotice {
+    /// The chosen resolution doesn't apply to the conflict, which was skipped.
+    Skipped { conflict: String, error: String },
+    /// An applied resolution, [`ConflictResolution::ShiftLater`], moved a
+    /// clock into another one.
+    NewConflict {
+        resolution: ConflictResolution,
+        finding: Finding,
+    },
+    /// `file` changed on disk while choosing, so `resolution` was not applied
+    /// and the file's conflicts were offered again.
+    ChangedOnDisk {
+        file: PathBuf,
+        resolution: ConflictResolution,
+    },
+}
+
+impl std::fmt::Display for ResolveNotice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skipped { error, .. } => write!(f, "{error}, skipping"),
+            Self::NewConflict {
+                resolution,
+                finding,
+            } => write!(
+                f,
+                "{} creates a new conflict: {finding}",
+                resolution.explanation()
+            ),
+            Self::ChangedOnDisk { file, resolution } => write!(
+                f,
+                "{file:?} changed on disk, not applying {}, please choose again",
+                resolution.explanation()
+            ),
+        }
+    }
+}
+
+/// What [`resolve_all`] did.
+#[derive(Debug, Clone, Default)]
+pub struct ResolveReport {
+    /// The applied resolutions, in the order they were applied.
+    pub applied: Vec<AppliedResolution>,
+    /// In the order they happened.
+    pub notices: Vec<ResolveNotice>,
+}
+
+/// A [`FileChange`] that was applied, with the clock as written to the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppliedChange {
+    DeletedClock { line: usize },
+    AddedClock { line: usize, clock: String },
+    UpdateClock { line: usize, clock: String },
+}
+
+impl From<&FileChange<'_>> for AppliedChange {
+    fn from(change: &FileChange<'_>) -> Self {
+        match change {
+            FileChange::DeletedClock { clock, .. } => Self::DeletedClock { line: clock.line },
+            FileChange::AddedClock { clock, .. } => Self::AddedClock {
+                line: clock.line,
+                clock: clock.to_string(),
+            },
+            FileChange::UpdateClock { clock, .. } => Self::UpdateClock {
+                line: clock.line,
+                clock: clock.to_string(),
+            },
+        }
+    }
+}
+
+/// Resolves the conflicts between the clocks of `org_files` one by one,
+/// letting `choose` pick one of the [`ClockConflict::resolution_options`] for
+/// each, e.g. by asking the user. The resolution is applied to the file
+/// contents in place, files read from disk are saved right away (see
+/// [`OrgFile::save`]), and the files are re-parsed before the next conflict.
+/// If a file the resolution changes was changed on disk in the meantime, it
+/// is read again instead of saved and its conflicts are offered again. This,
+/// resolutions that don't apply and new conflicts created by a resolution are
+/// listed in the [`ResolveReport::notices`].
+/// Conflicts resolved with [`ConflictResolution::Skip`], or with a resolution
+/// that doesn't apply, are not offered again. Running clocks end at `now`.
+/// Returns once no unskipped conflicts are left.
+pub fn resolve_all<F>(
+    org_files: &mut [OrgFile],
+    parse_options: &ParseOptions,
+    exclude_tags: &[String],
+    now: DateTime<Utc>,
+    mut choose: F,
+) -> anyhow::Result<ResolveReport>
+where
+    F: FnMut(&ClockConflict, &[ConflictResolution]) -> ConflictResolution,
+{
+    let mut skipped = HashSet::new();
+    let mut report = ResolveReport::default();
+    loop {
+        let next = {
+            let docs = org_files
+                .iter()
+                .map(|f| f.document_with_options(parse_options))
+                .collect::<Vec<_>>();
+            let mut next = None;
+            for conflict in ClockConflict::find_conflicts_excluding(&docs, exclude_tags, now) {
+                let hash = conflict.hashme();
+                if skipped.contains(&hash) {
+                    continue;
+                }
+                let resolution = choose(&conflict, &conflict.resolution_options());
+                let conflict_report = conflict.report();
+                let changes = match conflict.resolve(resolution) {
+                    Ok(changes) => changes,
+                    Err(err) => {
+                        report.notices.push(ResolveNotice::Skipped {
+                            conflict: conflict_report,
+                            error: err.to_string(),
+                        });
+                        skipped.insert(hash);
+                        continue;
+                    }
+                };
+                if changes.is_empty() {
+                    skipped.insert(hash);
+                    continue;
+                }
+                // only reported once the resolution is applied
+                let mut new_conflicts = Vec::new();
+                if matches!(resolution, ConflictResolution::ShiftLater) {
+                    new_conflicts.extend(
+                        changes
+                            .iter()
+                            .flat_map(|c| c.new_conflicts(&docs, now))
+                            .map(|finding| ResolveNotice::NewConflict {
+                                resolution,
+                                finding,
+                            }),
+                    );
+                }
+                let mut per_file = Vec::new();
+                for (file, changes) in changes_by_file(changes) {
+                    let index = org_files
+                        .iter()
+                        .position(|f| f.file == file)
+                        .expect("conflict file is one of the org files");
+                    let applied_changes = changes.iter().map(AppliedChange::from).collect();
+                    let content = FileChange::apply_to_string(changes, &org_files[index].content)?;
+                    per_file.push((
+                        index,
+                        content.into_owned(),
+                        AppliedResolution {
+                            conflict: conflict_report.clone(),
+                            resolution,
+                            file,
+                            changes: applied_changes,
+                        },
+                    ));
+                }
+                next = Some((per_file, new_conflicts));
+                break;
+            }
+            next
+        };
+        let Some((per_file, new_conflicts)) = next else {
+            return Ok(report);
+        };
+        // nothing is saved unless every file of the resolution is unchanged
+        let mut changed_on_disk = false;
+        for (index, _, resolution) in &per_file {
+            let org_file = &mut org_files[*index];
+            if org_file.changed_on_disk()? {
+                report.notices.push(ResolveNotice::ChangedOnDisk {
+                    file: org_file.file.clone(),
+                    resolution: resolution.resolution,
+                });
+                *org_file = OrgFile::from_file(&org_file.file)?;
+                changed_on_disk = true;
+            }
+        }
+        if changed_on_disk {
+            continue;
+        }
+        for (index, content, resolution) in per_file {
+            let org_file = &mut org_files[index];
+            if org_file.disk_hash.is_some() {
+                org_file.save_content(content)?;
+            } else {
+                org_file.set_content(content);
+            }
+            report.applied.push(resolution);
+        }
+        report.notices.extend(new_conflicts);
+    }
+}
+
+/// FNV-1a, unlike [`DefaultHasher`] its output doesn't depend on the Rust
+/// version, so [`ClockConflict::hashme`] values can be stored. Integers are
+/// hashed little-endian and `usize` as `u64` to not depend on the platform.
+struct Fnv1aHasher(u64);
+
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Progress of a [`ClockConflictIterator`], so that a long session can be
+/// saved, e.g. as JSON, and resumed with [`ClockConflictIterator::from_state`].
+/// Only valid for the same documents and excluded tags: `seen` holds
+/// [`ClockConflict::hashme`] values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictSearchState {
+    pub last_i: usize,
+    pub last_j: usize,
+    pub seen: BTreeSet<u64>,
+    /// End of running clocks as a Unix timestamp, the same for all pairs.
+    pub now: i64,
+}
+
+impl Default for ConflictSearchState {
+    /// The start of a search, running clocks end now.
+    fn default() -> Self {
+        Self {
+            last_i: 0,
+            last_j: 0,
+            seen: BTreeSet::new(),
+            now: Utc::now().timestamp(),
+        }
+    }
+}
+
+/// The conflicts between clocks, see [`ClockConflict::find_conflicts`].
+pub struct ClockConflictIterator<'a> {
     data: Vec<(&'a PathBuf, &'a Headline<'a>, &'a Clock<'a>)>,
     last_i: usize,
     last_j: usize,
-    seen: HashSet<u64>,
+    seen: BTreeSet<u64>,
+    /// End of running clocks, the same for all pairs.
+    now: DateTime<Utc>,
+}
+
+impl<'a> ClockConflictIterator<'a> {
+    /// Continues a search from `state` where it was left with
+    /// [`ClockConflictIterator::state`].
+    pub fn from_state(
+        org_docs: &'a [OrgDocument<'a>],
+        exclude_tags: &[String],
+        state: ConflictSearchState,
+    ) -> Self {
+        let data = org_docs
+            .iter()
+            .flat_map(|doc| {
+                doc.clocks_with_headlines()
+                    .filter(|(_, clock)| {
+                        !exclude_tags
+                            .iter()
+                            .any(|tag| doc.has_inherited_tag(clock.parent, tag))
+                    })
+                    .map(|(headline, clock)| (&doc.file, headline, clock))
+            })
+            .collect();
+        Self {
+            data,
+            last_i: state.last_i,
+            last_j: state.last_j,
+            seen: state.seen,
+            now: Utc
+                .timestamp_opt(state.now, 0)
+                .single()
+                .unwrap_or_else(Utc::now),
+        }
+    }
+
+    /// The progress so far, to resume with
+    /// [`ClockConflictIterator::from_state`].
+    pub fn state(&self) -> ConflictSearchState {
+        ConflictSearchState {
+            last_i: self.last_i,
+            last_j: self.last_j,
+            seen: self.seen.clone(),
+            now: self.now.timestamp(),
+        }
+    }
 }
 
 impl<'a> Iterator for ClockConflictIterator<'a> {
@@ -415,7 +1051,7 @@ impl<'a> Iterator for ClockConflictIterator<'a> {
                 if i == self.last_i && j < self.last_j {
                     continue;
                 }
-                if i != j && clock1.overlaps(clock2) {
+                if i != j && clock1.overlaps(clock2, self.now) {
                     let conflict = ClockConflict {
                         clock1,
                         clock2,
@@ -444,9 +1080,17 @@ impl<'a> Iterator for ClockConflictIterator<'a> {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+
+    use chrono::{TimeZone, Utc};
 
-    use crate::{clock_conflict::ConflictResolution, ClockConflict, FileChange, OrgDocument};
+    use crate::{
+        clock_conflict::{
+            resolve_all, resolve_conflicts_automatically, AppliedChange, ClockConflictIterator,
+            ConflictResolution, ResolveNotice, WouldCreateNegative,
+        },
+        Clock, ClockConflict, FileChange, FindingKind, OrgDocument, OrgFile, ParseOptions,
+    };
 
     #[test]
     fn resolve_conflict_by_joining_times() {
@@ -464,7 +1108,8 @@ CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:50] =>  0:10
             ClockConflict::find_conflicts(docs)
                 .next()
                 .unwrap()
-                .resolve(ConflictResolution::Auto),
+                .resolve(ConflictResolution::Auto)
+                .unwrap(),
             org_string,
         )
         .expect("apply changes");
@@ -475,6 +1120,50 @@ CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:55] =>  0:15
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn exclude_tagged_subtree() {
+        let org_string = "
+* planning :meta:
+** estimate
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* doing
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:30] =>  1:00
+* also doing
+CLOCK: [2022-12-12 Mon 11:15]--[2022-12-12 Mon 11:45] =>  0:30
+";
+        let docs = &[OrgDocument::parse(PathBuf::from("test.org"), org_string)];
+        assert_eq!(ClockConflict::find_conflicts(docs).count(), 2);
+
+        let exclude = ["meta".to_string()];
+        let conflicts =
+            ClockConflict::find_conflicts_excluding(docs, &exclude, Utc::now()).collect::<Vec<_>>();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].headline1.title, "doing");
+        assert_eq!(conflicts[0].headline2.title, "also doing");
+    }
+
+    #[test]
+    fn running_clocks_end_at_now() {
+        let org_string = "
+* running
+CLOCK: [2022-12-12 Mon 10:00]
+* later
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 12:00] =>  1:00
+";
+        let docs = &[OrgDocument::parse(PathBuf::from("test.org"), org_string)];
+        // 10:30 and 11:30 in Berlin
+        let before = Utc.with_ymd_and_hms(2022, 12, 12, 9, 30, 0).unwrap();
+        let during = Utc.with_ymd_and_hms(2022, 12, 12, 10, 30, 0).unwrap();
+        assert_eq!(
+            ClockConflict::find_conflicts_excluding(docs, &[], before).count(),
+            0
+        );
+        assert_eq!(
+            ClockConflict::find_conflicts_excluding(docs, &[], during).count(),
+            1
+        );
+    }
+
     #[test]
     fn resolve_conflict_by_adjusting_time() {
         let org_string = "
@@ -485,12 +1174,12 @@ CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:50] =>  0:10
 ";
 
         let docs = &[OrgDocument::parse(PathBuf::from("test.org"), org_string)];
-        let conflict = ClockConflict::find_conflicts(docs)
-            .into_iter()
-            .next()
-            .unwrap();
+        let conflict = ClockConflict::find_conflicts(docs).next().unwrap();
 
-        let changes = conflict.clone().resolve(ConflictResolution::ShrinkEarlier);
+        let changes = conflict
+            .clone()
+            .resolve(ConflictResolution::ShrinkEarlier)
+            .unwrap();
         let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
         let expected = "
 * fooo
@@ -500,7 +1189,7 @@ CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:45] =>  0:05
 ";
         assert_eq!(result, expected);
 
-        let changes = conflict.resolve(ConflictResolution::ShrinkLater);
+        let changes = conflict.resolve(ConflictResolution::ShrinkLater).unwrap();
         let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
         let expected = "
 * fooo
@@ -511,6 +1200,118 @@ CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:50] =>  0:10
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn apply_refuses_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.org");
+        let org_string = "* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+";
+        std::fs::write(&path, org_string).unwrap();
+        let doc = OrgDocument::parse(&path, org_string);
+        let change = || vec![FileChange::update(&path, doc.clocks[0].clone())];
+
+        let edited = format!("* new headline\n{org_string}");
+        std::fs::write(&path, &edited).unwrap();
+        assert!(FileChange::apply(change(), doc.content_hash()).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), edited);
+
+        std::fs::write(&path, org_string).unwrap();
+        FileChange::apply(change(), doc.content_hash()).unwrap();
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .ends_with("=>  1:00\n"));
+    }
+
+    #[test]
+    fn resolve_conflict_by_shifting_later() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 11:30]--[2022-12-12 Mon 12:00] =>  0:30
+CLOCK: [2022-12-12 Mon 10:50]--[2022-12-12 Mon 11:30] =>  0:40
+* bar
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:00] =>  0:30
+";
+
+        let docs = &[OrgDocument::parse(PathBuf::from("test.org"), org_string)];
+        let conflict = ClockConflict::find_conflicts(docs).next().unwrap();
+        assert!(conflict
+            .resolution_options()
+            .iter()
+            .any(|r| matches!(r, ConflictResolution::ShiftLater)));
+
+        let changes = conflict.resolve(ConflictResolution::ShiftLater).unwrap();
+        let FileChange::UpdateClock { clock, .. } = &changes[0] else {
+            panic!("expected an update, got {changes:?}");
+        };
+        assert_eq!(clock.duration(), chrono::Duration::minutes(40));
+
+        // the shifted clock now runs into the clock after it
+        let new_conflicts = changes[0].new_conflicts(docs, Utc::now());
+        assert_eq!(new_conflicts.len(), 1);
+        assert_eq!(new_conflicts[0].related.as_ref().unwrap().line, 3);
+
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2022-12-12 Mon 11:30]--[2022-12-12 Mon 12:00] =>  0:30
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:40] =>  0:40
+* bar
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:00] =>  0:30
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn resolve_conflict_by_splitting_overlap() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:50]--[2022-12-12 Mon 11:30] =>  0:40
+* bar
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:00] =>  0:30
+";
+
+        let docs = &[OrgDocument::parse(PathBuf::from("test.org"), org_string)];
+        let conflict = ClockConflict::find_conflicts(docs).next().unwrap();
+        assert!(conflict
+            .resolution_options()
+            .iter()
+            .any(|r| matches!(r, ConflictResolution::SplitOverlap { .. })));
+
+        let changes = conflict
+            .resolve(ConflictResolution::SplitOverlap {
+                earlier_fraction: 0.5,
+            })
+            .unwrap();
+        assert_eq!(changes.len(), 2);
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:55]--[2022-12-12 Mon 11:30] =>  0:35
+* bar
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 10:55] =>  0:25
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn inapplicable_resolution_is_an_error() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:45]--[2022-12-12 Mon 10:55] =>  0:10
+* bar
+CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:50] =>  0:10
+";
+
+        let docs = &[OrgDocument::parse(PathBuf::from("test.org"), org_string)];
+        let conflict = ClockConflict::find_conflicts(docs).next().unwrap();
+        assert!(conflict
+            .clone()
+            .resolve(ConflictResolution::SplitContaining)
+            .is_err());
+        assert!(conflict.resolve(ConflictResolution::RemoveInner).is_err());
+    }
+
     #[test]
     fn resolve_conflict_by_splitting() {
         let org_string = "
@@ -521,14 +1322,12 @@ CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:59] =>  0:10
 ";
 
         let docs = &[OrgDocument::parse(PathBuf::from("test.org"), org_string)];
-        let conflict = ClockConflict::find_conflicts(docs)
-            .into_iter()
-            .next()
-            .unwrap();
+        let conflict = ClockConflict::find_conflicts(docs).next().unwrap();
 
         let changes = conflict
             .clone()
-            .resolve(ConflictResolution::SplitContaining);
+            .resolve(ConflictResolution::SplitContaining)
+            .unwrap();
         let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
         let expected = "
 * fooo
@@ -539,4 +1338,301 @@ CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:45] =>  0:05
 ";
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn resolve_conflicts_automatically_converges() {
+        let fooo = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 10:45] =>  0:15
+* bar
+CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 11:20] =>  0:40
+";
+        let baz = "
+* baz
+CLOCK: [2022-12-12 Mon 10:50]--[2022-12-12 Mon 11:30] =>  0:40
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+";
+        let org_files = || vec![OrgFile::new("fooo.org", fooo), OrgFile::new("baz.org", baz)];
+
+        let mut files = org_files();
+        let rounds =
+            resolve_conflicts_automatically(&mut files, 10, &[], Utc::now()).expect("converges");
+        assert!(rounds > 1);
+        let docs = files.iter().map(|f| f.document()).collect::<Vec<_>>();
+        assert_eq!(ClockConflict::find_conflicts(&docs).count(), 0);
+        // the clock without conflicts is untouched
+        assert!(files[1]
+            .content
+            .contains("CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30"));
+
+        let mut files = org_files();
+        assert!(resolve_conflicts_automatically(&mut files, 1, &[], Utc::now()).is_err());
+    }
+
+    #[test]
+    fn reject_negative_resolution() {
+        let org_string = "
+* broken
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 09:00] => -1:00
+* other
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:00] =>  0:30
+";
+        let docs = [OrgDocument::parse("test.org", org_string)];
+        let doc = &docs[0];
+        // a negative clock doesn't overlap, so construct the conflict directly
+        let conflict = || ClockConflict {
+            clock1: &doc.clocks[0],
+            clock2: &doc.clocks[1],
+            headline1: &doc.headlines[0],
+            headline2: &doc.headlines[1],
+            file1: &doc.file,
+            file2: &doc.file,
+        };
+        let split = ConflictResolution::SplitOverlap {
+            earlier_fraction: 0.5,
+        };
+        let err = conflict().resolve(split).unwrap_err();
+        let WouldCreateNegative(finding) = err.downcast_ref::<WouldCreateNegative>().unwrap();
+        assert_eq!(finding.kind, FindingKind::WouldCreateNegative);
+        assert_eq!(finding.line, 3);
+        assert_eq!(
+            finding.message,
+            "WOULD CREATE NEGATIVE CLOCK [2022-12-12 Mon 10:00]--[2022-12-12 Mon 09:45] => -0:15 \
+             by Split the overlap between both timestamps, not applied"
+        );
+
+        // shrinking the broken clock repairs it and is fine
+        assert!(conflict()
+            .resolve(ConflictResolution::ShrinkEarlier)
+            .is_ok());
+    }
+
+    #[test]
+    fn resume_conflict_search() {
+        let org_string = "
+* a
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* b
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:30] =>  1:00
+* c
+CLOCK: [2022-12-12 Mon 10:45]--[2022-12-12 Mon 12:00] =>  1:15
+CLOCK: [2022-12-13 Tue 08:00]
+* d
+CLOCK: [2022-12-13 Tue 09:00]--[2022-12-13 Tue 10:00] =>  1:00
+";
+        let docs = [OrgDocument::parse("test.org", org_string)];
+        let report = |conflicts: Vec<ClockConflict>| {
+            conflicts.iter().map(|c| c.report()).collect::<Vec<_>>()
+        };
+        let all = report(ClockConflict::find_conflicts(&docs).collect());
+        assert_eq!(all.len(), 4);
+
+        let mut search = ClockConflict::find_conflicts(&docs);
+        let first = report(search.by_ref().take(2).collect());
+        let json = serde_json::to_string(&search.state()).unwrap();
+        drop(search);
+
+        let state = serde_json::from_str(&json).unwrap();
+        let rest = report(ClockConflictIterator::from_state(&docs, &[], state).collect());
+        assert_eq!([first, rest].concat(), all);
+    }
+
+    #[test]
+    fn resolve_all_with_callback() {
+        let fooo = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* bar
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:30] =>  1:00
+CLOCK: [2022-12-12 Mon 11:15]--[2022-12-12 Mon 11:45] =>  0:30
+";
+        let org_files = || vec![OrgFile::new("fooo.org", fooo)];
+        let opts = ParseOptions::default();
+
+        let mut files = org_files();
+        let mut asked = 0;
+        let applied = resolve_all(&mut files, &opts, &[], Utc::now(), |_, options| {
+            asked += 1;
+            assert!(matches!(options.last(), Some(ConflictResolution::Skip)));
+            ConflictResolution::Skip
+        })
+        .unwrap()
+        .applied;
+        assert!(applied.is_empty());
+        assert_eq!(asked, 2);
+        assert_eq!(files[0].content, fooo);
+
+        // resolutions that don't apply are skipped and noted
+        let partial = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* bar
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:30] =>  1:00
+";
+        let mut files = vec![OrgFile::new("fooo.org", partial)];
+        let report = resolve_all(&mut files, &opts, &[], Utc::now(), |_, _| {
+            ConflictResolution::SplitContaining
+        })
+        .unwrap();
+        assert!(report.applied.is_empty());
+        assert!(matches!(
+            &report.notices[..],
+            [ResolveNotice::Skipped { .. }]
+        ));
+        assert_eq!(files[0].content, partial);
+
+        let mut files = org_files();
+        let applied = resolve_all(&mut files, &opts, &[], Utc::now(), |conflict, _| {
+            conflict.auto_resolution()
+        })
+        .unwrap()
+        .applied;
+        assert_eq!(applied.len(), 2);
+        assert!(applied.iter().all(|a| a.file == Path::new("fooo.org")));
+        assert_eq!(
+            applied[0].changes,
+            [AppliedChange::UpdateClock {
+                line: 3,
+                clock: "[2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30".to_string()
+            }]
+        );
+        let docs = files.iter().map(|f| f.document()).collect::<Vec<_>>();
+        assert_eq!(ClockConflict::find_conflicts(&docs).count(), 0);
+
+        // files read from disk are saved after each resolution
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("fooo.org");
+        std::fs::write(&file, fooo).unwrap();
+        let mut files = vec![OrgFile::from_file(&file).unwrap()];
+        let mut saved = Vec::new();
+        resolve_all(&mut files, &opts, &[], Utc::now(), |conflict, _| {
+            saved.push(std::fs::read_to_string(&file).unwrap());
+            conflict.auto_resolution()
+        })
+        .unwrap();
+        assert_eq!(saved[0], fooo);
+        assert_ne!(saved[1], fooo);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), files[0].content);
+
+        // a file edited while choosing is read again and the conflict offered again
+        std::fs::write(&file, fooo).unwrap();
+        let mut files = vec![OrgFile::from_file(&file).unwrap()];
+        let edited = format!("* edited in emacs{fooo}");
+        let mut asked = 0;
+        let report = resolve_all(&mut files, &opts, &[], Utc::now(), |conflict, _| {
+            asked += 1;
+            if asked == 1 {
+                std::fs::write(&file, &edited).unwrap();
+            }
+            conflict.auto_resolution()
+        })
+        .unwrap();
+        assert_eq!(asked, 3);
+        assert_eq!(report.applied.len(), 2);
+        assert!(matches!(
+            &report.notices[..],
+            [ResolveNotice::ChangedOnDisk { file: changed, .. }] if changed == &file
+        ));
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert!(content.starts_with("* edited in emacs\n"));
+        assert_eq!(content, files[0].content);
+    }
+
+    #[test]
+    fn resolve_all_split_overlap_across_files() {
+        let a = "
+* a
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+";
+        let b = "
+* b
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:30] =>  1:00
+";
+        let dir = tempfile::tempdir().unwrap();
+        let (file_a, file_b) = (dir.path().join("a.org"), dir.path().join("b.org"));
+        std::fs::write(&file_a, a).unwrap();
+        std::fs::write(&file_b, b).unwrap();
+        let mut files = vec![
+            OrgFile::from_file(&file_a).unwrap(),
+            OrgFile::from_file(&file_b).unwrap(),
+        ];
+        let split = ConflictResolution::SplitOverlap {
+            earlier_fraction: 0.5,
+        };
+        let applied = resolve_all(
+            &mut files,
+            &ParseOptions::default(),
+            &[],
+            Utc::now(),
+            |_, options| {
+                assert!(options
+                    .iter()
+                    .any(|r| matches!(r, ConflictResolution::SplitOverlap { .. })));
+                split
+            },
+        )
+        .unwrap()
+        .applied;
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0].file, file_a);
+        assert_eq!(applied[1].file, file_b);
+        assert_eq!(
+            std::fs::read_to_string(&file_a).unwrap(),
+            "
+* a
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:45] =>  0:45
+"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&file_b).unwrap(),
+            "
+* b
+CLOCK: [2022-12-12 Mon 10:45]--[2022-12-12 Mon 11:30] =>  0:45
+"
+        );
+    }
+
+    #[test]
+    fn mistargeted_changes_are_rejected() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+some notes
+";
+        let clock = |line| {
+            let mut clock =
+                Clock::try_from("CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00]").unwrap();
+            clock.line = line;
+            clock
+        };
+
+        // headline and body lines are not touched
+        for line in [2, 4] {
+            let change = FileChange::delete("test.org", clock(line));
+            assert!(FileChange::apply_to_string(vec![change], org_string).is_err());
+            let change = FileChange::update("test.org", clock(line));
+            assert!(FileChange::apply_to_string(vec![change], org_string).is_err());
+        }
+        let change = FileChange::add("test.org", clock(10));
+        assert!(FileChange::apply_to_string(vec![change], org_string).is_err());
+
+        let change = FileChange::update("test.org", clock(3));
+        let result = FileChange::apply_to_string(vec![change], org_string).expect("apply changes");
+        assert!(result.contains("=>  1:00\nsome notes\n"));
+    }
+
+    #[test]
+    fn conflict_hash_is_fnv1a() {
+        use std::hash::Hasher;
+
+        let mut hasher = super::Fnv1aHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+        hasher.write_usize(1);
+        let mut expected = super::Fnv1aHasher::default();
+        expected.write(b"a");
+        expected.write(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(hasher.finish(), expected.finish());
+    }
 }
@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path};
+
+use crate::{FindingKind, LintOptions, Severity};
+
+/// Settings read from a config file like
+///
+/// ```toml
+/// [severity]
+/// long-duration = "error"
+/// missing-id = "warning"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Severities by finding kind name.
+    #[serde(default)]
+    pub severity: HashMap<FindingKind, Severity>,
+}
+
+impl Config {
+    /// Name of the config file looked up in the org directory.
+    pub const FILE_NAME: &'static str = ".org-linter.toml";
+
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn from_file(file: impl AsRef<Path>) -> Result<Self> {
+        let file = file.as_ref();
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("cannot read config file {file:?}"))?;
+        Self::parse(&content).with_context(|| format!("invalid config file {file:?}"))
+    }
+
+    /// Applies the settings of the config to `opts`.
+    pub fn apply(&self, opts: &mut LintOptions) {
+        opts.severities.extend(self.severity.iter());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::{lint_document, FindingKind, LintOptions, OrgDocument, Severity};
+
+    #[test]
+    fn severity_override() {
+        let config = Config::parse(
+            r#"
+[severity]
+zero-duration = "error"
+"#,
+        )
+        .unwrap();
+        let doc = OrgDocument::parse(
+            "test.org",
+            "* task\nCLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:00] =>  0:00\n",
+        );
+
+        let mut opts = LintOptions::default();
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings[0].kind, FindingKind::ZeroDuration);
+        assert_eq!(findings[0].severity, Severity::Warning);
+
+        config.apply(&mut opts);
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings[0].severity, Severity::Error);
+
+        assert!(Config::parse("[severity]\nzero-duration = \"fatal\"").is_err());
+        assert!(Config::parse("[severity]\nno-such-check = \"error\"").is_err());
+    }
+}
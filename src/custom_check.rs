@@ -0,0 +1,206 @@
+//! Checks implemented by external commands.
+//!
+//! The command gets one document as JSON on stdin:
+//!
+//! ```json
+//! {
+//!   "file": "/home/me/org/work.org",
+//!   "headlines": [
+//!     {"line": 1, "level": 1, "title": "task", "tags": ["work"],
+//!      "properties": {"ID": "42"}, "parent": null}
+//!   ],
+//!   "clocks": [
+//!     {"line": 2, "headline": 0, "start": "2022-12-12T10:00:00",
+//!      "end": "2022-12-12T11:00:00", "duration_minutes": 60, "total": "1:00"}
+//!   ]
+//! }
+//! ```
+//!
+//! `parent` and `headline` are indexes into `headlines`. `end` and
+//! `duration_minutes` are `null` for running clocks, `total` is `null` if the
+//! clock has no `=>` total. The command writes one finding per line to stdout,
+//! e.g. `{"line": 2, "message": "clocked on a weekend"}`. Empty lines are
+//! ignored. A non-zero exit status is an error.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::{Finding, FindingKind, OrgDocument};
+
+#[derive(Debug, Serialize)]
+pub struct DocumentJson<'a> {
+    pub file: &'a Path,
+    pub headlines: Vec<HeadlineJson<'a>>,
+    pub clocks: Vec<ClockJson<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeadlineJson<'a> {
+    pub line: usize,
+    pub level: usize,
+    pub title: &'a str,
+    pub tags: Vec<&'a str>,
+    pub properties: BTreeMap<&'a str, &'a str>,
+    pub parent: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClockJson<'a> {
+    pub line: usize,
+    pub headline: usize,
+    pub start: String,
+    pub end: Option<String>,
+    pub duration_minutes: Option<i64>,
+    pub total: Option<&'a str>,
+}
+
+/// A finding as returned by a custom check command.
+#[derive(Debug, Deserialize)]
+pub struct CustomFinding {
+    pub line: usize,
+    pub message: String,
+}
+
+impl<'a> DocumentJson<'a> {
+    pub fn new(doc: &'a OrgDocument<'a>) -> Self {
+        let headlines = doc
+            .headlines
+            .iter()
+            .enumerate()
+            .map(|(i, headline)| HeadlineJson {
+                line: headline.line,
+                level: headline.level,
+                title: headline.title,
+                tags: headline.tags().collect(),
+                properties: headline.properties.iter().copied().collect(),
+                parent: doc.parent_of(i),
+            })
+            .collect();
+        let clocks = doc
+            .clocks
+            .iter()
+            .map(|clock| ClockJson {
+                line: clock.line,
+                headline: clock.parent,
+                start: iso_format(clock.start),
+                end: clock.end.map(iso_format),
+                duration_minutes: clock.end.map(|_| clock.duration().num_minutes()),
+                total: clock.duration_string,
+            })
+            .collect();
+        Self {
+            file: &doc.file,
+            headlines,
+            clocks,
+        }
+    }
+}
+
+fn iso_format(d: NaiveDateTime) -> String {
+    d.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Runs `command` with `sh -c`, passing `doc` as JSON, and returns the
+/// findings it reports.
+pub fn run_custom_check(command: &str, doc: &OrgDocument) -> Result<Vec<Finding>> {
+    let input = serde_json::to_vec(&DocumentJson::new(doc))?;
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start custom check {command:?}"))?;
+
+    // write from another thread so a command that starts printing before it
+    // has read all of its input cannot deadlock us
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    // the command may exit without reading its input, that's fine
+    let _ = writer.join();
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "custom check {command:?} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .with_context(|| format!("custom check {command:?} printed invalid UTF-8"))?;
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let finding: CustomFinding = serde_json::from_str(line).with_context(|| {
+                format!("invalid finding from custom check {command:?}: {line}")
+            })?;
+            Ok(Finding::new(
+                FindingKind::Custom,
+                &doc.file,
+                finding.line,
+                finding.message,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FindingKind, OrgDocument};
+
+    const ORG: &str = "* task :work:
+:PROPERTIES:
+:ID: 42
+:END:
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+";
+
+    #[test]
+    fn document_json() {
+        let doc = OrgDocument::parse("test.org", ORG);
+        let json = serde_json::to_value(super::DocumentJson::new(&doc)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "file": "test.org",
+                "headlines": [{"line": 1, "level": 1, "title": "task", "tags": ["work"],
+                               "properties": {"ID": "42"}, "parent": null}],
+                "clocks": [{"line": 5, "headline": 0, "start": "2022-12-12T10:00:00",
+                            "end": "2022-12-12T11:00:00", "duration_minutes": 60, "total": "1:00"}]
+            })
+        );
+    }
+
+    #[test]
+    fn findings_of_custom_check_are_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("check.sh");
+        std::fs::write(
+            &script,
+            "cat > /dev/null\necho '{\"line\": 5, \"message\": \"clocked on a monday\"}'\necho\n",
+        )
+        .unwrap();
+        let doc = OrgDocument::parse("test.org", ORG);
+
+        let findings = super::run_custom_check(&format!("sh {}", script.display()), &doc).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::Custom);
+        assert_eq!(findings[0].line, 5);
+        assert_eq!(findings[0].message, "clocked on a monday");
+
+        assert!(super::run_custom_check("cat > /dev/null; exit 3", &doc).is_err());
+        assert!(super::run_custom_check("echo not json", &doc).is_err());
+    }
+}
@@ -0,0 +1,913 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, HashMap, HashSet},
+};
+
+use chrono::{Datelike, Duration, NaiveTime};
+
+use crate::{
+    clock::CLOCK_RE, headline::parse_property, Clock, FileChange, Finding, OrgDocument,
+    TimestampType, WeekdayLocale,
+};
+
+/// Combines clocks of the same headline that abut each other, i.e. where the
+/// end of one clock is the start of the next, into a single clock spanning the
+/// whole range. The merged clock takes the place of the chain's first clock in
+/// the file, the remaining clocks of the chain are deleted.
+pub fn merge_adjacent<'a>(doc: &OrgDocument<'a>) -> Vec<FileChange<'a>> {
+    let mut changes = Vec::new();
+    for (_, mut clocks) in doc.clocks_by_headline() {
+        clocks.retain(|clock| !clock.is_running());
+        clocks.sort_by_key(|clock| clock.start);
+        let mut chain: Vec<&Clock<'a>> = Vec::new();
+        for clock in clocks {
+            match chain.last() {
+                Some(last) if last.end == Some(clock.start) => chain.push(clock),
+                _ => {
+                    merge_chain(doc, &chain, &mut changes);
+                    chain = vec![clock];
+                }
+            }
+        }
+        merge_chain(doc, &chain, &mut changes);
+    }
+    changes
+}
+
+fn merge_chain<'a>(doc: &OrgDocument<'a>, chain: &[&Clock<'a>], changes: &mut Vec<FileChange<'a>>) {
+    let (Some(first), Some(last)) = (chain.first(), chain.last()) else {
+        return;
+    };
+    if chain.len() < 2 {
+        return;
+    }
+    let keep = chain.iter().min_by_key(|clock| clock.line).unwrap();
+    let mut merged = (*keep).clone();
+    merged.start = first.start;
+    merged.end = last.end;
+    changes.push(FileChange::update(&doc.file, merged));
+    for clock in chain.iter().filter(|clock| clock.line != keep.line) {
+        changes.push(FileChange::delete(&doc.file, (*clock).clone()));
+    }
+}
+
+/// Rewrites clocks whose `=>` total has minutes without zero padding. The
+/// total is recomputed from start and end.
+pub fn pad_minutes<'a>(doc: &OrgDocument<'a>) -> Vec<FileChange<'a>> {
+    doc.clocks
+        .iter()
+        .filter(|clock| clock.has_nonpadded_minutes())
+        .map(|clock| FileChange::update(&doc.file, clock.clone()))
+        .collect()
+}
+
+/// Rewrites clocks whose start and end use different timestamp types so that
+/// both are inactive, as org writes them.
+pub fn normalize_timestamp_types<'a>(doc: &OrgDocument<'a>) -> Vec<FileChange<'a>> {
+    doc.clocks
+        .iter()
+        .filter(|clock| clock.has_mixed_timestamp_types())
+        .map(|clock| {
+            let mut clock = clock.clone();
+            clock.timestamp_type = TimestampType::Inactive;
+            clock.end_timestamp_type = Some(TimestampType::Inactive);
+            FileChange::update(&doc.file, clock)
+        })
+        .collect()
+}
+
+/// Corrects the day of week names of clocks that don't match their dates.
+/// With a `locale`, names written in another locale are rewritten in it as
+/// well, see also [`crate::ParseOptions::locale`]. Only the names change, the
+/// rest of the line stays as it is. `content` must be the text `doc` was
+/// parsed from.
+pub fn fix_weekdays(doc: &OrgDocument, content: &str, locale: Option<WeekdayLocale>) -> String {
+    let fix = doc
+        .clocks
+        .iter()
+        .filter(|clock| {
+            clock.has_wrong_weekday()
+                || locale.is_some_and(|locale| {
+                    WeekdayLocale::detect(clock.start_weekday) != Some(locale)
+                })
+        })
+        .map(|clock| (clock.line - 1, clock))
+        .collect::<HashMap<_, _>>();
+    replace_lines(content, |i, line| {
+        let clock = fix.get(&i)?;
+        let locale = locale.unwrap_or(clock.locale);
+        let captures = CLOCK_RE.captures(line)?;
+        let mut fixed = line.to_string();
+        // the end first, so that the position of the start stays valid
+        for (group, date) in [(13, clock.end), (5, Some(clock.start))] {
+            if let (Some(token), Some(date)) = (captures.get(group), date) {
+                fixed.replace_range(token.range(), locale.abbreviation(date.weekday()));
+            }
+        }
+        Some(fixed)
+    })
+}
+
+/// `content` with the lines for which `replace` returns a new text, by index
+/// from 0, replaced. All other lines and the line endings are kept as they
+/// are.
+fn replace_lines(content: &str, mut replace: impl FnMut(usize, &str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(content.len());
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        match replace(i, text) {
+            Some(replaced) => {
+                result.push_str(&replaced);
+                result.push_str(&line[text.len()..]);
+            }
+            None => result.push_str(line),
+        }
+    }
+    result
+}
+
+/// `content` without the lines in `remove`, by index from 0. All other lines
+/// and the line endings are kept as they are.
+fn remove_lines(content: &str, remove: &HashSet<usize>) -> String {
+    content
+        .split_inclusive('\n')
+        .enumerate()
+        .filter(|(i, _)| !remove.contains(i))
+        .map(|(_, line)| line)
+        .collect()
+}
+
+/// Rewrites every clock line that is not in the canonical form of
+/// [`Clock`]'s `Display`: weekday names in the clock's locale, single spaces,
+/// zero padded times and a recomputed `=>` total. With a `locale` the weekday
+/// names are written in it. Timestamps are kept as they are, so running it
+/// again changes nothing. Without a [`OrgDocument::source`] all clocks are
+/// rewritten.
+pub fn normalize_clocks<'a>(
+    doc: &OrgDocument<'a>,
+    locale: Option<WeekdayLocale>,
+) -> Vec<FileChange<'a>> {
+    let lines = doc.source.map(|source| source.lines().collect::<Vec<_>>());
+    doc.clocks
+        .iter()
+        .map(|clock| {
+            let mut clock = clock.clone();
+            clock.locale = locale.unwrap_or(clock.locale);
+            clock
+        })
+        .filter(|clock| {
+            let canonical = format!("CLOCK: {clock}");
+            let line = lines
+                .as_ref()
+                .and_then(|lines| lines.get(clock.line - 1).copied());
+            line.is_none_or(|line| line.trim_start() != canonical)
+        })
+        .map(|clock| FileChange::update(&doc.file, clock))
+        .collect()
+}
+
+/// Ends running clocks below a headline in a done state (see
+/// [`OrgDocument::done_headline`]) at the `CLOSED:` time of that headline.
+/// Clocks are kept running if the headline has no closing time or was closed
+/// before the clock started.
+pub fn close_running_under_done<'a>(
+    doc: &OrgDocument<'a>,
+    done_keywords: &[String],
+) -> Vec<FileChange<'a>> {
+    doc.clocks
+        .iter()
+        .filter(|clock| clock.is_running())
+        .filter_map(|clock| {
+            let done = doc.done_headline(clock.parent, done_keywords)?;
+            let closed = doc.headlines[done].planning.closed?;
+            let closed = closed.date.and_time(closed.time?);
+            if closed < clock.start {
+                return None;
+            }
+            let mut clock = clock.clone();
+            clock.end = Some(closed);
+            clock.end_timestamp_type = Some(clock.timestamp_type);
+            clock.end_weekday = None;
+            Some(FileChange::update(&doc.file, clock))
+        })
+        .collect()
+}
+
+/// Splits the clock on `line` at the first `time` after its start, see
+/// [`Clock::split_at`]. The later part goes above the earlier one, where org
+/// adds new clocks, so it can be moved to another headline.
+pub fn split_clock<'a>(
+    doc: &OrgDocument<'a>,
+    line: usize,
+    time: NaiveTime,
+) -> anyhow::Result<Vec<FileChange<'a>>> {
+    let clock = doc
+        .clocks
+        .iter()
+        .find(|clock| clock.line == line)
+        .ok_or_else(|| anyhow::anyhow!("no clock on line {line} of {:?}", doc.file))?;
+    let mut at = clock.start.date().and_time(time);
+    if at <= clock.start {
+        at += Duration::days(1);
+    }
+    let (first, second) = clock
+        .split_at(at)
+        .ok_or_else(|| anyhow::anyhow!("{} is not within {clock}", time.format("%H:%M")))?;
+    Ok(vec![
+        FileChange::update(&doc.file, first),
+        FileChange::add(&doc.file, second),
+    ])
+}
+
+/// Rewrites the `=>` total of finished clocks where it does not match start
+/// and end.
+pub fn fix_durations<'a>(doc: &OrgDocument<'a>) -> Vec<FileChange<'a>> {
+    doc.clocks
+        .iter()
+        .filter(|clock| !clock.matches_duration())
+        .map(|clock| FileChange::update(&doc.file, clock.clone()))
+        .collect()
+}
+
+/// Deletes finished clocks that start and end at the same time. Clocks with a
+/// phantom duration (see [`Clock::has_phantom_duration`]) are kept for manual
+/// review, [`fix_durations`] zeroes their total.
+pub fn remove_zero_clocks<'a>(doc: &OrgDocument<'a>) -> Vec<FileChange<'a>> {
+    doc.clocks
+        .iter()
+        .filter(|clock| {
+            !clock.is_running() && clock.duration().is_zero() && !clock.has_phantom_duration()
+        })
+        .map(|clock| FileChange::delete(&doc.file, clock.clone()))
+        .collect()
+}
+
+/// A fixer computes the changes that fix one kind of problem in a document.
+pub type Fixer = for<'a> fn(&OrgDocument<'a>) -> Vec<FileChange<'a>>;
+
+/// All automatic fixers by name, in the order [`preview_fixes`] runs them.
+pub const ALL_FIXERS: &[(&str, Fixer)] = &[
+    ("remove-zero-clocks", remove_zero_clocks),
+    ("fix-durations", fix_durations),
+    ("normalize-timestamp-types", normalize_timestamp_types),
+    ("pad-minutes", pad_minutes),
+    ("merge-adjacent", merge_adjacent),
+    ("sort-clocks", sort_clocks),
+];
+
+/// A fixer whose changes were dropped because they touch lines that an
+/// earlier fixer already changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixOverlap {
+    pub fixer: &'static str,
+    pub other: &'static str,
+    pub line: usize,
+}
+
+impl std::fmt::Display for FixOverlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "skipping {} because it changes line {} which {} already changes",
+            self.fixer, self.line, self.other
+        )
+    }
+}
+
+/// The combined changes of all fixers for one document.
+#[derive(Debug, Clone)]
+pub struct FixPreview<'a> {
+    /// Number of changes per fixer that contributed to `changes`.
+    pub counts: Vec<(&'static str, usize)>,
+    pub changes: Vec<FileChange<'a>>,
+    pub overlaps: Vec<FixOverlap>,
+    /// [`crate::FindingKind::WouldCreateNegative`] findings of fixers whose
+    /// changes were left out because they would write a negative clock.
+    pub rejected: Vec<Finding>,
+}
+
+/// Runs every fixer of [`ALL_FIXERS`] on `doc` and combines their changes.
+/// Fixers depend on the original line contents, so if a fixer wants to change
+/// a line that an earlier fixer already changes, all of its changes are left
+/// out and reported as an overlap instead of producing a corrupt result. The
+/// same goes for fixers that would write a clock ending before its start.
+pub fn preview_fixes<'a>(doc: &OrgDocument<'a>) -> FixPreview<'a> {
+    let mut claimed: HashMap<usize, &'static str> = HashMap::new();
+    let mut preview = FixPreview {
+        counts: Vec::new(),
+        changes: Vec::new(),
+        overlaps: Vec::new(),
+        rejected: Vec::new(),
+    };
+    for (name, fixer) in ALL_FIXERS {
+        let changes = fixer(doc);
+        if changes.is_empty() {
+            continue;
+        }
+        let rejected = changes
+            .iter()
+            .filter_map(|change| change.negative_clock(name))
+            .collect::<Vec<_>>();
+        if !rejected.is_empty() {
+            preview.rejected.extend(rejected);
+            continue;
+        }
+        let overlap = changes.iter().find_map(|change| {
+            claimed.get(&change.line()).map(|other| FixOverlap {
+                fixer: name,
+                other,
+                line: change.line(),
+            })
+        });
+        if let Some(overlap) = overlap {
+            preview.overlaps.push(overlap);
+            continue;
+        }
+        for change in &changes {
+            claimed.insert(change.line(), name);
+        }
+        preview.counts.push((name, changes.len()));
+        preview.changes.extend(changes);
+    }
+    preview
+}
+
+/// Removes the `property` lines of headlines that have neither clocks nor
+/// descendants with clocks. `content` must be the text `doc` was parsed from.
+pub fn remove_orphan_totals(doc: &OrgDocument, content: &str, property: &str) -> String {
+    let clock_counts = doc.subtree_clock_counts();
+    let mut remove = HashSet::new();
+    let lines = content.lines().collect::<Vec<_>>();
+    for (headline, count) in doc.headlines.iter().zip(clock_counts) {
+        if count > 0 || headline.property(property).is_none() {
+            continue;
+        }
+        // the drawer follows the planning line, if there is one
+        let drawer_start = headline.line + usize::from(!headline.planning.is_empty());
+        let mut in_properties = false;
+        for (i, line) in lines.iter().enumerate().skip(drawer_start) {
+            if line.trim().eq_ignore_ascii_case(":PROPERTIES:") {
+                in_properties = true;
+            } else if !in_properties || line.trim().eq_ignore_ascii_case(":END:") {
+                break;
+            } else if parse_property(line)
+                .is_some_and(|(name, _)| name.eq_ignore_ascii_case(property))
+            {
+                remove.insert(i);
+            }
+        }
+    }
+    remove_lines(content, &remove)
+}
+
+/// Rewrites the tags of headlines that have a tag more than once, keeping the
+/// first occurrence of each, e.g. `:work:work:urgent:` becomes
+/// `:work:urgent:`.
+pub fn dedupe_tags(doc: &OrgDocument, content: &str) -> String {
+    let mut replace = HashMap::new();
+    for headline in &doc.headlines {
+        let Some(tags_string) = headline.tags_string else {
+            continue;
+        };
+        if headline.duplicate_tags().is_empty() {
+            continue;
+        }
+        let mut tags = Vec::new();
+        for tag in headline.tags() {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        replace.insert(
+            headline.line - 1,
+            (tags_string, format!(":{}:", tags.join(":"))),
+        );
+    }
+    replace_lines(content, |i, line| {
+        let (old, new) = replace.get(&i)?;
+        let pos = line.rfind(old)?;
+        Some(format!("{}{new}{}", &line[..pos], &line[pos + old.len()..]))
+    })
+}
+
+/// Clocks in a drawer whose indentation differs from the one most clocks of
+/// the drawer use, together with that indentation. On a tie the indentation
+/// of the drawer's first clock wins.
+pub(crate) fn misaligned_clocks<'c, 'a>(doc: &'c OrgDocument<'a>) -> Vec<(&'c Clock<'a>, &'a str)> {
+    let mut by_drawer: BTreeMap<usize, Vec<&Clock<'a>>> = BTreeMap::new();
+    for clock in &doc.clocks {
+        if let Some(drawer) = clock.drawer {
+            by_drawer.entry(drawer).or_default().push(clock);
+        }
+    }
+    let mut result = Vec::new();
+    for clocks in by_drawer.values() {
+        let mut counts: Vec<(&'a str, usize)> = Vec::new();
+        for clock in clocks {
+            match counts
+                .iter_mut()
+                .find(|(indent, _)| *indent == clock.indent)
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((clock.indent, 1)),
+            }
+        }
+        let expected = counts
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|(indent, _)| *indent)
+            .unwrap_or_default();
+        result.extend(
+            clocks
+                .iter()
+                .filter(|clock| clock.indent != expected)
+                .map(|clock| (*clock, expected)),
+        );
+    }
+    result
+}
+
+/// Re-indents clocks in drawers to match the other clocks of their drawer,
+/// see [`misaligned_clocks`]. `content` must be the text `doc` was parsed
+/// from.
+pub fn normalize_clock_indentation(doc: &OrgDocument, content: &str) -> String {
+    let reindent = misaligned_clocks(doc)
+        .into_iter()
+        .map(|(clock, expected)| (clock.line, expected))
+        .collect::<HashMap<_, _>>();
+    replace_lines(content, |i, line| {
+        let indent = reindent.get(&(i + 1))?;
+        Some(format!("{indent}{}", line.trim_start()))
+    })
+}
+
+/// Whether the clocks of a headline are predominantly written newest-first.
+/// Org inserts new clocks at the top, so that is assumed when undecided.
+fn newest_first(clocks: &[&Clock]) -> bool {
+    let (ascending, descending) = clocks.windows(2).fold((0, 0), |(asc, desc), pair| {
+        match pair[0].start.cmp(&pair[1].start) {
+            Ordering::Less => (asc + 1, desc),
+            Ordering::Greater => (asc, desc + 1),
+            Ordering::Equal => (asc, desc),
+        }
+    });
+    descending >= ascending
+}
+
+/// Returns the first clock (in file order) that breaks the chronological
+/// order the other clocks of the headline are written in.
+pub(crate) fn first_unsorted_clock<'c, 'a>(clocks: &[&'c Clock<'a>]) -> Option<&'c Clock<'a>> {
+    let newest_first = newest_first(clocks);
+    clocks.windows(2).find_map(|pair| {
+        let out_of_order = if newest_first {
+            pair[0].start < pair[1].start
+        } else {
+            pair[0].start > pair[1].start
+        };
+        out_of_order.then_some(pair[1])
+    })
+}
+
+/// Rewrites the clocks of each headline whose clocks are not in chronological
+/// order. The direction (newest- or oldest-first) that most of the headline's
+/// clocks already follow is kept. The clock lines stay where they are, only
+/// their content is swapped around.
+pub fn sort_clocks<'a>(doc: &OrgDocument<'a>) -> Vec<FileChange<'a>> {
+    let mut changes = Vec::new();
+    for (_, clocks) in doc.clocks_by_headline() {
+        if first_unsorted_clock(&clocks).is_none() {
+            continue;
+        }
+        let mut sorted = clocks.clone();
+        if newest_first(&clocks) {
+            sorted.sort_by_key(|clock| Reverse(clock.start));
+        } else {
+            sorted.sort_by_key(|clock| clock.start);
+        }
+        for (slot, clock) in clocks.iter().zip(sorted) {
+            if slot.line != clock.line {
+                let mut clock = clock.clone();
+                clock.line = slot.line;
+                changes.push(FileChange::update(&doc.file, clock));
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FileChange, FindingKind, OrgDocument};
+
+    #[test]
+    fn split_clock() {
+        let org_string = "
+* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 12:00] =>  2:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let time = |h| chrono::NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let changes = super::split_clock(&doc, 3, time(11)).unwrap();
+        assert_eq!(
+            doc.apply_changes(changes).unwrap(),
+            "
+* task
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 12:00] =>  1:00
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+"
+        );
+        assert!(super::split_clock(&doc, 3, time(13)).is_err());
+        assert!(super::split_clock(&doc, 2, time(11)).is_err());
+
+        // the added clock is indented like the split one
+        let org_string = "
+* task
+  :LOGBOOK:
+  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 12:00] =>  2:00
+  :END:
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let changes = super::split_clock(&doc, 4, time(11)).unwrap();
+        let split = doc.apply_changes(changes).unwrap();
+        assert_eq!(
+            split,
+            "
+* task
+  :LOGBOOK:
+  CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 12:00] =>  1:00
+  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+  :END:
+"
+        );
+        let doc = OrgDocument::parse("test.org", &split);
+        assert!(super::misaligned_clocks(&doc).is_empty());
+    }
+
+    #[test]
+    fn merge_three_adjacent_clocks() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:15] =>  0:15
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:00] =>  0:30
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+* bar
+CLOCK: [2022-12-12 Mon 11:15]--[2022-12-12 Mon 11:30] =>  0:15
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let changes = super::merge_adjacent(&doc);
+        assert_eq!(changes.len(), 3);
+
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:15] =>  1:15
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+* bar
+CLOCK: [2022-12-12 Mon 11:15]--[2022-12-12 Mon 11:30] =>  0:15
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn pad_minutes() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:03] =>  1:3
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let changes = super::pad_minutes(&doc);
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:03] =>  1:03
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn normalize_mixed_timestamp_types() {
+        let org_string = "
+* fooo
+CLOCK: [2021-04-18 Sun 00:57]--<2021-04-18 Sun 02:30> =>  1:33
+CLOCK: [2021-04-17 Sat 09:00]--[2021-04-17 Sat 09:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let changes = super::normalize_timestamp_types(&doc);
+        assert_eq!(changes.len(), 1);
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2021-04-18 Sun 00:57]--[2021-04-18 Sun 02:30] =>  1:33
+CLOCK: [2021-04-17 Sat 09:00]--[2021-04-17 Sat 09:30] =>  0:30
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn normalize_clocks() {
+        let org_string = "
+* fooo
+:LOGBOOK:
+  CLOCK:  [2021-04-18 sun  00:57]-- [2021-04-18 Sun, 02:30]=>1:3
+  CLOCK: [2021-04-17 Sat 09:00:15]--[2021-04-17 Sat 09:30:45] =>  0:30
+:END:
+CLOCK: [2021-04-16 Fri 09:00]
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let changes = super::normalize_clocks(&doc, None);
+        assert_eq!(changes.len(), 1);
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+:LOGBOOK:
+  CLOCK: [2021-04-18 Sun 00:57]--[2021-04-18 Sun 02:30] =>  1:33
+  CLOCK: [2021-04-17 Sat 09:00:15]--[2021-04-17 Sat 09:30:45] =>  0:30
+:END:
+CLOCK: [2021-04-16 Fri 09:00]
+";
+        assert_eq!(result, expected);
+
+        // already canonical, nothing to do
+        let doc = OrgDocument::parse("test.org", expected);
+        assert!(super::normalize_clocks(&doc, None).is_empty());
+        let changes = super::normalize_clocks(&doc, Some(crate::WeekdayLocale::De));
+        assert_eq!(changes.len(), 3);
+    }
+
+    #[test]
+    fn preview_duration_and_zero_fixes() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:00] =>  0:00
+CLOCK: [2022-12-12 Mon 08:00]--[2022-12-12 Mon 08:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let preview = super::preview_fixes(&doc);
+        assert!(preview.overlaps.is_empty());
+        assert_eq!(
+            preview.counts,
+            vec![("remove-zero-clocks", 1), ("fix-durations", 1)]
+        );
+        let result =
+            FileChange::apply_to_string(preview.changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+CLOCK: [2022-12-12 Mon 08:00]--[2022-12-12 Mon 08:30] =>  0:30
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn phantom_durations_are_zeroed_not_removed() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:00] =>  1:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert!(super::remove_zero_clocks(&doc).is_empty());
+        let preview = super::preview_fixes(&doc);
+        let result =
+            FileChange::apply_to_string(preview.changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:00] =>  0:00
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn preview_skips_overlapping_fixes() {
+        // the zero clock also has nonpadded minutes
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:00] =>  0:0
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let preview = super::preview_fixes(&doc);
+        assert_eq!(preview.counts, vec![("remove-zero-clocks", 1)]);
+        assert_eq!(
+            preview.overlaps,
+            vec![super::FixOverlap {
+                fixer: "pad-minutes",
+                other: "remove-zero-clocks",
+                line: 3
+            }]
+        );
+        let result =
+            FileChange::apply_to_string(preview.changes, org_string).expect("apply changes");
+        assert_eq!(result, "\n* fooo\n");
+    }
+
+    #[test]
+    fn preview_rejects_negative_clocks() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 10:00] =>  1:00
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 08:00] => -2:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let preview = super::preview_fixes(&doc);
+        assert!(preview.changes.is_empty());
+        assert_eq!(preview.rejected.len(), 1);
+        assert_eq!(preview.rejected[0].kind, FindingKind::WouldCreateNegative);
+        assert_eq!(preview.rejected[0].line, 3);
+        assert!(preview.rejected[0].message.contains("by merge-adjacent"));
+    }
+
+    #[test]
+    fn dedupe_tags() {
+        let org_string = "
+* task :work:work:urgent:
+** subtask :work:
+* other   :a:b:a:b:a:
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(doc.headlines[0].duplicate_tags(), vec!["work"]);
+        assert_eq!(doc.headlines[2].duplicate_tags(), vec!["a", "b"]);
+        let result = super::dedupe_tags(&doc, org_string);
+        let expected = "
+* task :work:urgent:
+** subtask :work:
+* other   :a:b:
+";
+        assert_eq!(result, expected);
+
+        let crlf = "* task :work:work:\r\n** clean :work:";
+        let doc = OrgDocument::parse("test.org", crlf);
+        assert_eq!(
+            super::dedupe_tags(&doc, crlf),
+            "* task :work:\r\n** clean :work:"
+        );
+        let clean = "* task :work:\r\n";
+        let doc = OrgDocument::parse("test.org", clean);
+        assert_eq!(super::dedupe_tags(&doc, clean), clean);
+    }
+
+    #[test]
+    fn close_running_under_done() {
+        let org_string = "
+* DONE task
+CLOSED: [2022-12-12 Mon 12:30]
+CLOCK: [2022-12-12 Mon 10:00]
+* DONE closed before clocking
+CLOSED: [2022-12-12 Mon 09:00]
+CLOCK: [2022-12-12 Mon 10:00]
+* TODO open
+CLOCK: [2022-12-12 Mon 10:00]
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let changes = super::close_running_under_done(&doc, &["DONE".to_string()]);
+        assert_eq!(changes.len(), 1);
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        assert_eq!(
+            result.lines().nth(3),
+            Some("CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 12:30] =>  2:30")
+        );
+    }
+
+    #[test]
+    fn remove_orphan_totals() {
+        let org_string = "
+* task
+:PROPERTIES:
+:ID: 1
+:TOTAL: 1:00
+:END:
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* moved away
+:PROPERTIES:
+:ID: 2
+:TOTAL: 2:00
+:END:
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let result = super::remove_orphan_totals(&doc, org_string, "TOTAL");
+        let expected = "
+* task
+:PROPERTIES:
+:ID: 1
+:TOTAL: 1:00
+:END:
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* moved away
+:PROPERTIES:
+:ID: 2
+:END:
+";
+        assert_eq!(result, expected);
+
+        let org_string =
+            "* done\r\nCLOSED: [2022-12-12 Mon 12:30]\r\n:PROPERTIES:\r\n:TOTAL: 2:00\r\n:END:";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let result = super::remove_orphan_totals(&doc, org_string, "TOTAL");
+        assert_eq!(
+            result,
+            "* done\r\nCLOSED: [2022-12-12 Mon 12:30]\r\n:PROPERTIES:\r\n:END:"
+        );
+    }
+
+    #[test]
+    fn normalize_clock_indentation() {
+        let org_string = "
+* task
+  :LOGBOOK:
+  CLOCK: [2022-12-12 Mon 12:00]--[2022-12-12 Mon 13:00] =>  1:00
+\tCLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30
+  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+  :END:
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let result = super::normalize_clock_indentation(&doc, org_string);
+        assert_eq!(result, org_string.replace('\t', "  "));
+    }
+
+    #[test]
+    fn clock_indentation_keeps_line_endings() {
+        let aligned = "* fooo\r\n  :LOGBOOK:\r\n  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30\r\n  :END:";
+        let doc = OrgDocument::parse("test.org", aligned);
+        assert_eq!(super::normalize_clock_indentation(&doc, aligned), aligned);
+
+        let misaligned = "* fooo\r\n  :LOGBOOK:\r\n  CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30\r\n  CLOCK: [2022-12-12 Mon 12:00]--[2022-12-12 Mon 12:30] =>  0:30\r\n CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30\r\n  :END:\r\n";
+        let doc = OrgDocument::parse("test.org", misaligned);
+        assert_eq!(
+            super::normalize_clock_indentation(&doc, misaligned),
+            misaligned.replace("\n CLOCK", "\n  CLOCK")
+        );
+    }
+
+    #[test]
+    fn fix_wrong_weekday() {
+        let org_string = "
+* fooo
+  CLOCK: [2022-12-12 Sun 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+  CLOCK: [2022-12-11 Sun 09:00]--[2022-12-11 Sun 09:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let result = super::fix_weekdays(&doc, org_string, None);
+        let expected = "
+* fooo
+  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+  CLOCK: [2022-12-11 Sun 09:00]--[2022-12-11 Sun 09:30] =>  0:30
+";
+        assert_eq!(result, expected);
+
+        let result = super::fix_weekdays(&doc, org_string, Some(crate::WeekdayLocale::De));
+        let expected = "
+* fooo
+  CLOCK: [2022-12-12 Mo 10:00]--[2022-12-12 Mo 11:00] =>  1:00
+  CLOCK: [2022-12-11 So 09:00]--[2022-12-11 So 09:30] =>  0:30
+";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn fix_weekday_keeps_rest_of_line() {
+        // odd spacing, a wrong total and CRLF line endings are left alone
+        let org_string =
+            "* fooo\r\nCLOCK:  [2022-12-12 Sun 10:00]--[2022-12-12  Sun 11:00]  => 0:30\r\n";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(
+            super::fix_weekdays(&doc, org_string, None),
+            "* fooo\r\nCLOCK:  [2022-12-12 Mon 10:00]--[2022-12-12  Mon 11:00]  => 0:30\r\n"
+        );
+    }
+
+    #[test]
+    fn sort_unsorted_clocks() {
+        let org_string = "
+* fooo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:15] =>  0:15
+CLOCK: [2022-12-12 Mon 08:00]--[2022-12-12 Mon 08:30] =>  0:30
+* bar
+CLOCK: [2022-12-12 Mon 11:15]--[2022-12-12 Mon 11:30] =>  0:15
+CLOCK: [2022-12-12 Mon 10:15]--[2022-12-12 Mon 10:30] =>  0:15
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let clocks = doc.clocks_by_headline();
+        assert_eq!(super::first_unsorted_clock(&clocks[&0]).unwrap().line, 5);
+        assert!(super::first_unsorted_clock(&clocks[&1]).is_none());
+
+        let changes = super::sort_clocks(&doc);
+        let result = FileChange::apply_to_string(changes, org_string).expect("apply changes");
+        let expected = "
+* fooo
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:15] =>  0:15
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+CLOCK: [2022-12-12 Mon 08:00]--[2022-12-12 Mon 08:30] =>  0:30
+* bar
+CLOCK: [2022-12-12 Mon 11:15]--[2022-12-12 Mon 11:30] =>  0:15
+CLOCK: [2022-12-12 Mon 10:15]--[2022-12-12 Mon 10:30] =>  0:15
+";
+        assert_eq!(result, expected);
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::Result;
+use chrono::Duration;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::{parse_duration, planning::Planning};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Headline<'a> {
     pub line: usize,
@@ -9,6 +12,70 @@ pub struct Headline<'a> {
     pub level: usize,
     pub title: &'a str,
     pub tags_string: Option<&'a str>,
+    /// Entries of the headline's `:PROPERTIES:` drawer in file order.
+    pub properties: Vec<(&'a str, &'a str)>,
+    /// Timestamps of the planning line directly below the headline.
+    pub planning: Planning,
+    /// The headline's line as written in the file, so that fixers can rewrite
+    /// parts of it without reformatting the rest.
+    pub raw: &'a str,
+}
+
+impl<'a> Headline<'a> {
+    /// The tags of the headline itself, without inherited ones.
+    pub fn tags(&self) -> impl Iterator<Item = &'a str> {
+        self.tags_string
+            .unwrap_or("")
+            .split(':')
+            .filter(|tag| !tag.is_empty())
+    }
+
+    /// Tags that occur more than once in the tag string, each once, in the
+    /// order of their first repetition.
+    pub fn duplicate_tags(&self) -> Vec<&'a str> {
+        let mut seen = Vec::new();
+        let mut duplicates = Vec::new();
+        for tag in self.tags() {
+            if seen.contains(&tag) {
+                if !duplicates.contains(&tag) {
+                    duplicates.push(tag);
+                }
+            } else {
+                seen.push(tag);
+            }
+        }
+        duplicates
+    }
+
+    /// The targets of the `[[file:PATH]]` and `[[file:PATH][description]]`
+    /// links in the title, without a `::` search option. Other link types are
+    /// skipped.
+    pub fn file_links(&self) -> impl Iterator<Item = &'a str> {
+        FILE_LINK_RE
+            .captures_iter(self.title)
+            .filter_map(|captures| captures.get(1))
+            .map(|path| path.as_str())
+    }
+
+    /// The first word of the title, e.g. `TODO` or `DONE`. Whether it is a
+    /// TODO keyword depends on the configured keywords.
+    pub fn keyword(&self) -> Option<&'a str> {
+        self.title.split_whitespace().next()
+    }
+
+    /// Looks up a property by name. Like org, names are case-insensitive.
+    pub fn property(&self, name: &str) -> Option<&'a str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+
+    /// The `:Effort:` property, e.g. `2:00`. `None` if missing or malformed.
+    pub fn effort(&self) -> Option<Duration> {
+        self.property("Effort")
+            .and_then(|effort| parse_duration(effort).ok())
+    }
 }
 
 impl<'a> std::fmt::Display for Headline<'a> {
@@ -21,11 +88,19 @@ impl<'a> std::fmt::Display for Headline<'a> {
     }
 }
 
+/// Cheap pre-check so that lines which cannot be a headline skip [`HEADLINE_RE`].
+#[inline]
+pub(crate) fn maybe_headline(line: &str, marker: char) -> bool {
+    line.starts_with(marker)
+}
+
+/// What follows the markers of a headline, e.g. `*` or `#` with
+/// `--heading-char`. Headlines start in the first column, the level is the
+/// number of markers.
 pub(crate) static HEADLINE_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
         r"(?ix)
-(\*+)   # parse *
-\s*
+^[\ \t]+
 (.+)    # title + tags
 ",
     )
@@ -37,38 +112,116 @@ pub(crate) static TITLE_TAGS_RE: Lazy<Regex> = Lazy::new(|| {
         r"(?ix)
 (.+?)
 \s+
-(:[^\s]+:)\s*$
+(:(?:[\w@\#%]+:)+)  # :tag1:tag2:, tags are words plus @, # and %
+\s*$
 ",
     )
-    .expect("clock re")
+    .expect("title tags re")
 });
 
+static FILE_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?x)
+\[\[file:
+([^\]]+?)       # path
+(?:::[^\]]*)?   # ::search option
+\]
+",
+    )
+    .expect("file link re")
+});
+
+pub(crate) static PROPERTY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?x)
+^\s*:([^:\s]+):   # :NAME:
+(?:\s+(.*?))?     # value
+\s*$
+",
+    )
+    .expect("property re")
+});
+
+/// Parses a line inside a `:PROPERTIES:` drawer into name and value.
+pub(crate) fn parse_property(line: &str) -> Option<(&str, &str)> {
+    let captures = PROPERTY_RE.captures(line)?;
+    let name = captures.get(1).unwrap().as_str();
+    let value = captures.get(2).map(|c| c.as_str()).unwrap_or("");
+    Some((name, value))
+}
+
+impl<'a> Headline<'a> {
+    /// Parses a headline whose level is marked with `marker` instead of `*`.
+    pub fn parse_with_marker(s: &'a str, marker: char) -> Result<Self> {
+        if !maybe_headline(s, marker) {
+            return Err(anyhow::anyhow!("Not a headline"));
+        }
+        let rest = s.trim_start_matches(marker);
+        let level = (s.len() - rest.len()) / marker.len_utf8();
+        match HEADLINE_RE.captures(rest) {
+            Some(captures) => Ok(Self::new(s, level, captures.get(1).unwrap().as_str())),
+            None => Err(anyhow::anyhow!("Not a headline")),
+        }
+    }
+
+    /// Parses a headline with a pattern checked by [`headline_pattern`]. The
+    /// level is the number of characters of the first group, the second group
+    /// is title and tags.
+    pub fn parse_with_pattern(s: &'a str, pattern: &Regex) -> Result<Self> {
+        let captures = pattern
+            .captures(s)
+            .ok_or_else(|| anyhow::anyhow!("Not a headline"))?;
+        let level = captures.get(1).map_or(0, |c| c.as_str().chars().count());
+        let title = captures.get(2).map_or("", |c| c.as_str());
+        if level == 0 || title.is_empty() {
+            return Err(anyhow::anyhow!("Not a headline"));
+        }
+        Ok(Self::new(s, level, title))
+    }
+
+    /// A headline of `level` from title and tags, e.g. `task :work:`, parsed
+    /// from the line `raw`.
+    fn new(raw: &'a str, level: usize, title: &'a str) -> Self {
+        let (title, tags_string) = if let Some(captures) = TITLE_TAGS_RE.captures(title) {
+            (
+                captures.get(1).unwrap().as_str(),
+                Some(captures.get(2).unwrap().as_str()),
+            )
+        } else {
+            (title, None)
+        };
+
+        Self {
+            line: 0,
+            parent: 0,
+            level,
+            title,
+            tags_string,
+            properties: Vec::new(),
+            planning: Planning::default(),
+            raw,
+        }
+    }
+}
+
+/// Compiles a custom headline pattern for [`Headline::parse_with_pattern`].
+/// It needs two capture groups: the level marker, e.g. `(\*+)`, and the
+/// title with tags.
+pub fn headline_pattern(pattern: &str) -> Result<Regex> {
+    let re = Regex::new(pattern)?;
+    // the whole match is group 0
+    if re.captures_len() < 3 {
+        return Err(anyhow::anyhow!(
+            "headline pattern {pattern:?} needs a capture group for the level marker and one for the title, e.g. ^(\\*+)\\s+(.+)"
+        ));
+    }
+    Ok(re)
+}
+
 impl<'a> TryFrom<&'a str> for Headline<'a> {
     type Error = anyhow::Error;
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
-        if let Some(captures) = HEADLINE_RE.captures(s) {
-            let level = captures.get(1).unwrap().as_str().len();
-            let title = captures.get(2).unwrap().as_str();
-
-            let (title, tags_string) = if let Some(captures) = TITLE_TAGS_RE.captures(title) {
-                (
-                    captures.get(1).unwrap().as_str(),
-                    Some(captures.get(2).unwrap().as_str()),
-                )
-            } else {
-                (title, None)
-            };
-
-            Ok(Self {
-                line: 0,
-                parent: 0,
-                level,
-                title,
-                tags_string,
-            })
-        } else {
-            Err(anyhow::anyhow!("Not a headline"))
-        }
+        Self::parse_with_marker(s, '*')
     }
 }
 
@@ -76,6 +229,34 @@ impl<'a> TryFrom<&'a str> for Headline<'a> {
 pub(crate) mod headline_tests {
     use super::Headline;
 
+    #[test]
+    fn raw_line() {
+        let line = "**   TODO  fix the   thing\t:work:urgent:  ";
+        let h = Headline::try_from(line).unwrap();
+        assert_eq!(h.raw, line);
+        assert_eq!(h.to_string(), "** TODO  fix the   thing :work:urgent:");
+
+        let org_string = "* a\n***  b  :x:\r\n# c\n";
+        let doc = crate::OrgDocument::parse("test.org", org_string);
+        let raw = doc.headlines.iter().map(|h| h.raw).collect::<Vec<_>>();
+        assert_eq!(raw, org_string.lines().take(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn custom_pattern() {
+        let pattern = super::headline_pattern(r"^(=+)\s+(.+?)\s*=*$").unwrap();
+        let h = Headline::parse_with_pattern("== chapter :draft: ==", &pattern).unwrap();
+        assert_eq!(
+            (h.level, h.title, h.tags_string),
+            (2, "chapter", Some(":draft:"))
+        );
+        assert!(Headline::parse_with_pattern("text == not a heading", &pattern).is_err());
+
+        let err = super::headline_pattern(r"^\*+ .+").unwrap_err();
+        assert!(err.to_string().contains("capture group"));
+        assert!(super::headline_pattern(r"^(\*+").is_err());
+    }
+
     #[test]
     fn test_parse_headline() {
         let h = Headline::try_from("* foo").unwrap();
@@ -86,5 +267,47 @@ pub(crate) mod headline_tests {
         assert_eq!(h.title, "foo: xxxx");
         assert_eq!(h.level, 3);
         assert_eq!(h.tags_string, Some(":bar:baz:"));
+        assert_eq!(h.tags().collect::<Vec<_>>(), vec!["bar", "baz"]);
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        let h = Headline::try_from("* foo: :work:").unwrap();
+        assert_eq!(h.title, "foo:");
+        assert_eq!(h.tags_string, Some(":work:"));
+
+        let h = Headline::try_from("* note ending in colon:").unwrap();
+        assert_eq!(h.title, "note ending in colon:");
+        assert_eq!(h.tags_string, None);
+
+        let h = Headline::try_from("* meeting with bob:   :@office:work_2:  ").unwrap();
+        assert_eq!(h.title, "meeting with bob:");
+        assert_eq!(h.tags().collect::<Vec<_>>(), vec!["@office", "work_2"]);
+
+        let h = Headline::try_from("* ratio :1:2: :").unwrap();
+        assert_eq!(h.title, "ratio :1:2: :");
+        assert_eq!(h.tags_string, None);
+    }
+
+    #[test]
+    fn test_not_a_headline() {
+        assert!(Headline::try_from("- * not a heading").is_err());
+        assert!(Headline::try_from("  * indented list item").is_err());
+        assert!(Headline::try_from("*bold* text").is_err());
+        assert!(Headline::try_from("text with a * star").is_err());
+    }
+
+    #[test]
+    fn test_parse_property() {
+        assert_eq!(
+            super::parse_property(":ID:       4a5b-11"),
+            Some(("ID", "4a5b-11"))
+        );
+        assert_eq!(
+            super::parse_property("  :Effort: 2:00"),
+            Some(("Effort", "2:00"))
+        );
+        assert_eq!(super::parse_property(":EMPTY:"), Some(("EMPTY", "")));
+        assert_eq!(super::parse_property("no property"), None);
     }
 }
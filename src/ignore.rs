@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+
+/// Glob patterns of files to skip, read from a file like `.gitignore`:
+///
+/// ```text
+/// # old stuff
+/// archive/*.org
+/// scratch.org
+/// ```
+///
+/// Patterns are matched against paths relative to the org directory. Patterns
+/// without a `/` also match the file name in any directory.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFile {
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreFile {
+    /// Name of the ignore file looked up in the org directory.
+    pub const FILE_NAME: &'static str = ".org-linter-ignore";
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let patterns = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                Pattern::new(line.trim_start_matches('/'))
+                    .with_context(|| format!("invalid pattern {line:?}"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn from_file(file: impl AsRef<Path>) -> Result<Self> {
+        let file = file.as_ref();
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("cannot read ignore file {file:?}"))?;
+        Self::parse(&content).with_context(|| format!("invalid ignore file {file:?}"))
+    }
+
+    /// Whether `path`, relative to the org directory, matches one of the
+    /// patterns.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let file_name = path.file_name().map(Path::new);
+        self.patterns.iter().any(|pattern| {
+            pattern.matches_path_with(path, options)
+                || (!pattern.as_str().contains('/')
+                    && file_name.is_some_and(|name| pattern.matches_path_with(name, options)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IgnoreFile;
+    use std::path::Path;
+
+    #[test]
+    fn ignore_patterns() {
+        let ignore = IgnoreFile::parse(
+            "
+# old stuff
+archive/*.org
+
+scratch.org
+",
+        )
+        .unwrap();
+        assert!(ignore.is_ignored(Path::new("archive/2021.org")));
+        assert!(!ignore.is_ignored(Path::new("archive/nested/2021.org")));
+        assert!(!ignore.is_ignored(Path::new("work.org")));
+        assert!(ignore.is_ignored(Path::new("scratch.org")));
+        assert!(ignore.is_ignored(Path::new("notes/scratch.org")));
+        assert!(IgnoreFile::parse("[").is_err());
+    }
+}
@@ -4,13 +4,63 @@ extern crate log;
 mod block;
 mod clock;
 mod clock_conflict;
+mod config;
+mod custom_check;
+mod fixes;
 mod headline;
+mod ignore;
+mod lint;
 mod org_document;
 mod org_file;
+mod pipeline;
+mod planning;
+mod query;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod state_change;
+mod stats;
+mod watch;
 
 pub use block::Block;
-pub use clock::Clock;
-pub use clock_conflict::{ClockConflict, FileChange};
-pub use headline::Headline;
-pub use org_document::OrgDocument;
-pub use org_file::OrgFile;
+pub use clock::{parse_duration, Clock, TimestampType, WeekdayLocale};
+pub use clock_conflict::{
+    resolve_all, resolve_conflicts_automatically, AppliedChange, AppliedResolution, ClockConflict,
+    ClockConflictIterator, ConflictResolution, ConflictSearchState, FileChange, ResolveNotice,
+    ResolveReport, WouldCreateNegative,
+};
+pub use config::Config;
+pub use custom_check::{run_custom_check, ClockJson, CustomFinding, DocumentJson, HeadlineJson};
+pub use fixes::{
+    close_running_under_done, dedupe_tags, fix_durations, fix_weekdays, merge_adjacent,
+    normalize_clock_indentation, normalize_clocks, normalize_timestamp_types, pad_minutes,
+    preview_fixes, remove_orphan_totals, remove_zero_clocks, sort_clocks, split_clock, FixOverlap,
+    FixPreview, Fixer, ALL_FIXERS,
+};
+pub use headline::{headline_pattern, Headline};
+pub use ignore::IgnoreFile;
+pub use lint::{
+    diff_findings, explain, lint_document, lint_documents, merge_findings, Finding, FindingKind,
+    FindingsDiff, KnownLongDuration, LintOptions, Location, Severity, Suppression,
+};
+pub use org_document::{
+    clocks_with_context, OrgDocument, ParseOptions, ParseWarning, StructuralError,
+};
+pub use org_file::{
+    archive_origin, content_hash, extract_org_from_markdown, follow_includes, includes,
+    is_markdown_file, OrgFile,
+};
+pub use pipeline::check_files_pipelined;
+pub use planning::{Planning, PlanningTimestamp};
+pub use query::{Comparison, Query};
+pub use schema::json_schema;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{export_sqlite, write_documents};
+pub use state_change::StateChange;
+pub use stats::{
+    effort_variances, format_decimal_hours, format_org_total, format_rounded, invoice_rows,
+    monthly_org_table, tag_usage, time_by_month, time_by_project, total_duration,
+    total_duration_including_running, uncovered_work_time, EffortVariance, InvoiceRow, LongSession,
+    Stats, Summary, TagUsage, WeeklySummary, WorkingHours,
+};
+pub use watch::{watch, WatchSession};
@@ -0,0 +1,2317 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    archive_origin,
+    fixes::{first_unsorted_clock, misaligned_clocks},
+    format_rounded, OrgDocument, ParseWarning, TimestampType,
+};
+
+/// How serious a finding is. Ordered from least to most severe.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(anyhow::anyhow!(
+                "unknown severity {s:?}, expected info, warning or error"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FindingKind {
+    DurationMismatch,
+    LongDuration,
+    RunningClock,
+    NegativeDuration,
+    ZeroDuration,
+    MissingId,
+    UnsortedClocks,
+    NonPaddedMinutes,
+    MultiDay,
+    ClockConflict,
+    MixedTimestampType,
+    OrphanTotal,
+    Custom,
+    SiblingOverlap,
+    WrongWeekday,
+    MisalignedClock,
+    CrossFileDuplicate,
+    LevelSkip,
+    HeadlessFile,
+    PhantomDuration,
+    MidnightPlaceholder,
+    MisplacedAppointment,
+    DuplicateTags,
+    TagDailyCap,
+    RunningUnderDone,
+    DstSpanning,
+    SplitSubtree,
+    WouldCreateNegative,
+    StaleArchiveStub,
+    StaleRunning,
+    BrokenLink,
+    StaleClocking,
+    ParseWarning,
+    RoundingInconsistency,
+}
+
+impl FindingKind {
+    pub const ALL: &'static [FindingKind] = &[
+        FindingKind::DurationMismatch,
+        FindingKind::LongDuration,
+        FindingKind::RunningClock,
+        FindingKind::NegativeDuration,
+        FindingKind::ZeroDuration,
+        FindingKind::MissingId,
+        FindingKind::UnsortedClocks,
+        FindingKind::NonPaddedMinutes,
+        FindingKind::MultiDay,
+        FindingKind::ClockConflict,
+        FindingKind::MixedTimestampType,
+        FindingKind::OrphanTotal,
+        FindingKind::Custom,
+        FindingKind::SiblingOverlap,
+        FindingKind::WrongWeekday,
+        FindingKind::MisalignedClock,
+        FindingKind::CrossFileDuplicate,
+        FindingKind::LevelSkip,
+        FindingKind::HeadlessFile,
+        FindingKind::PhantomDuration,
+        FindingKind::MidnightPlaceholder,
+        FindingKind::MisplacedAppointment,
+        FindingKind::DuplicateTags,
+        FindingKind::TagDailyCap,
+        FindingKind::RunningUnderDone,
+        FindingKind::DstSpanning,
+        FindingKind::SplitSubtree,
+        FindingKind::WouldCreateNegative,
+        FindingKind::StaleArchiveStub,
+        FindingKind::StaleRunning,
+        FindingKind::BrokenLink,
+        FindingKind::StaleClocking,
+        FindingKind::ParseWarning,
+        FindingKind::RoundingInconsistency,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FindingKind::DurationMismatch => "duration-mismatch",
+            FindingKind::LongDuration => "long-duration",
+            FindingKind::RunningClock => "running-clock",
+            FindingKind::NegativeDuration => "negative-duration",
+            FindingKind::ZeroDuration => "zero-duration",
+            FindingKind::MissingId => "missing-id",
+            FindingKind::UnsortedClocks => "unsorted-clocks",
+            FindingKind::NonPaddedMinutes => "nonpadded-minutes",
+            FindingKind::MultiDay => "multiday",
+            FindingKind::ClockConflict => "clock-conflict",
+            FindingKind::MixedTimestampType => "mixed-timestamp-type",
+            FindingKind::OrphanTotal => "orphan-total",
+            FindingKind::Custom => "custom",
+            FindingKind::SiblingOverlap => "sibling-overlap",
+            FindingKind::WrongWeekday => "wrong-weekday",
+            FindingKind::MisalignedClock => "misaligned-clock",
+            FindingKind::CrossFileDuplicate => "cross-file-duplicate",
+            FindingKind::LevelSkip => "level-skip",
+            FindingKind::HeadlessFile => "headless-file",
+            FindingKind::PhantomDuration => "phantom-duration",
+            FindingKind::MidnightPlaceholder => "midnight-placeholder",
+            FindingKind::MisplacedAppointment => "misplaced-appointment",
+            FindingKind::DuplicateTags => "duplicate-tags",
+            FindingKind::TagDailyCap => "tag-daily-cap",
+            FindingKind::RunningUnderDone => "running-under-done",
+            FindingKind::DstSpanning => "dst-spanning",
+            FindingKind::SplitSubtree => "split-subtree",
+            FindingKind::WouldCreateNegative => "would-create-negative",
+            FindingKind::StaleArchiveStub => "stale-archive-stub",
+            FindingKind::StaleRunning => "stale-running",
+            FindingKind::BrokenLink => "broken-link",
+            FindingKind::StaleClocking => "stale-clocking",
+            FindingKind::ParseWarning => "parse-warning",
+            FindingKind::RoundingInconsistency => "rounding-inconsistency",
+        }
+    }
+
+    /// The severity of findings of this kind unless configured otherwise.
+    /// Errors are most likely wrong data, warnings are suspicious and infos
+    /// are about style.
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            FindingKind::DurationMismatch
+            | FindingKind::NegativeDuration
+            | FindingKind::ClockConflict
+            | FindingKind::MixedTimestampType
+            | FindingKind::SiblingOverlap
+            | FindingKind::HeadlessFile
+            | FindingKind::PhantomDuration
+            | FindingKind::CrossFileDuplicate
+            | FindingKind::WouldCreateNegative
+            | FindingKind::ParseWarning
+            | FindingKind::RoundingInconsistency => Severity::Error,
+            FindingKind::LongDuration
+            | FindingKind::RunningClock
+            | FindingKind::ZeroDuration
+            | FindingKind::UnsortedClocks
+            | FindingKind::MultiDay
+            | FindingKind::OrphanTotal
+            | FindingKind::WrongWeekday
+            | FindingKind::LevelSkip
+            | FindingKind::MidnightPlaceholder
+            | FindingKind::MisplacedAppointment
+            | FindingKind::TagDailyCap
+            | FindingKind::SplitSubtree
+            | FindingKind::RunningUnderDone
+            | FindingKind::StaleArchiveStub
+            | FindingKind::StaleRunning
+            | FindingKind::BrokenLink
+            | FindingKind::StaleClocking
+            | FindingKind::Custom => Severity::Warning,
+            FindingKind::MissingId
+            | FindingKind::NonPaddedMinutes
+            | FindingKind::MisalignedClock
+            | FindingKind::DuplicateTags
+            | FindingKind::DstSpanning => Severity::Info,
+        }
+    }
+
+    /// What the check means and what usually causes it, for `--explain`.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            FindingKind::DurationMismatch => {
+                "The total after `=>` does not match the time between start and end of the clock. \
+                 This usually happens when timestamps are edited manually without updating the total. \
+                 Pressing C-c C-c on the clock line in Emacs recomputes it."
+            }
+            FindingKind::LongDuration => {
+                "The clock is longer than --long-duration (or as long with --long-duration-inclusive). Most often a clock-out was forgotten \
+                 and the clock was closed much later."
+            }
+            FindingKind::RunningClock => {
+                "The clock has a start but no end timestamp, i.e. it is still running. \
+                 Left-over running clocks typically come from a crashed or closed Emacs session."
+            }
+            FindingKind::NegativeDuration => {
+                "The end timestamp of the clock is before its start. \
+                 This is almost always a typo in one of the timestamps, e.g. a wrong day or hour."
+            }
+            FindingKind::ZeroDuration => {
+                "Start and end of the clock are the same. Such clocks are created when clocking in \
+                 and out right away and can usually be deleted."
+            }
+            FindingKind::MissingId => {
+                "The headline has clocks but no ID property, which makes it hard to reference \
+                 the logged time later. `org-id-get-create` adds one."
+            }
+            FindingKind::UnsortedClocks => {
+                "The clocks of the headline are not in chronological order. This happens when clocks \
+                 are moved or pasted between headlines. `--fix-sort-clocks` restores the order."
+            }
+            FindingKind::NonPaddedMinutes => {
+                "The minutes of the `=>` total are not zero padded, e.g. `1:3` instead of `1:03`. \
+                 The total is still correct, this is only about formatting and usually comes from \
+                 editing the total by hand. `fix --pad-minutes` normalizes it."
+            }
+            FindingKind::MultiDay => {
+                "The clock spans more calendar days than --report-multiday allows. Such clocks are \
+                 usually a forgotten clock-out that was closed manually days later."
+            }
+            FindingKind::ClockConflict => {
+                "Two clocks overlap, i.e. the same time was logged twice. Usually one of the clocks \
+                 was not stopped when switching tasks. `fix --conflicts` helps resolving them."
+            }
+            FindingKind::MixedTimestampType => {
+                "The start and end timestamp of a clock use different types, e.g. [...]--<...>. Org \
+                 always writes clocks with inactive timestamps, so one of them was most likely edited \
+                 by hand. fix --normalize-timestamp-types rewrites both as inactive."
+            }
+            FindingKind::OrphanTotal => {
+                "The headline has a cached total property but neither it nor its children have any \
+                 clocks. This is usually left over after clocks were moved to another headline while \
+                 reorganizing. fix --remove-orphan-totals deletes the property."
+            }
+            FindingKind::Custom => {
+                "Reported by an external --custom-check command. See the message for details and the \
+                 documentation of the command for what it checks."
+            }
+            FindingKind::SiblingOverlap => {
+                "Two sibling headlines, i.e. tasks with the same parent, have clocks that overlap. \
+                 You can't work on two subtasks of a project at once, so usually a clock was not \
+                 stopped when switching tasks."
+            }
+            FindingKind::WrongWeekday => {
+                "A day of week in a clock timestamp doesn't match its date, e.g. Sun for a Monday. \
+                 This happens when the date of a timestamp is edited by hand without updating the day \
+                 name. --fix-weekday corrects it."
+            }
+            FindingKind::MisalignedClock => {
+                "A clock in a drawer is indented differently than the other clocks of the drawer. \
+                 Emacs logs clocks consistently, so a misaligned clock was most likely pasted in by \
+                 hand. fix --clock-indentation aligns it with its siblings."
+            }
+            FindingKind::CrossFileDuplicate => {
+                "A clock with the same start and end exists in another file. This usually means a \
+                 file was copied, e.g. when duplicating a project file, so its time is counted twice. \
+                 Delete the stray copy or the duplicated clocks."
+            }
+            FindingKind::LevelSkip => {
+                "A headline is more than one level deeper than its parent, e.g. a *** headline \
+                 directly below a * headline. Org outlines are expected to nest one level at a time, \
+                 skipped levels confuse the agenda, exports and structure editing. Usually a headline \
+                 was promoted or moved without adjusting its children."
+            }
+            FindingKind::HeadlessFile => {
+                "The file has clock lines but no headlines at all, so none of its clocks belong to a \
+                 task and all of its time is dropped. The file is probably malformed, e.g. a fragment \
+                 that was split off, or not meant to be an org file."
+            }
+            FindingKind::PhantomDuration => {
+                "Start and end of the clock are the same but the total after `=>` claims some time, \
+                 e.g. [10:00]--[10:00] => 1:00. Most likely one of the timestamps was mistyped, so \
+                 check which one is wrong. fix --all only zeroes the total and keeps the clock for \
+                 review."
+            }
+            FindingKind::MidnightPlaceholder => {
+                "A running clock starts exactly at --placeholder-time, 00:00 by default. Such clocks \
+                 are usually left over from capture templates and were never actually started, real \
+                 work rarely begins at midnight. Delete the clock if nobody worked on the task."
+            }
+            FindingKind::MisplacedAppointment => {
+                "A clock uses an active timestamp <...> and starts in the future. Clocks log time \
+                 that was spent, so this is most likely an appointment or a scheduled date that ended \
+                 up in a LOGBOOK, e.g. by typing it in the wrong place. Move it out of the drawer \
+                 into the entry, where the agenda picks it up."
+            }
+            FindingKind::DuplicateTags => {
+                "A headline has the same tag more than once, e.g. :work:work:urgent:. It's harmless \
+                 for org, but tag based reports may count the headline's time twice unless they \
+                 deduplicate. Usually the tag was added again by hand or while merging headlines. fix \
+                 --dedupe-tags keeps the first of each tag."
+            }
+            FindingKind::TagDailyCap => {
+                "The clocks of headlines with a tag, or below a headline or in a file with it, add up \
+                 to more than the --report-tag-daily-cap of the tag on one day, across all files. For \
+                 billing caps this is time that can't be invoiced, usually a clock was left running \
+                 or the same time was logged under two tasks."
+            }
+            FindingKind::RunningUnderDone => {
+                "A clock is still running although its headline or one of its ancestors is in a done \
+                 state like DONE (see --done-keyword). You can't still be working on a completed \
+                 task, so most likely the clock was not stopped when the task was closed. fix \
+                 --close-running-under-done ends it at the CLOSED time of the headline."
+            }
+            FindingKind::DstSpanning => {
+                "The clock crosses a daylight saving time change, so its wall clock time (end minus \
+                 start as written) and the time that actually passed differ by the DST shift. Either \
+                 value is accepted as => total and not reported as duration mismatch. This note only \
+                 points out that the clock's length depends on how it is counted."
+            }
+            FindingKind::SplitSubtree => {
+                "A headline with clocks is in both a file and its archive file, e.g. foo.org and \
+                 foo_archive.org. Usually archiving the subtree was incomplete and left clocks \
+                 behind."
+            }
+            FindingKind::WouldCreateNegative => {
+                "A fixer or conflict resolution would have written a clock that ends before it \
+                 starts. The change is not applied, usually one of the original clocks is already \
+                 broken."
+            }
+            FindingKind::StaleArchiveStub => {
+                "A clock newer than --report-stale-archive-stub below a done headline with the \
+                 ARCHIVE tag. Such stubs are left behind when archiving, new time on them was most \
+                 likely meant for another headline or should be archived too."
+            }
+            FindingKind::StaleRunning => {
+                "A clock has been running for longer than --report-stale-running, most likely it was \
+                 forgotten. The time is measured up to --now if given."
+            }
+            FindingKind::BrokenLink => {
+                "A headline title links to a file that does not exist, e.g. \
+                 [[file:~/projects/foo][foo]]. Relative paths are resolved against the directory of \
+                 the org file."
+            }
+            FindingKind::StaleClocking => {
+                "The file was modified long after its newest clock, per --report-stale-clocking. \
+                 Maybe notes were added without clocking the time, or a tool rewrote the file."
+            }
+            FindingKind::ParseWarning => {
+                "The parser had to skip or guess something, e.g. a clock before the first headline, \
+                 an unparsable clock line or a block that is never closed. Only reported with \
+                 --strict, otherwise it is a log message."
+            }
+            FindingKind::RoundingInconsistency => {
+                "The => total of a clock with seconds is neither the duration rounded down nor \
+                 rounded to the nearest minute. Org computes the total from the exact times, so the \
+                 total was likely edited or imported from another tool. fix --all rewrites it \
+                 to the total rounded down."
+            }
+        }
+    }
+}
+
+impl FromStr for FindingKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FindingKind::ALL
+            .iter()
+            .find(|kind| kind.name() == s)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("unknown finding kind {s:?}"))
+    }
+}
+
+impl std::fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for FindingKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for FindingKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Explanations for the kinds of `findings`, one paragraph per kind.
+pub fn explain<'a>(findings: impl IntoIterator<Item = &'a Finding>) -> String {
+    let kinds = findings
+        .into_iter()
+        .map(|finding| finding.kind)
+        .collect::<HashSet<_>>();
+    let mut result = String::new();
+    for kind in FindingKind::ALL {
+        if kinds.contains(kind) {
+            result.push_str(&format!("{}:\n  {}\n", kind.name(), kind.explanation()));
+        }
+    }
+    result
+}
+
+/// A line in a file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+/// Why a finding is not reported. Suppressed findings are only emitted with
+/// [`LintOptions::show_suppressed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Suppression {
+    /// A long clock listed in [`LintOptions::known_long_durations`].
+    Allowlist,
+}
+
+impl Suppression {
+    pub const ALL: &'static [Suppression] = &[Suppression::Allowlist];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Suppression::Allowlist => "allowlist",
+        }
+    }
+}
+
+/// A single problem found in an org file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    #[serde(default)]
+    pub severity: Severity,
+    pub file: PathBuf,
+    pub line: usize,
+    pub message: String,
+    /// A second location involved, e.g. the other clock of a conflict.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub related: Option<Location>,
+    /// Set if the finding would not have been reported without
+    /// [`LintOptions::show_suppressed`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppressed: Option<Suppression>,
+    /// How likely the finding is a real problem, from 0 to 1. Below 1 only
+    /// for heuristic checks, see [`confidence_past`]. Omitted in JSON if 1.
+    #[serde(default = "certain", skip_serializing_if = "is_certain")]
+    pub confidence: f32,
+    /// [`Finding::fingerprint_in`] the org directory of the run that found
+    /// it, so that runs on different machines can be compared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+// confidence is never NaN, see `Finding::with_confidence`
+impl Eq for Finding {}
+
+fn certain() -> f32 {
+    1.0
+}
+
+fn is_certain(confidence: &f32) -> bool {
+    *confidence == 1.0
+}
+
+/// Confidence of a heuristic that fires when `value` is past `threshold`:
+/// 0.5 right at the threshold, rising to 1 at twice the threshold.
+pub fn confidence_past(value: f64, threshold: f64) -> f32 {
+    if threshold <= 0.0 {
+        return 1.0;
+    }
+    (value / (2.0 * threshold)).clamp(0.0, 1.0) as f32
+}
+
+impl Finding {
+    pub fn new(
+        kind: FindingKind,
+        file: impl Into<PathBuf>,
+        line: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            severity: kind.default_severity(),
+            file: file.into(),
+            line,
+            message: message.into(),
+            related: None,
+            suppressed: None,
+            confidence: 1.0,
+            fingerprint: None,
+        }
+    }
+
+    /// Sets [`Finding::confidence`], clamped to 0..=1. NaN counts as 1 so
+    /// that a broken heuristic doesn't hide findings.
+    pub fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence = if confidence.is_nan() {
+            1.0
+        } else {
+            confidence.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    pub fn with_related(mut self, file: impl Into<PathBuf>, line: usize) -> Self {
+        self.related = Some(Location {
+            file: file.into(),
+            line,
+        });
+        self
+    }
+
+    pub fn suppressed_by(mut self, suppression: Suppression) -> Self {
+        self.suppressed = Some(suppression);
+        self
+    }
+
+    pub fn file_name(&self) -> &str {
+        self.file.file_name().and_then(|f| f.to_str()).unwrap_or("")
+    }
+
+    /// Identifies the finding independent of where the org directory is
+    /// stored and of lines added or removed above it: a hex FNV-1a hash of
+    /// kind, the path relative to `org_dir` (the file name for files outside of
+    /// it) and message. It is the same across runs, machines and versions of
+    /// org-linter.
+    pub fn fingerprint_in(&self, org_dir: &Path) -> String {
+        let path = match self.file.strip_prefix(org_dir) {
+            Ok(relative) => relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => self.file_name().to_string(),
+        };
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for part in [self.kind.name(), &path, &self.message] {
+            for byte in part.bytes().chain(std::iter::once(0)) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        format!("{hash:016x}")
+    }
+
+    /// Sets [`Finding::fingerprint`] for a finding in `org_dir`.
+    pub fn with_fingerprint(mut self, org_dir: &Path) -> Self {
+        self.fingerprint = Some(self.fingerprint_in(org_dir));
+        self
+    }
+
+    /// [`Finding::fingerprint`], or for findings without one, e.g. in the
+    /// output of older versions, the fingerprint by file name.
+    pub fn identity(&self) -> String {
+        match &self.fingerprint {
+            Some(fingerprint) => fingerprint.clone(),
+            None => self.fingerprint_in(Path::new("")),
+        }
+    }
+}
+
+/// The union of findings from several runs, e.g. on different machines.
+/// Findings with the same [`Finding::identity`] are kept once. Sorted by
+/// file name, line and kind.
+pub fn merge_findings(runs: impl IntoIterator<Item = Vec<Finding>>) -> Vec<Finding> {
+    let mut seen = HashSet::new();
+    let mut merged = runs
+        .into_iter()
+        .flatten()
+        .filter(|finding| seen.insert(finding.identity()))
+        .collect::<Vec<_>>();
+    merged.sort_by_cached_key(|f| {
+        (
+            f.file_name().to_string(),
+            f.line,
+            f.kind.name(),
+            f.message.clone(),
+        )
+    });
+    merged
+}
+
+/// Findings that differ between two runs, see [`diff_findings`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FindingsDiff {
+    /// Only in the new run.
+    pub added: Vec<Finding>,
+    /// Only in the old run, i.e. fixed.
+    pub removed: Vec<Finding>,
+}
+
+/// Compares the findings of two runs, e.g. on two versions of the org files,
+/// by [`Finding::identity`]. Both sides are deduplicated and sorted like
+/// [`merge_findings`].
+pub fn diff_findings(old: &[Finding], new: &[Finding]) -> FindingsDiff {
+    let old_fingerprints = old.iter().map(Finding::identity).collect::<HashSet<_>>();
+    let new_fingerprints = new.iter().map(Finding::identity).collect::<HashSet<_>>();
+    FindingsDiff {
+        added: merge_findings([new
+            .iter()
+            .filter(|f| !old_fingerprints.contains(&f.identity()))
+            .cloned()
+            .collect()]),
+        removed: merge_findings([old
+            .iter()
+            .filter(|f| !new_fingerprints.contains(&f.identity()))
+            .cloned()
+            .collect()]),
+    }
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}:{}] {}", self.file_name(), self.line, self.message)?;
+        if let Some(suppression) = self.suppressed {
+            write!(f, " (suppressed: {})", suppression.name())?;
+        }
+        Ok(())
+    }
+}
+
+/// A long clock that is known to be correct and should not be reported.
+pub struct KnownLongDuration {
+    pub file: &'static str,
+    pub duration: &'static str,
+    pub title: &'static str,
+}
+
+#[derive(Clone)]
+pub struct LintOptions {
+    pub report_long_duration: bool,
+    /// Clocks longer than this are reported. Clocks of exactly this duration
+    /// are only reported with `long_duration_inclusive`.
+    pub long_duration: Duration,
+    pub long_duration_inclusive: bool,
+    pub known_long_durations: &'static [KnownLongDuration],
+    pub report_duration_mismatch: bool,
+    pub report_running_clock: bool,
+    pub report_negative_duration: bool,
+    pub report_zero_clocks: bool,
+    /// Report clocks with zero time but a nonzero total. Such clocks are
+    /// neither reported as duration mismatch nor as zero clocks.
+    pub report_phantom_duration: bool,
+    pub report_unsorted_clocks: bool,
+    pub report_nonpadded_minutes: bool,
+    pub report_missing_id: bool,
+    /// Name of the property checked by `report_missing_id`.
+    pub id_property: String,
+    /// Report clocks spanning more than this many calendar days.
+    pub report_multiday: Option<i64>,
+    pub report_mixed_timestamp_type: bool,
+    pub report_orphan_total: bool,
+    /// Name of the property checked by `report_orphan_total`.
+    pub total_property: String,
+    pub report_sibling_overlap: bool,
+    /// Severities overriding [`FindingKind::default_severity`].
+    pub severities: HashMap<FindingKind, Severity>,
+    pub report_wrong_weekday: bool,
+    pub report_clock_indentation: bool,
+    pub report_cross_file_duplicate: bool,
+    /// Report headlines with clocks in both a file and its archive file, see
+    /// [`crate::archive_origin`].
+    pub report_split_subtree: bool,
+    pub report_level_skip: bool,
+    pub report_headless_file: bool,
+    /// Report running clocks that start at `placeholder_time`.
+    pub report_midnight_placeholder: bool,
+    pub placeholder_time: NaiveTime,
+    /// Report clocks with an active start timestamp after `now`.
+    pub report_misplaced_appointment: bool,
+    pub report_duplicate_tags: bool,
+    /// Report days on which the clocks of a tag, including inherited ones,
+    /// add up to more than its cap. Across all documents.
+    pub tag_daily_caps: Vec<(String, Duration)>,
+    pub report_running_under_done: bool,
+    /// TODO keywords of finished tasks, for `report_running_under_done`.
+    pub done_keywords: Vec<String>,
+    /// Note clocks that cross a DST change, see [`crate::Clock::spans_dst`].
+    pub report_dst_spanning: bool,
+    /// Report clocks with seconds whose total is neither rounded down nor
+    /// rounded, see [`crate::Clock::rounded_totals`], instead of as a
+    /// duration mismatch.
+    pub report_rounding_inconsistency: bool,
+    /// Report clocks starting on or after this date below a done headline
+    /// (see `done_keywords`) tagged `:ARCHIVE:`.
+    pub report_stale_archive_stub: Option<NaiveDate>,
+    /// Drop findings with a lower [`Finding::confidence`].
+    pub min_confidence: f32,
+    /// Emit findings that are suppressed, e.g. by `known_long_durations`,
+    /// marked with the reason, see [`Finding::suppressed`].
+    pub show_suppressed: bool,
+    /// Treat warnings as errors: parser warnings become findings, headlines
+    /// that skip a level are reported and findings of warning severity are
+    /// errors.
+    pub strict: bool,
+    /// Report files modified more than this many days after their newest
+    /// clock, see [`OrgDocument::modified`].
+    pub report_stale_clocking: Option<u32>,
+    /// Report headline titles with `file:` links to files that do not exist.
+    pub report_broken_links: bool,
+    /// Report running clocks that started longer than this before `now`.
+    pub report_stale_running: Option<Duration>,
+    /// The current time, for checks about running clocks and clocks in the
+    /// future.
+    pub now: NaiveDateTime,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            report_long_duration: true,
+            long_duration: Duration::hours(10),
+            long_duration_inclusive: false,
+            known_long_durations: &[],
+            report_duration_mismatch: true,
+            report_running_clock: false,
+            report_negative_duration: true,
+            report_zero_clocks: true,
+            report_phantom_duration: true,
+            report_unsorted_clocks: false,
+            report_nonpadded_minutes: false,
+            report_missing_id: false,
+            id_property: "ID".to_string(),
+            report_multiday: None,
+            report_mixed_timestamp_type: false,
+            report_orphan_total: false,
+            total_property: "TOTAL".to_string(),
+            report_sibling_overlap: false,
+            severities: HashMap::new(),
+            report_wrong_weekday: false,
+            report_clock_indentation: false,
+            report_cross_file_duplicate: false,
+            report_split_subtree: false,
+            report_level_skip: false,
+            report_headless_file: false,
+            report_midnight_placeholder: false,
+            placeholder_time: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+            report_misplaced_appointment: false,
+            report_duplicate_tags: false,
+            tag_daily_caps: Vec::new(),
+            report_running_under_done: false,
+            report_dst_spanning: false,
+            report_rounding_inconsistency: false,
+            report_stale_archive_stub: None,
+            report_stale_running: None,
+            report_broken_links: false,
+            report_stale_clocking: None,
+            strict: false,
+            show_suppressed: false,
+            min_confidence: 0.0,
+            done_keywords: vec!["DONE".to_string()],
+            now: Local::now().naive_local(),
+        }
+    }
+}
+
+impl LintOptions {
+    /// [`LintOptions::now`] in UTC, for comparing with clocks, see
+    /// [`crate::Clock::overlaps`].
+    pub fn now_utc(&self) -> DateTime<Utc> {
+        Local.from_local_datetime(&self.now).earliest().map_or_else(
+            || Utc.from_utc_datetime(&self.now),
+            |now| now.with_timezone(&Utc),
+        )
+    }
+
+    /// The configured severity of `kind`, raised to an error with
+    /// [`LintOptions::strict`] if it is a warning.
+    pub fn severity(&self, kind: FindingKind) -> Severity {
+        let severity = self
+            .severities
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| kind.default_severity());
+        if self.strict && severity == Severity::Warning {
+            Severity::Error
+        } else {
+            severity
+        }
+    }
+
+    /// Sets the configured severity on each of `findings`.
+    pub fn classify(&self, findings: &mut [Finding]) {
+        for finding in findings {
+            finding.severity = self.severity(finding.kind);
+        }
+    }
+}
+
+/// Runs the per-document checks enabled in `opts`.
+pub fn lint_document(doc: &OrgDocument, opts: &LintOptions) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let file = &doc.file;
+    let file_name = doc.file_name();
+
+    for clock in &doc.clocks {
+        let duration_string_raw = clock.duration_string.unwrap_or("");
+        let duration_string = clock.duration_formatted();
+        let headline = &doc.headlines[clock.parent];
+        let title = headline.title;
+        let line = clock.line;
+
+        let phantom = opts.report_phantom_duration && clock.has_phantom_duration();
+        if phantom {
+            findings.push(Finding::new(
+                FindingKind::PhantomDuration,
+                file,
+                line,
+                format!("PHANTOM DURATION {title:?}: no time between start and end but {duration_string_raw}"),
+            ));
+        }
+
+        let rounding = clock
+            .rounded_totals()
+            .filter(|_| opts.report_rounding_inconsistency && !phantom);
+        if let (Some((floor, round)), Some(claimed)) = (rounding, clock.claimed_duration()) {
+            if claimed != floor && claimed != round {
+                findings.push(Finding::new(
+                    FindingKind::RoundingInconsistency,
+                    file,
+                    line,
+                    format!(
+                        "ROUNDING INCONSISTENCY {title:?}: {duration_string_raw} matches neither {} (rounded down) nor {} (rounded)",
+                        format_rounded(floor, 1),
+                        format_rounded(round, 1)
+                    ),
+                ));
+            }
+        } else if opts.report_duration_mismatch && !phantom && !clock.matches_duration() {
+            findings.push(Finding::new(
+                FindingKind::DurationMismatch,
+                file,
+                line,
+                format!("DURATION STRING DOES NOT MATCH: {title:?} ({duration_string_raw} vs {duration_string})"),
+            ));
+        };
+
+        if opts.report_dst_spanning && clock.spans_dst() {
+            findings.push(Finding::new(
+                FindingKind::DstSpanning,
+                file,
+                line,
+                format!("DST SPANNING: {clock} in {title:?} crosses a DST change"),
+            ));
+        }
+
+        if opts.report_nonpadded_minutes && clock.has_nonpadded_minutes() {
+            findings.push(Finding::new(
+                FindingKind::NonPaddedMinutes,
+                file,
+                line,
+                format!(
+                    "NONPADDED MINUTES: {title:?} ({duration_string_raw} vs {duration_string})"
+                ),
+            ));
+        }
+
+        if opts.report_wrong_weekday && clock.has_wrong_weekday() {
+            findings.push(Finding::new(
+                FindingKind::WrongWeekday,
+                file,
+                line,
+                format!("WRONG WEEKDAY: {clock} in {title:?}"),
+            ));
+        }
+
+        if opts.report_mixed_timestamp_type && clock.has_mixed_timestamp_types() {
+            findings.push(Finding::new(
+                FindingKind::MixedTimestampType,
+                file,
+                line,
+                format!("MIXED TIMESTAMP TYPES: {clock} in {title:?}"),
+            ));
+        }
+
+        let long = if opts.long_duration_inclusive {
+            clock.duration() >= opts.long_duration
+        } else {
+            clock.duration() > opts.long_duration
+        };
+        if opts.report_long_duration && long {
+            let allowed = opts.known_long_durations.iter().any(|k| {
+                file_name.ends_with(k.file) && title == k.title && k.duration == duration_string
+            });
+            if !allowed || opts.show_suppressed {
+                let finding = Finding::new(
+                    FindingKind::LongDuration,
+                    file,
+                    line,
+                    format!("LONG DURATION: {duration_string} in {title:?}"),
+                )
+                .with_confidence(confidence_past(
+                    clock.duration().num_minutes() as f64,
+                    opts.long_duration.num_minutes() as f64,
+                ));
+                findings.push(if allowed {
+                    finding.suppressed_by(Suppression::Allowlist)
+                } else {
+                    finding
+                });
+            }
+        }
+
+        if let Some(max_days) = opts.report_multiday {
+            if let Some(days) = clock.calendar_days().filter(|days| *days > max_days) {
+                findings.push(Finding::new(
+                    FindingKind::MultiDay,
+                    file,
+                    line,
+                    format!("MULTIDAY CLOCK: spans {days} days in {title:?}"),
+                ));
+            }
+        }
+
+        if opts.report_midnight_placeholder
+            && clock.is_running()
+            && clock.start.time() == opts.placeholder_time
+        {
+            // a clock started at midnight is more likely a placeholder the
+            // longer it runs, right after midnight someone may be working
+            let running = opts.now - clock.start;
+            findings.push(
+                Finding::new(
+                    FindingKind::MidnightPlaceholder,
+                    file,
+                    line,
+                    format!("PLACEHOLDER CLOCK {title:?}: running since {clock}"),
+                )
+                .with_confidence(confidence_past(running.num_minutes() as f64, 12.0 * 60.0)),
+            );
+        }
+
+        if opts.report_misplaced_appointment
+            && clock.timestamp_type == TimestampType::Active
+            && clock.start > opts.now
+        {
+            findings.push(Finding::new(
+                FindingKind::MisplacedAppointment,
+                file,
+                line,
+                format!(
+                    "MISPLACED APPOINTMENT {title:?}: {clock} is in the future, move it out of the LOGBOOK"
+                ),
+            ));
+        }
+
+        if opts.report_running_under_done && clock.is_running() {
+            if let Some(done) = doc.done_headline(clock.parent, &opts.done_keywords) {
+                let done = &doc.headlines[done];
+                findings.push(Finding::new(
+                    FindingKind::RunningUnderDone,
+                    file,
+                    line,
+                    format!(
+                        "RUNNING CLOCK UNDER DONE {title:?}: {:?} on line {} is {}",
+                        done.title,
+                        done.line,
+                        done.keyword().unwrap_or_default()
+                    ),
+                ));
+            }
+        }
+
+        if let Some(since) = opts.report_stale_archive_stub {
+            let stub = std::iter::once(clock.parent)
+                .chain(doc.ancestors(clock.parent))
+                .map(|i| &doc.headlines[i])
+                .find(|headline| {
+                    headline.tags().any(|tag| tag == "ARCHIVE")
+                        && headline
+                            .keyword()
+                            .is_some_and(|keyword| opts.done_keywords.iter().any(|k| k == keyword))
+                });
+            if let Some(stub) = stub.filter(|_| clock.start.date() >= since) {
+                findings.push(
+                    Finding::new(
+                        FindingKind::StaleArchiveStub,
+                        file,
+                        line,
+                        format!(
+                            "STALE ARCHIVE STUB {:?}: {clock} is from {since} or later",
+                            stub.title
+                        ),
+                    )
+                    .with_related(file, stub.line)
+                    // right after the cutoff the clock may still be from
+                    // before archiving, a month later it is surely stale
+                    .with_confidence(confidence_past(
+                        (clock.start.date() - since).num_days() as f64 + 30.0,
+                        30.0,
+                    )),
+                );
+            }
+        }
+
+        if let Some(max) = opts.report_stale_running.filter(|_| clock.is_running()) {
+            let running = opts.now - clock.start;
+            if running > max {
+                findings.push(
+                    Finding::new(
+                        FindingKind::StaleRunning,
+                        file,
+                        line,
+                        format!(
+                            "STALE RUNNING CLOCK {title:?}: {clock} is running for {}",
+                            format_rounded(running, 1)
+                        ),
+                    )
+                    .with_confidence(confidence_past(
+                        running.num_minutes() as f64,
+                        max.num_minutes() as f64,
+                    )),
+                );
+            }
+        }
+
+        if opts.report_running_clock && clock.is_running() {
+            findings.push(Finding::new(
+                FindingKind::RunningClock,
+                file,
+                line,
+                format!("RUNNING CLOCK {title:?}"),
+            ));
+        }
+
+        if opts.report_negative_duration && clock.duration() < Duration::zero() {
+            findings.push(Finding::new(
+                FindingKind::NegativeDuration,
+                file,
+                line,
+                format!("NEGATIVE DURATION {title:?}: {duration_string}"),
+            ));
+        }
+
+        if opts.report_zero_clocks
+            && !phantom
+            && clock.duration() == Duration::zero()
+            && !clock.is_running()
+        {
+            findings.push(Finding::new(
+                FindingKind::ZeroDuration,
+                file,
+                line,
+                format!("ZERO DURATION {title:?}: {duration_string}"),
+            ));
+        }
+    }
+
+    if opts.report_unsorted_clocks {
+        for (headline, clocks) in doc.clocks_by_headline() {
+            if let Some(clock) = first_unsorted_clock(&clocks) {
+                let headline = &doc.headlines[headline];
+                findings.push(Finding::new(
+                    FindingKind::UnsortedClocks,
+                    file,
+                    clock.line,
+                    format!(
+                        "UNSORTED CLOCKS {:?} (headline on line {})",
+                        headline.title, headline.line
+                    ),
+                ));
+            }
+        }
+    }
+
+    if opts.report_missing_id {
+        for (i, headline) in doc.headlines.iter().enumerate() {
+            let has_clocks = doc.clocks.iter().any(|clock| clock.parent == i);
+            if has_clocks && headline.property(&opts.id_property).is_none() {
+                findings.push(Finding::new(
+                    FindingKind::MissingId,
+                    file,
+                    headline.line,
+                    format!("MISSING {} PROPERTY {:?}", opts.id_property, headline.title),
+                ));
+            }
+        }
+    }
+
+    if opts.report_duplicate_tags {
+        for headline in &doc.headlines {
+            let duplicates = headline.duplicate_tags();
+            if !duplicates.is_empty() {
+                findings.push(Finding::new(
+                    FindingKind::DuplicateTags,
+                    file,
+                    headline.line,
+                    format!(
+                        "DUPLICATE TAGS {:?}: {}",
+                        headline.title,
+                        duplicates.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+
+    if opts.report_broken_links {
+        for headline in &doc.headlines {
+            for link in headline.file_links() {
+                let path = resolve_link(file, link);
+                if !path.exists() {
+                    findings.push(Finding::new(
+                        FindingKind::BrokenLink,
+                        file,
+                        headline.line,
+                        format!(
+                            "BROKEN LINK {:?}: {} does not exist",
+                            headline.title,
+                            path.display()
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let (Some(days), Some(modified)) = (opts.report_stale_clocking, doc.modified) {
+        let newest = doc
+            .clocks
+            .iter()
+            .max_by_key(|clock| clock.end.unwrap_or(clock.start));
+        if let Some(newest) = newest {
+            let newest_time = newest.end.unwrap_or(newest.start);
+            let stale = modified - newest_time;
+            if stale > Duration::days(days.into()) {
+                findings.push(
+                    Finding::new(
+                        FindingKind::StaleClocking,
+                        file,
+                        newest.line,
+                        format!(
+                            "STALE CLOCKING {file_name}: modified {} but the newest clock is from {}",
+                            modified.format("%Y-%m-%d %H:%M"),
+                            newest_time.date()
+                        ),
+                    )
+                    .with_confidence(confidence_past(
+                        stale.num_hours() as f64,
+                        f64::from(days) * 24.0,
+                    )),
+                );
+            }
+        }
+    }
+
+    if opts.report_headless_file && doc.headlines.is_empty() {
+        let orphan_lines = doc
+            .warnings
+            .iter()
+            .filter_map(|warning| match warning {
+                ParseWarning::OrphanClock { line } => Some(*line),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if let Some(first) = orphan_lines.first() {
+            findings.push(Finding::new(
+                FindingKind::HeadlessFile,
+                file,
+                *first,
+                format!(
+                    "HEADLESS FILE: {} clocks but no headlines in {file_name}",
+                    orphan_lines.len()
+                ),
+            ));
+        }
+    }
+
+    if opts.strict {
+        for warning in &doc.warnings {
+            findings.push(Finding::new(
+                FindingKind::ParseWarning,
+                file,
+                warning.line(),
+                format!("PARSE WARNING: {warning}"),
+            ));
+        }
+    }
+
+    if opts.report_level_skip || opts.strict {
+        for (i, headline) in doc.headlines.iter().enumerate() {
+            let Some(parent) = doc.parent_of(i).map(|parent| &doc.headlines[parent]) else {
+                continue;
+            };
+            if headline.level > parent.level + 1 {
+                findings.push(
+                    Finding::new(
+                        FindingKind::LevelSkip,
+                        file,
+                        headline.line,
+                        format!(
+                            "LEVEL SKIP: {:?} is on level {} below {:?} on level {}",
+                            headline.title, headline.level, parent.title, parent.level
+                        ),
+                    )
+                    .with_related(file, parent.line),
+                );
+            }
+        }
+    }
+
+    if opts.report_sibling_overlap {
+        let now = opts.now_utc();
+        let clocks = doc
+            .clocks
+            .iter()
+            .filter(|clock| !clock.is_running())
+            .collect::<Vec<_>>();
+        for (i, clock1) in clocks.iter().enumerate() {
+            for clock2 in &clocks[i + 1..] {
+                if clock1.parent == clock2.parent
+                    || doc.parent_of(clock1.parent) != doc.parent_of(clock2.parent)
+                    || !clock1.overlaps(clock2, now)
+                {
+                    continue;
+                }
+                let (start1, end1) = clock1.utc_interval();
+                let (start2, end2) = clock2.utc_interval();
+                let overlap = end1.min(end2) - start1.max(start2);
+                let title1 = doc.headlines[clock1.parent].title;
+                let title2 = doc.headlines[clock2.parent].title;
+                findings.push(
+                    Finding::new(
+                        FindingKind::SiblingOverlap,
+                        file,
+                        clock1.line,
+                        format!(
+                            "SIBLING OVERLAP: {title1:?} and {title2:?} overlap by {}:{:0>2}",
+                            overlap.num_hours(),
+                            overlap.num_minutes() % 60
+                        ),
+                    )
+                    .with_related(file, clock2.line),
+                );
+            }
+        }
+    }
+
+    if opts.report_clock_indentation {
+        for (clock, expected) in misaligned_clocks(doc) {
+            findings.push(Finding::new(
+                FindingKind::MisalignedClock,
+                file,
+                clock.line,
+                format!(
+                    "MISALIGNED CLOCK in {:?}: indented {:?}, expected {expected:?}",
+                    doc.headlines[clock.parent].title, clock.indent
+                ),
+            ));
+        }
+    }
+
+    if opts.report_orphan_total {
+        let clock_counts = doc.subtree_clock_counts();
+        for (headline, count) in doc.headlines.iter().zip(clock_counts) {
+            if count > 0 {
+                continue;
+            }
+            if let Some(total) = headline.property(&opts.total_property) {
+                findings.push(Finding::new(
+                    FindingKind::OrphanTotal,
+                    file,
+                    headline.line,
+                    format!(
+                        "ORPHAN {} PROPERTY {:?} without clocks: {total}",
+                        opts.total_property, headline.title
+                    ),
+                ));
+            }
+        }
+    }
+
+    opts.classify(&mut findings);
+    findings.retain(|finding| finding.confidence >= opts.min_confidence);
+    findings
+}
+
+/// The path a `file:` link in `file` points to. `~` is the home directory,
+/// relative paths start at the directory of `file`.
+fn resolve_link(file: &Path, link: &str) -> PathBuf {
+    let home = link
+        .strip_prefix("~/")
+        .and_then(|rest| Some(std::env::home_dir()?.join(rest)));
+    match home {
+        Some(path) => path,
+        None => file.parent().unwrap_or(Path::new("")).join(link),
+    }
+}
+
+/// Runs the checks enabled in `opts` that compare clocks of different
+/// documents.
+pub fn lint_documents(docs: &[OrgDocument], opts: &LintOptions) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if opts.report_cross_file_duplicate {
+        let mut seen: HashMap<_, Vec<(&PathBuf, usize)>> = HashMap::new();
+        for doc in docs {
+            for clock in &doc.clocks {
+                let occurrences = seen.entry((clock.start, clock.end)).or_default();
+                if let Some((other_file, other_line)) =
+                    occurrences.iter().find(|(file, _)| **file != doc.file)
+                {
+                    findings.push(
+                        Finding::new(
+                            FindingKind::CrossFileDuplicate,
+                            &doc.file,
+                            clock.line,
+                            format!(
+                                "DUPLICATE CLOCK {clock} in {:?}, also in {}:{other_line}",
+                                doc.headlines[clock.parent].title,
+                                other_file.display()
+                            ),
+                        )
+                        .with_related(*other_file, *other_line),
+                    );
+                }
+                occurrences.push((&doc.file, clock.line));
+            }
+        }
+    }
+
+    if opts.report_split_subtree {
+        for archive in docs {
+            let Some(origin) = archive_origin(&archive.file)
+                .and_then(|origin| docs.iter().find(|doc| doc.file == origin))
+            else {
+                continue;
+            };
+            let clocked = |doc: &OrgDocument| {
+                doc.clocks
+                    .iter()
+                    .map(|clock| clock.parent)
+                    .collect::<BTreeSet<_>>()
+            };
+            let archived = clocked(archive);
+            for index in clocked(origin) {
+                let headline = &origin.headlines[index];
+                let Some(archived) = archived
+                    .iter()
+                    .map(|&i| &archive.headlines[i])
+                    .find(|archived| archived.title == headline.title)
+                else {
+                    continue;
+                };
+                findings.push(
+                    Finding::new(
+                        FindingKind::SplitSubtree,
+                        &origin.file,
+                        headline.line,
+                        format!(
+                            "SPLIT SUBTREE {:?} has clocks here and in {}:{}, archiving looks incomplete",
+                            headline.title,
+                            archive.file.display(),
+                            archived.line
+                        ),
+                    )
+                    .with_related(&archive.file, archived.line),
+                );
+            }
+        }
+    }
+
+    for (tag, cap) in &opts.tag_daily_caps {
+        // per day the total and the first clock, by date and time, as location
+        let mut days: BTreeMap<NaiveDate, (Duration, NaiveDateTime, &PathBuf, usize)> =
+            BTreeMap::new();
+        for doc in docs {
+            for clock in &doc.clocks {
+                let Some(end) = clock.end else {
+                    continue;
+                };
+                if !doc.has_inherited_tag(clock.parent, tag) {
+                    continue;
+                }
+                // clocks across midnight count towards each day
+                let mut start = clock.start;
+                while start < end {
+                    let midnight = (start.date() + Duration::days(1))
+                        .and_hms_opt(0, 0, 0)
+                        .unwrap();
+                    let day_end = end.min(midnight);
+                    let day = days.entry(start.date()).or_insert((
+                        Duration::zero(),
+                        clock.start,
+                        &doc.file,
+                        clock.line,
+                    ));
+                    day.0 = day.0 + (day_end - start);
+                    if clock.start < day.1 {
+                        (day.1, day.2, day.3) = (clock.start, &doc.file, clock.line);
+                    }
+                    start = day_end;
+                }
+            }
+        }
+        for (date, (total, _, file, line)) in days {
+            if total > *cap {
+                findings.push(Finding::new(
+                    FindingKind::TagDailyCap,
+                    file,
+                    line,
+                    format!(
+                        "TAG DAILY CAP: {tag} has {} on {date}, more than {}",
+                        format_rounded(total, 1),
+                        format_rounded(*cap, 1)
+                    ),
+                ));
+            }
+        }
+    }
+
+    opts.classify(&mut findings);
+    findings.retain(|finding| finding.confidence >= opts.min_confidence);
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{
+        explain, lint_document, lint_documents, merge_findings, Finding, FindingKind,
+        KnownLongDuration, LintOptions, Severity, Suppression,
+    };
+    use crate::OrgDocument;
+
+    #[test]
+    fn report_missing_id() {
+        let org_string = "
+* with id
+:PROPERTIES:
+:ID:       0b7a4c3e-1f2d-4e5a-9c8b-7d6e5f4a3b2c
+:END:
+CLOCK: [2022-12-12 Mon 10:40]--[2022-12-12 Mon 10:50] =>  0:10
+* without id
+CLOCK: [2022-12-12 Mon 11:40]--[2022-12-12 Mon 11:50] =>  0:10
+* no clocks, no id
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_missing_id: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::MissingId);
+        assert_eq!(findings[0].line, 7);
+        assert_eq!(
+            findings[0].to_string(),
+            "[test.org:7] MISSING ID PROPERTY \"without id\""
+        );
+
+        let opts = LintOptions {
+            report_missing_id: true,
+            id_property: "CUSTOM_ID".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(lint_document(&doc, &opts).len(), 2);
+    }
+
+    #[test]
+    fn merge_findings_by_fingerprint() {
+        let zero = |org_dir: &str, file: &str, line, title: &str| {
+            Finding::new(
+                FindingKind::ZeroDuration,
+                Path::new(org_dir).join(file),
+                line,
+                format!("ZERO DURATION {title:?}"),
+            )
+            .with_fingerprint(Path::new(org_dir))
+        };
+        let laptop = vec![
+            zero("/home/me/org", "b.org", 3, "b"),
+            zero("/home/me/org", "a.org", 5, "a"),
+        ];
+        // a line was inserted above "a" on the desktop
+        let desktop = vec![
+            zero("/Users/me/org", "a.org", 6, "a"),
+            zero("/Users/me/org", "a.org", 9, "c"),
+            zero("/Users/me/org", "sub/a.org", 5, "a"),
+        ];
+        assert_eq!(laptop[1].fingerprint, desktop[0].fingerprint);
+        assert_ne!(desktop[0].fingerprint, desktop[1].fingerprint);
+        assert_ne!(desktop[0].fingerprint, desktop[2].fingerprint);
+
+        let merged = merge_findings([laptop, desktop]);
+        let merged = merged
+            .iter()
+            .map(|f| (f.file_name(), f.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            vec![("a.org", 5), ("a.org", 5), ("a.org", 9), ("b.org", 3)]
+        );
+    }
+
+    #[test]
+    fn explain_fired_kinds() {
+        let org_string = "
+* negative
+CLOCK: [2022-12-12 Mon 10:50]--[2022-12-12 Mon 10:40] =>  -0:10
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let findings = lint_document(&doc, &LintOptions::default());
+        assert_eq!(findings.len(), 1);
+        let explanation = explain(&findings);
+        assert!(explanation.contains("negative-duration:"));
+        assert!(explanation.contains(FindingKind::NegativeDuration.explanation()));
+        assert!(!explanation.contains("duration-mismatch:"));
+        assert!(!explanation.contains(FindingKind::DurationMismatch.explanation()));
+        assert!(explain(&[]).is_empty());
+    }
+
+    #[test]
+    fn nonpadded_minutes_are_not_a_mismatch() {
+        let org_string = "
+* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:03] =>  1:3
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:3
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_nonpadded_minutes: true,
+            ..Default::default()
+        };
+        let kinds = lint_document(&doc, &opts)
+            .into_iter()
+            .map(|f| (f.line, f.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (3, FindingKind::NonPaddedMinutes),
+                (4, FindingKind::DurationMismatch),
+                (4, FindingKind::NonPaddedMinutes),
+            ]
+        );
+    }
+
+    #[test]
+    fn report_mixed_timestamp_type() {
+        let org_string = "
+* task
+CLOCK: [2021-04-18 Sun 00:57]--<2021-04-18 Sun 02:30> =>  1:33
+CLOCK: [2021-04-17 Sat 09:00]--[2021-04-17 Sat 09:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_mixed_timestamp_type: true,
+            ..Default::default()
+        };
+        let kinds = lint_document(&doc, &opts)
+            .into_iter()
+            .map(|f| (f.line, f.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![(3, FindingKind::MixedTimestampType)]);
+    }
+
+    #[test]
+    fn report_clock_indentation() {
+        let org_string = "
+* task
+  :LOGBOOK:
+  CLOCK: [2022-12-12 Mon 12:00]--[2022-12-12 Mon 13:00] =>  1:00
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30
+  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+  :END:
+CLOCK: [2022-12-11 Sun 10:00]--[2022-12-11 Sun 10:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_clock_indentation: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::MisalignedClock);
+        assert_eq!(findings[0].line, 5);
+        assert_eq!(
+            findings[0].message,
+            "MISALIGNED CLOCK in \"task\": indented \"\", expected \"  \""
+        );
+    }
+
+    #[test]
+    fn report_split_subtree() {
+        let foo = "
+* project
+** done task
+CLOCK: [2022-12-13 Tue 09:00]--[2022-12-13 Tue 09:30] =>  0:30
+** open task
+CLOCK: [2022-12-14 Wed 10:00]--[2022-12-14 Wed 11:00] =>  1:00
+";
+        let archive = "
+* done task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* open task
+";
+        let docs = [
+            OrgDocument::parse("notes/foo.org", foo),
+            OrgDocument::parse("notes/foo_archive.org", archive),
+            OrgDocument::parse("bar_archive.org", archive),
+        ];
+        let opts = LintOptions {
+            report_split_subtree: true,
+            ..Default::default()
+        };
+        let findings = lint_documents(&docs, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::SplitSubtree);
+        assert_eq!(findings[0].file.to_str(), Some("notes/foo.org"));
+        assert_eq!(findings[0].line, 3);
+        let related = findings[0].related.as_ref().unwrap();
+        assert_eq!(related.file.to_str(), Some("notes/foo_archive.org"));
+        assert_eq!(related.line, 2);
+    }
+
+    #[test]
+    fn report_cross_file_duplicate() {
+        let original = "
+* project
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+";
+        let copy = "
+* project (copy)
+CLOCK: [2022-12-13 Tue 10:00]--[2022-12-13 Tue 11:00] =>  1:00
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+";
+        let docs = [
+            OrgDocument::parse("project.org", original),
+            OrgDocument::parse("copy.org", copy),
+        ];
+        let opts = LintOptions {
+            report_cross_file_duplicate: true,
+            ..Default::default()
+        };
+        let findings = lint_documents(&docs, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::CrossFileDuplicate);
+        assert_eq!(findings[0].file.to_str(), Some("copy.org"));
+        assert_eq!(findings[0].line, 4);
+        let related = findings[0].related.as_ref().unwrap();
+        assert_eq!(related.file.to_str(), Some("project.org"));
+        assert_eq!(related.line, 3);
+    }
+
+    #[test]
+    fn report_level_skip() {
+        let org_string = "
+* project
+*** skipped level
+* other project
+** task
+*** subtask
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_level_skip: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::LevelSkip);
+        assert_eq!(findings[0].line, 3);
+        assert_eq!(findings[0].related.as_ref().unwrap().line, 2);
+    }
+
+    #[test]
+    fn report_headless_file() {
+        let org_string = "
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+";
+        let doc = OrgDocument::parse("clocks.org", org_string);
+        let opts = LintOptions {
+            report_headless_file: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::HeadlessFile);
+        assert_eq!(findings[0].line, 2);
+        assert_eq!(
+            findings[0].message,
+            "HEADLESS FILE: 2 clocks but no headlines in clocks.org"
+        );
+
+        let doc = OrgDocument::parse("empty.org", "just some text\n");
+        assert!(lint_document(&doc, &opts).is_empty());
+    }
+
+    #[test]
+    fn report_phantom_duration() {
+        let org_string = "
+* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:00] =>  1:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let findings = lint_document(&doc, &LintOptions::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::PhantomDuration);
+        assert_eq!(findings[0].line, 3);
+
+        let opts = LintOptions {
+            report_phantom_duration: false,
+            ..Default::default()
+        };
+        let kinds = lint_document(&doc, &opts)
+            .into_iter()
+            .map(|f| f.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![FindingKind::DurationMismatch, FindingKind::ZeroDuration]
+        );
+    }
+
+    #[test]
+    fn report_midnight_placeholder() {
+        let org_string = "
+* captured
+CLOCK: [2021-04-18 Sun 00:00]
+* worked overnight
+CLOCK: [2021-04-17 Sat 00:00]--[2021-04-17 Sat 01:30] =>  1:30
+* running
+CLOCK: [2021-04-19 Mon 09:00]
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_midnight_placeholder: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::MidnightPlaceholder);
+        assert_eq!(findings[0].line, 3);
+        assert!(findings[0].message.contains("\"captured\""));
+
+        let opts = LintOptions {
+            report_midnight_placeholder: true,
+            placeholder_time: chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, 7);
+    }
+
+    #[test]
+    fn report_misplaced_appointment() {
+        let org_string = "
+* dentist
+:LOGBOOK:
+CLOCK: <2023-03-01 Wed 10:00>--<2023-03-01 Wed 11:00> =>  1:00
+:END:
+* past appointment
+CLOCK: <2023-02-01 Wed 10:00>--<2023-02-01 Wed 11:00> =>  1:00
+* inactive
+CLOCK: [2023-03-02 Thu 10:00]--[2023-03-02 Thu 11:00] =>  1:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_misplaced_appointment: true,
+            now: chrono::NaiveDate::from_ymd_opt(2023, 2, 15)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::MisplacedAppointment);
+        assert_eq!(findings[0].line, 4);
+        assert!(findings[0].message.contains("LOGBOOK"));
+    }
+
+    #[test]
+    fn report_duplicate_tags() {
+        let org_string = "
+* task :work:work:urgent:
+* tidy :work:urgent:
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_duplicate_tags: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::DuplicateTags);
+        assert_eq!(findings[0].line, 2);
+        assert_eq!(findings[0].message, "DUPLICATE TAGS \"task\": work");
+    }
+
+    #[test]
+    fn report_tag_daily_cap() {
+        let a = "
+* client :billable:
+** meeting
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 14:00] =>  5:00
+CLOCK: [2022-12-13 Tue 09:00]--[2022-12-13 Tue 12:00] =>  3:00
+* internal
+CLOCK: [2022-12-13 Tue 13:00]--[2022-12-13 Tue 20:00] =>  7:00
+";
+        let b = "
+#+FILETAGS: :billable:
+* other client
+CLOCK: [2022-12-12 Mon 15:00]--[2022-12-12 Mon 19:00] =>  4:00
+";
+        let docs = [
+            OrgDocument::parse("a.org", a),
+            OrgDocument::parse("b.org", b),
+        ];
+        let opts = LintOptions {
+            tag_daily_caps: vec![("billable".to_string(), chrono::Duration::hours(8))],
+            ..Default::default()
+        };
+        let findings = lint_documents(&docs, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::TagDailyCap);
+        assert_eq!(findings[0].file_name(), "a.org");
+        assert_eq!(findings[0].line, 4);
+        assert_eq!(
+            findings[0].message,
+            "TAG DAILY CAP: billable has 9:00 on 2022-12-12, more than 8:00"
+        );
+    }
+
+    #[test]
+    fn report_running_under_done() {
+        let org_string = "
+* DONE project
+** task
+CLOCK: [2022-12-12 Mon 10:00]
+* TODO open
+CLOCK: [2022-12-12 Mon 11:00]
+* CANCELLED dropped
+CLOCK: [2022-12-12 Mon 12:00]
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_running_under_done: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::RunningUnderDone);
+        assert_eq!(findings[0].line, 4);
+        assert_eq!(
+            findings[0].message,
+            "RUNNING CLOCK UNDER DONE \"task\": \"DONE project\" on line 2 is DONE"
+        );
+
+        let opts = LintOptions {
+            report_running_under_done: true,
+            done_keywords: vec!["DONE".to_string(), "CANCELLED".to_string()],
+            ..Default::default()
+        };
+        let lines = lint_document(&doc, &opts)
+            .iter()
+            .map(|f| f.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![4, 8]);
+    }
+
+    #[test]
+    fn show_suppressed() {
+        static KNOWN: &[KnownLongDuration] = &[KnownLongDuration {
+            file: "test.org",
+            duration: "12:00",
+            title: "conference",
+        }];
+        let org_string = "
+* conference
+CLOCK: [2023-01-09 Mon 08:00]--[2023-01-09 Mon 20:00] => 12:00
+* hackathon
+CLOCK: [2023-01-10 Tue 08:00]--[2023-01-10 Tue 20:00] => 12:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_long_duration: true,
+            long_duration: chrono::Duration::hours(8),
+            known_long_durations: KNOWN,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, 5);
+        assert_eq!(findings[0].suppressed, None);
+
+        let opts = LintOptions {
+            show_suppressed: true,
+            ..opts
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].suppressed, Some(Suppression::Allowlist));
+        assert_eq!(
+            findings[0].to_string(),
+            "[test.org:3] LONG DURATION: 12:00 in \"conference\" (suppressed: allowlist)"
+        );
+        assert_eq!(findings[1].suppressed, None);
+        let json = serde_json::to_value(&findings).unwrap();
+        assert_eq!(json[0]["suppressed"], "allowlist");
+        assert!(json[1].get("suppressed").is_none());
+    }
+
+    #[test]
+    fn min_confidence() {
+        let org_string = "
+* borderline
+CLOCK: [2023-01-09 Mon 08:00]--[2023-01-09 Mon 17:00] =>  9:00
+* strong
+CLOCK: [2023-01-10 Tue 06:00]--[2023-01-10 Tue 23:00] => 17:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_long_duration: true,
+            long_duration: chrono::Duration::hours(8),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 2);
+        assert!((findings[0].confidence - 9.0 / 16.0).abs() < 1e-6);
+        assert_eq!(findings[1].confidence, 1.0);
+        let json = serde_json::to_value(&findings).unwrap();
+        assert_eq!(json[0]["confidence"], 0.5625);
+        assert!(json[1].get("confidence").is_none());
+
+        let opts = LintOptions {
+            min_confidence: 0.8,
+            ..opts
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, 5);
+
+        let finding = findings[0].clone();
+        assert_eq!(finding.clone().with_confidence(f32::NAN).confidence, 1.0);
+        assert_eq!(finding.clone().with_confidence(-1.0).confidence, 0.0);
+        assert_eq!(finding.with_confidence(2.0).confidence, 1.0);
+    }
+
+    #[test]
+    fn strict() {
+        let org_string = "CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:00] =>  1:00
+* task
+*** skipped a level
+CLOCK: [2023-01-11 Wed 09:00]--[2023-01-11 Wed 10:00] =>  1:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_long_duration: true,
+            long_duration: chrono::Duration::minutes(30),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+
+        let opts = LintOptions {
+            strict: true,
+            ..opts
+        };
+        let findings = lint_document(&doc, &opts);
+        let kinds = findings.iter().map(|f| f.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                FindingKind::LongDuration,
+                FindingKind::ParseWarning,
+                FindingKind::LevelSkip
+            ]
+        );
+        assert!(findings.iter().all(|f| f.severity == Severity::Error));
+        assert_eq!(
+            findings[1].message,
+            "PARSE WARNING: found clock on line 1 but have no headline"
+        );
+    }
+
+    #[test]
+    fn report_stale_clocking() {
+        let org_string = "
+* task
+CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:00] =>  1:00
+CLOCK: [2023-01-09 Mon 09:00]--[2023-01-09 Mon 10:00] =>  1:00
+";
+        let mut doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_stale_clocking: Some(30),
+            ..Default::default()
+        };
+        let date = |month, day| {
+            chrono::NaiveDate::from_ymd_opt(2023, month, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+        };
+        // without a modification time, e.g. from stdin, there is nothing to compare
+        assert!(lint_document(&doc, &opts).is_empty());
+
+        doc.modified = date(2, 1);
+        assert!(lint_document(&doc, &opts).is_empty());
+
+        doc.modified = date(3, 1);
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::StaleClocking);
+        assert_eq!(findings[0].line, 3);
+        assert_eq!(
+            findings[0].message,
+            "STALE CLOCKING test.org: modified 2023-03-01 12:00 but the newest clock is from 2023-01-10"
+        );
+    }
+
+    #[test]
+    fn report_broken_links() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("exists.org"), "").unwrap();
+        let org_string = "
+* see [[file:exists.org::*Intro][exists]] and [[https://example.com][web]]
+* [[file:missing.org][missing]] :work:
+CLOCK: [2023-02-14 Tue 09:00]--[2023-02-14 Tue 10:00] =>  1:00
+";
+        let doc = OrgDocument::parse(dir.path().join("notes.org"), org_string);
+        let opts = LintOptions {
+            report_broken_links: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::BrokenLink);
+        assert_eq!(findings[0].line, 3);
+        assert_eq!(
+            findings[0].message,
+            format!(
+                "BROKEN LINK \"[[file:missing.org][missing]]\": {} does not exist",
+                dir.path().join("missing.org").display()
+            )
+        );
+    }
+
+    #[test]
+    fn report_stale_running() {
+        let org_string = "
+* forgotten
+CLOCK: [2023-02-14 Tue 09:00]
+* current
+CLOCK: [2023-02-15 Wed 08:00]
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_stale_running: Some(chrono::Duration::hours(12)),
+            now: chrono::NaiveDate::from_ymd_opt(2023, 2, 15)
+                .unwrap()
+                .and_hms_opt(10, 30, 0)
+                .unwrap(),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::StaleRunning);
+        assert_eq!(
+            findings[0].message,
+            "STALE RUNNING CLOCK \"forgotten\": [2023-02-14 Tue 09:00] is running for 25:30"
+        );
+
+        // as of the evening the current clock is stale too
+        let opts = LintOptions {
+            now: opts.now + chrono::Duration::hours(10),
+            ..opts
+        };
+        assert_eq!(lint_document(&doc, &opts).len(), 2);
+    }
+
+    #[test]
+    fn report_rounding_inconsistency() {
+        let org_string = "
+* imported
+CLOCK: [2021-04-18 Sun 00:57:40]--[2021-04-18 Sun 02:30:10] =>  1:32
+CLOCK: [2021-04-18 Sun 00:57:40]--[2021-04-18 Sun 02:30:10] =>  1:33
+CLOCK: [2021-04-18 Sun 00:57:40]--[2021-04-18 Sun 02:30:10] =>  1:34
+CLOCK: [2021-04-18 Sun 03:00]--[2021-04-18 Sun 04:00] =>  1:01
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        // both roundings are accepted, only 1:34 is inconsistent
+        let findings = lint_document(&doc, &LintOptions::default());
+        let kinds = findings
+            .iter()
+            .map(|f| (f.kind, f.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (FindingKind::DurationMismatch, 5),
+                (FindingKind::DurationMismatch, 6)
+            ]
+        );
+
+        let opts = LintOptions {
+            report_rounding_inconsistency: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        let kinds = findings
+            .iter()
+            .map(|f| (f.kind, f.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (FindingKind::RoundingInconsistency, 5),
+                (FindingKind::DurationMismatch, 6)
+            ]
+        );
+        assert_eq!(
+            findings[0].message,
+            "ROUNDING INCONSISTENCY \"imported\": 1:34 matches neither 1:32 (rounded down) nor 1:33 (rounded)"
+        );
+    }
+
+    #[test]
+    fn report_stale_archive_stub() {
+        let org_string = "
+* DONE old project (archived) :ARCHIVE:
+** task
+CLOCK: [2023-03-01 Wed 10:00]--[2023-03-01 Wed 11:00] =>  1:00
+CLOCK: [2022-06-01 Wed 10:00]--[2022-06-01 Wed 11:00] =>  1:00
+* TODO parked :ARCHIVE:
+CLOCK: [2023-03-01 Wed 12:00]--[2023-03-01 Wed 13:00] =>  1:00
+* DONE finished
+CLOCK: [2023-03-01 Wed 14:00]--[2023-03-01 Wed 15:00] =>  1:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_stale_archive_stub: chrono::NaiveDate::from_ymd_opt(2023, 1, 1),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::StaleArchiveStub);
+        assert_eq!(findings[0].line, 4);
+        assert_eq!(findings[0].related.as_ref().unwrap().line, 2);
+        assert_eq!(
+            findings[0].message,
+            "STALE ARCHIVE STUB \"DONE old project (archived)\": \
+             [2023-03-01 Wed 10:00]--[2023-03-01 Wed 11:00] =>  1:00 is from 2023-01-01 or later"
+        );
+        assert_eq!(findings[0].confidence, 1.0);
+
+        let opts = LintOptions {
+            report_stale_archive_stub: chrono::NaiveDate::from_ymd_opt(2023, 3, 1),
+            ..Default::default()
+        };
+        assert_eq!(lint_document(&doc, &opts)[0].confidence, 0.5);
+    }
+
+    #[test]
+    fn report_dst_spanning() {
+        let org_string = "
+* night shift
+CLOCK: [2021-03-28 Sun 01:00]--[2021-03-28 Sun 04:00] =>  3:00
+CLOCK: [2021-03-27 Sat 01:00]--[2021-03-27 Sat 04:00] =>  3:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert!(lint_document(&doc, &LintOptions::default()).is_empty());
+
+        let opts = LintOptions {
+            report_dst_spanning: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::DstSpanning);
+        assert_eq!(findings[0].severity, super::Severity::Info);
+        assert_eq!(findings[0].line, 3);
+    }
+
+    #[test]
+    fn long_duration_threshold() {
+        let org_string = "
+* task
+CLOCK: [2022-12-12 Mon 08:00]--[2022-12-12 Mon 18:00] => 10:00
+CLOCK: [2022-12-13 Tue 08:00]--[2022-12-13 Tue 18:01] => 10:01
+CLOCK: [2022-12-14 Wed 08:00]--[2022-12-14 Wed 17:59] =>  9:59
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let long_lines = |opts: &LintOptions| {
+            lint_document(&doc, opts)
+                .into_iter()
+                .filter(|f| f.kind == FindingKind::LongDuration)
+                .map(|f| f.line)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(long_lines(&LintOptions::default()), vec![4]);
+        let opts = LintOptions {
+            long_duration_inclusive: true,
+            ..Default::default()
+        };
+        assert_eq!(long_lines(&opts), vec![3, 4]);
+    }
+
+    #[test]
+    fn report_orphan_total() {
+        let org_string = "
+* project
+:PROPERTIES:
+:TOTAL: 3:00
+:END:
+** task
+:PROPERTIES:
+:TOTAL: 1:00
+:END:
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+* moved away
+:PROPERTIES:
+:TOTAL: 2:00
+:END:
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_orphan_total: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::OrphanTotal);
+        assert_eq!(findings[0].line, 11);
+        assert!(findings[0].message.contains("2:00"));
+    }
+
+    #[test]
+    fn report_sibling_overlap() {
+        let org_string = "
+* project
+** task 1
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+** task 2
+CLOCK: [2022-12-12 Mon 10:30]--[2022-12-12 Mon 11:30] =>  1:00
+*** subtask of task 2
+CLOCK: [2022-12-12 Mon 10:15]--[2022-12-12 Mon 10:45] =>  0:30
+* other project
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 12:00] =>  2:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_sibling_overlap: true,
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::SiblingOverlap);
+        assert_eq!(findings[0].line, 4);
+        assert_eq!(findings[0].related.as_ref().unwrap().line, 6);
+        assert_eq!(
+            findings[0].message,
+            "SIBLING OVERLAP: \"task 1\" and \"task 2\" overlap by 0:30"
+        );
+    }
+
+    #[test]
+    fn report_multiday() {
+        let org_string = "
+* task
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-14 Wed 10:00] => 49:00
+CLOCK: [2022-12-15 Thu 22:00]--[2022-12-16 Fri 02:00] =>  4:00
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let opts = LintOptions {
+            report_long_duration: false,
+            report_multiday: Some(1),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::MultiDay);
+        assert_eq!(findings[0].line, 3);
+        assert_eq!(
+            findings[0].message,
+            "MULTIDAY CLOCK: spans 2 days in \"task\""
+        );
+    }
+}
@@ -1,24 +1,40 @@
-use anyhow::Result;
-use chrono::Duration;
-use clap::Parser;
-use once_cell::sync::Lazy;
-use org_linter::{ClockConflict, FileChange, OrgDocument, OrgFile};
-use std::{collections::HashSet, ffi::OsString, fs, io::BufRead, path::PathBuf, str::FromStr};
-
-static DEFAULT_ORG_DIR: Lazy<String> = Lazy::new(|| {
-    #[allow(deprecated)]
-    std::env::home_dir()
-        .unwrap()
-        .join("org")
-        .to_string_lossy()
-        .to_string()
-});
+use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use org_linter::{
+    check_files_pipelined, close_running_under_done, dedupe_tags, diff_findings, effort_variances,
+    explain, fix_weekdays, follow_includes, format_decimal_hours, format_rounded, headline_pattern,
+    invoice_rows, is_markdown_file, json_schema, lint_document, lint_documents, merge_adjacent,
+    merge_findings, monthly_org_table, normalize_clock_indentation, normalize_clocks,
+    normalize_timestamp_types, pad_minutes, parse_duration, preview_fixes, remove_orphan_totals,
+    resolve_all, resolve_conflicts_automatically, run_custom_check, sort_clocks, split_clock,
+    tag_usage, time_by_month, time_by_project, uncovered_work_time, watch, ClockConflict, Config,
+    FileChange, Finding, IgnoreFile, KnownLongDuration, LintOptions, OrgDocument, OrgFile,
+    ParseOptions, Query, Severity, Stats, Summary, WatchSession, WeekdayLocale, WeeklySummary,
+    WorkingHours,
+};
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    io::{BufRead, IsTerminal},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 #[derive(Parser)]
 #[command(
     about = "Checks your org files for stranger things. Currently mostly focused on soundness of org clocks."
 )]
 struct CheckOrgOptions {
+    #[arg(skip)]
+    config: Config,
+
     #[arg(
         long = "report-long-durations",
         default_value_t = true,
@@ -28,14 +44,21 @@ struct CheckOrgOptions {
     report_long_duration: bool,
 
     #[arg(
-        value_parser = parse_duration_from_cli,
+        value_parser = parse_duration,
         default_value = "10:00",
         long = "long-duration",
-        help = "Duration used for --report-long-durations. HH:MM format.",
+        help = "Duration used for --report-long-durations. H:MM, minutes or a number with m or h suffix, e.g. 90m or 1.5h.",
         requires = "long-duration"
     )]
     long_duration: Duration,
 
+    #[arg(
+        long = "long-duration-inclusive",
+        default_value_t = false,
+        help = "Also report clocks that are exactly --long-duration long. By default only longer clocks are reported. [default: false]"
+    )]
+    long_duration_inclusive: bool,
+
     #[arg(
         long = "duration-mismatch",
         default_value_t = true,
@@ -64,6 +87,13 @@ struct CheckOrgOptions {
     )]
     report_zero_clocks: bool,
 
+    #[arg(
+        long = "phantom-duration",
+        default_value_t = true,
+        help = "Report clocks whose start and end timestamp is the same but whose duration is not zero. [default: true]"
+    )]
+    report_phantom_duration: bool,
+
     #[arg(
         long = "clock-conflicts",
         default_value_t = false,
@@ -79,236 +109,2425 @@ struct CheckOrgOptions {
     fix_clock_conflicts: bool,
 
     #[arg(
-        long = "org-dir",
-        help = "The org directory that contains the org files.",
-        group = "org-dir",
-        default_value = &**DEFAULT_ORG_DIR,
+        long = "report-unsorted-clocks",
+        default_value_t = false,
+        help = "Report headlines whose clocks are not in chronological order. [default: false]"
     )]
-    org_dir: PathBuf,
+    report_unsorted_clocks: bool,
 
     #[arg(
-        long = "recursive",
-        default_value_t = true,
-        requires = "org-dir",
-        help = "Recursively find .org files in --org-dir. [default: true]"
+        long = "fix-sort-clocks",
+        default_value_t = false,
+        help = "Rewrite the clocks of headlines into chronological order. [default: false]"
     )]
-    recursive: bool,
+    fix_sort_clocks: bool,
 
     #[arg(
-        long = "org-file",
-        help = "Specify individual org files to lint. Overrides --org-dir."
+        long = "report-nonpadded-minutes",
+        default_value_t = false,
+        help = "Report clocks whose duration has minutes without zero padding, e.g. 1:3. [default: false]"
     )]
-    org_files: Option<Vec<PathBuf>>,
-}
+    report_nonpadded_minutes: bool,
 
-fn parse_duration_from_cli(s: &str) -> Result<Duration, String> {
-    if let Some((h, m)) = s.split_once(':') {
-        Ok(
-            Duration::hours(h.parse().map_err(|_| "cannot parse hours".to_string())?)
-                + Duration::minutes(m.parse().map_err(|_| "cannot parse minutes".to_string())?),
-        )
-    } else {
-        Err("cannot parse duration".to_string())
-    }
-}
+    #[arg(
+        long = "report-missing-id",
+        default_value_t = false,
+        help = "Report headlines that have clocks but no ID property. [default: false]"
+    )]
+    report_missing_id: bool,
 
-struct KnownLongDuration {
-    file: &'static str,
-    duration: &'static str,
-    title: &'static str,
-}
+    #[arg(
+        long = "id-property",
+        default_value = "ID",
+        help = "Name of the property required by --report-missing-id."
+    )]
+    id_property: String,
 
-#[rustfmt::skip]
-const KNOWN_LONG_DURATIONS: &[KnownLongDuration] = &[
-    KnownLongDuration {file:"clockin.org", duration: "12:59", title: "privacy setup"},
-    KnownLongDuration {file:"clockin.org", duration: "9:10", title: "ClojureD"},
-    KnownLongDuration {file:"clockin.org", duration: "11:04", title: "Testing wasm"},
-    KnownLongDuration {file:"clockin.org", duration: "10:08", title: "emacs python setup"},
-    KnownLongDuration {file:"clockin.org", duration: "9:47", title: "[[file:books.org][organizing my books]]"},
-    KnownLongDuration {file:"clockin.org", duration: "10:04", title: "Testing live reload with rust [[https://fasterthanli.me/articles/so-you-want-to-live-reload-rust][So you want to live-reload Rust - fasterthanli.me]]"},
-    KnownLongDuration {file:"clockin.org", duration: "9:22", title: "blog post: how does bevy component query work?"},
-    KnownLongDuration {file:"clockin.org", duration: "8:08", title: "blog post: setting up a Rust web / wasm project like it's 2022"},
+    #[arg(
+        long = "merge-adjacent",
+        default_value_t = false,
+        help = "Merge clocks of the same headline where one ends exactly when the next starts. [default: false]"
+    )]
+    merge_adjacent: bool,
 
-    KnownLongDuration {file: "coscreen.org",duration: "9:30", title: "Create objective means to profile and determine end to end latency that users perceive when interacting with our user interface."},
-    KnownLongDuration {file: "coscreen.org",duration: "10:41", title: "DONE implement messaging on top of electrons window messaging api"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:23", title: "mojave user gets extra \"coscreen helper\" permission request"},
-    KnownLongDuration {file: "coscreen.org",duration: "9:18", title: "single window picking"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:04", title: "[node-wrtc] capture window content"},
-    KnownLongDuration {file: "coscreen.org",duration: "9:48", title: "[node-wrtc] capture window content"},
-    KnownLongDuration {file: "coscreen.org",duration: "9:44", title: "i420 yuv conversion"},
-    KnownLongDuration {file: "coscreen.org",duration: "11:29", title: "ACTIVE profiling support for coscreen native"},
-    KnownLongDuration {file: "coscreen.org",duration: "11:47", title: "Learning about GTK & libwebrtc screen capturing"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:13", title: "sending libwebrtc screen capture to browser"},
-    KnownLongDuration {file: "coscreen.org",duration: "14:36", title: "testing native client with rust"},
-    KnownLongDuration {file: "coscreen.org",duration: "9:02", title: "testing native client with rust"},
-    KnownLongDuration {file: "coscreen.org",duration: "11:03", title: "fix oauth"},
-    KnownLongDuration {file: "coscreen.org",duration: "9:46", title: "remote control for full desktop / display capturing macos"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:46", title: "remote control for full desktop / display capturing macos"},
-    KnownLongDuration {file: "coscreen.org",duration: "9:51", title: "setup"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:28", title: "admin.coscreen.org: retention stats"},
-    KnownLongDuration {file: "coscreen.org",duration: "11:21", title: "REVIEW Capture CPU/System and actual screen resolution info statistics to Cloudwatch at a regular interval. :Beta1.1:"},
-    KnownLongDuration {file: "coscreen.org",duration: "11:14", title: "REVIEW Capture CPU/System and actual screen resolution info statistics to Cloudwatch at a regular interval. :Beta1.1:"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:53", title: "[[https://docs.google.com/spreadsheets/d/1ovnzpuIW7bY0Fexc8HDpfGdtni3ZXWKJqN7y5pviCZ8/edit#gid=0][Till's metrics]]"},
-    KnownLongDuration {file: "coscreen.org",duration: "9:53", title: "[admin panel] Better reporting on teams & team activity"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:15", title: "call 2.0 refactoring"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:01", title: "invite link copying can take very long"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:39", title: "create & make use of @coscreen/backend"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:34", title: "[[file:~/projects/coscreen/coscreen-backend-rs][coscreen-backend-rs]]"},
-    KnownLongDuration {file: "coscreen.org",duration: "14:23", title: "user stats with rust"},
-    KnownLongDuration {file: "coscreen.org",duration: "8:07", title: "building an API prototype"},
-    KnownLongDuration {file: "coscreen.org",duration: "14:48", title: "alerts based on firebase audit logs"},
+    #[arg(
+        long = "no-consecutive-clock-warning",
+        default_value_t = false,
+        help = "Don't warn about clocks that are separated from the previous clock of their headline by a drawer or block. [default: false]"
+    )]
+    no_consecutive_clock_warning: bool,
 
-    KnownLongDuration {file: "google.org", duration: "13:03", title: "[[https://drive.google.com/corp/drive/u/0/folders/1B1TWxkV-1Al8xX5KDu4l-tJlxWBsrgoV][Defcon 26 videos davidtomaschik@]]"},
+    #[arg(
+        long = "strict-consecutive-clocks",
+        default_value_t = false,
+        help = "Also warn about clocks that are separated from the previous clock of their headline by notes or blank lines. [default: false]"
+    )]
+    strict_consecutive_clocks: bool,
 
-    KnownLongDuration {file: "haskell.org", duration: "10:00", title: "coding-challenges"},
+    #[arg(
+        long = "explain",
+        default_value_t = false,
+        help = "Explain what each kind of reported problem means and what commonly causes it, after human or emacs output. [default: false]"
+    )]
+    explain: bool,
 
-    KnownLongDuration {file: "private.org", duration: "9:43", title: "tax return 2020"},
+    #[arg(
+        long = "report-multiday",
+        help = "Report clocks whose end is more than this many calendar days after their start."
+    )]
+    report_multiday: Option<i64>,
 
-    KnownLongDuration {file: "codium.org", duration: "8:27", title: "Codium go backend for genx realizer"},
+    #[arg(
+        long = "format",
+        value_enum,
+        default_value = "human",
+        help = "How to print findings."
+    )]
+    format: OutputFormat,
 
-    KnownLongDuration {file: "projects.org", duration: "8:18", title: "rust hot reloading"},
-    KnownLongDuration {file: "projects.org", duration: "10:27", title: "[2020-02-21] Rust twitter fetch followers / [[file:~/projects/rust/star_counter][rust/star_counter]]"},
-    KnownLongDuration {file: "projects.org", duration: "19:06", title: "twitter yet again 2020-11-29"},
-    KnownLongDuration {file: "projects.org", duration: "9:55", title: "twitter yet again 2020-11-29"},
-    KnownLongDuration {file: "projects.org", duration: "11:41", title: "twitter yet again 2020-11-29"},
-    KnownLongDuration {file: "projects.org", duration: "8:21", title: "[2021-05-21] [[file:~/projects/python/twitter-viz/twipycli][python/twitter-viz/twipycli]] - twitter analysis with python"},
-    KnownLongDuration {file: "projects.org", duration: "8:30", title: "[2022-04-30] twitter analysis one more time [[file:~/projects/rust/twitter-analyzer][rust/twitter-analyzer]]"},
-    KnownLongDuration {file: "projects.org", duration: "21:09", title: "[2022-04-30] twitter analysis one more time [[file:~/projects/rust/twitter-analyzer][rust/twitter-analyzer]]"},
-    KnownLongDuration {file: "projects.org", duration: "10:26", title: "lynn datenauswertung"},
-    KnownLongDuration {file: "projects.org", duration: "10:21", title: "playing around with lisp twitter api via chirp / common lisp"},
-    KnownLongDuration {file: "projects.org", duration: "10:57", title: "[[file:~/projects/rust/fritz-homeautomation][fritz rust app]]"},
-    KnownLongDuration {file: "projects.org", duration: "12:49", title: "[[file:~/projects/rust/homeautomation][homeautomation framework]]"},
-];
+    #[arg(
+        long = "watch",
+        default_value_t = false,
+        help = "Keep running and re-check org files whenever they change. [default: false]"
+    )]
+    watch: bool,
 
-fn main() -> Result<()> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
+    #[arg(
+        long = "report-mixed-timestamp-type",
+        default_value_t = false,
+        help = "Report clocks whose start and end timestamps are of different types, e.g. [...]--<...>. [default: false]"
+    )]
+    report_mixed_timestamp_type: bool,
+
+    #[arg(
+        long = "report-orphan-total",
+        default_value_t = false,
+        help = "Report headlines with a cached total property but no clocks in their subtree. [default: false]"
+    )]
+    report_orphan_total: bool,
 
-    let opts = CheckOrgOptions::parse();
+    #[arg(
+        long = "total-property",
+        default_value = "TOTAL",
+        global = true,
+        help = "Name of the cached total property checked by --report-orphan-total and removed by fix --remove-orphan-totals."
+    )]
+    total_property: String,
 
-    let files = if let Some(files) = &opts.org_files {
-        files.clone()
-    } else {
-        fs::read_dir(&opts.org_dir)?
-            .into_iter()
-            .filter_map(|file| {
-                let file = file.ok()?;
-                if file.file_type().ok()?.is_file()
-                    && file.path().extension() == Some(&OsString::from_str("org").ok()?)
-                {
-                    Some(file.path())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-    };
+    #[arg(
+        long = "quiet",
+        default_value_t = false,
+        help = "Don't print the summary line with the number of findings and the exit code to stderr. The exit code is 1 if there are findings, see --fail-on-severity. [default: false]"
+    )]
+    quiet: bool,
 
-    let org_files = files
-        .iter()
-        .map(OrgFile::from_file)
-        .collect::<Result<Vec<_>>>()?;
+    #[arg(
+        long = "custom-check",
+        value_name = "COMMAND",
+        help = "Run COMMAND with sh -c for each org file, passing the parsed file as JSON on stdin. It prints findings as JSON lines like {\"line\": 2, \"message\": \"...\"}. Can be given multiple times."
+    )]
+    custom_checks: Vec<String>,
 
-    let docs = org_files.iter().map(|ea| ea.document()).collect::<Vec<_>>();
+    #[arg(
+        long = "report-sibling-overlap",
+        default_value_t = false,
+        help = "Report clocks that overlap with a clock of a sibling headline in the same file. [default: false]"
+    )]
+    report_sibling_overlap: bool,
 
-    // check docs
-    println!("checking org files...");
-    for doc in &docs {
-        check_org(doc, &opts);
-    }
+    #[arg(
+        long = "config",
+        value_name = "FILE",
+        global = true,
+        help = "Config file with severities per finding kind. Defaults to .org-linter.toml in --org-dir if it exists."
+    )]
+    config_file: Option<PathBuf>,
 
-    // clock conflicts
-    if opts.report_clock_conflicts {
-        println!("finding clock conflicts...");
-        for conflict in ClockConflict::find_conflicts(&docs) {
-            println!("{}", conflict.report());
-        }
-    } else if opts.fix_clock_conflicts {
-        let mut skipped = HashSet::new();
-        'outer: loop {
-            let org_files = files
-                .iter()
-                .map(OrgFile::from_file)
-                .collect::<Result<Vec<_>>>()?;
-            let docs = org_files.iter().map(|ea| ea.document()).collect::<Vec<_>>();
-            for conflict in ClockConflict::find_conflicts(&docs) {
-                let hash = conflict.hashme();
-                if skipped.contains(&hash) {
-                    continue;
-                }
-                println!("{}", conflict.report());
-                let resolutions = conflict.resolution_options();
-                let options = resolutions
-                    .iter()
-                    .enumerate()
-                    .map(|(i, resolution)| (i, resolution.explanation()))
-                    .collect::<Vec<_>>();
+    #[arg(
+        long = "fail-on-severity",
+        value_name = "SEVERITY",
+        value_parser = parse_severity,
+        default_value = "info",
+        help = "Exit with 1 if there are findings with this severity or higher. One of info, warning, error."
+    )]
+    fail_on_severity: Severity,
 
-                println!("Select resolution:");
+    #[arg(
+        long = "count-only",
+        default_value_t = false,
+        help = "Only print the number of findings, as {\"count\": N} with --format json or summary-json. [default: false]"
+    )]
+    count_only: bool,
 
-                for (i, expl) in options {
-                    println!("  {i}) {expl}");
-                }
-                let mut stdin = std::io::stdin().lock();
-                let selected = loop {
-                    let mut input = String::new();
-                    stdin.read_line(&mut input).expect("readline");
-                    match input.trim().parse::<usize>() {
-                        Ok(i) if i < resolutions.len() => break i,
-                        _ => println!("invalid input"),
-                    };
-                };
-                let resolution = resolutions.get(selected).expect("get resolution");
-                let changes = conflict.resolve(*resolution);
-                if !changes.is_empty() {
-                    FileChange::apply(changes)?;
-                    continue 'outer;
-                } else {
-                    skipped.insert(hash);
-                }
-            }
+    #[arg(
+        long = "limit",
+        value_name = "N",
+        help = "Only print the first N findings. The summary and exit code still count all findings."
+    )]
+    limit: Option<usize>,
 
-            break;
-        }
-    }
+    #[arg(
+        long = "limit-per-file",
+        value_name = "N",
+        help = "Only print the first N findings of each file."
+    )]
+    limit_per_file: Option<usize>,
 
-    Ok(())
-}
+    #[arg(
+        long = "report-wrong-weekday",
+        default_value_t = false,
+        help = "Report clocks whose day of week doesn't match the date. [default: false]"
+    )]
+    report_wrong_weekday: bool,
 
-fn check_org(doc: &OrgDocument, opts: &CheckOrgOptions) {
-    let file_name = doc.file_name();
-    // let long_duration = opts.long_duration.unwrap_or_else(|| Duration::hours(10));
-    let long_duration = opts.long_duration;
+    #[arg(
+        long = "fix-weekday",
+        default_value_t = false,
+        help = "Correct the day of week of clock timestamps. [default: false]"
+    )]
+    fix_weekday: bool,
 
-    for clock in &doc.clocks {
-        let duration_string_raw = clock.duration_string.unwrap_or("");
-        let duration_string = clock.duration_formatted();
-        let headline = &doc.headlines[clock.parent];
-        let title = headline.title;
-        let line = clock.line;
+    #[arg(
+        long = "locale",
+        visible_alias = "weekday-locale",
+        value_name = "LOCALE",
+        value_parser = parse_weekday_locale,
+        help = "Language of the day names in clocks written by the fixers, en or de. With --fix-weekday all clocks are rewritten in it. By default the language of each clock is kept."
+    )]
+    locale: Option<WeekdayLocale>,
 
-        if opts.report_duration_mismatch && !clock.matches_duration() {
-            println!("[{file_name}:{line}] DURATION STRING DOES NOT MATCH: {title:?} ({duration_string_raw} vs {duration_string})");
-        };
+    #[arg(
+        long = "report-tag-daily-cap",
+        value_name = "TAG=H:MM",
+        value_parser = parse_tag_cap,
+        help = "Report days on which the clocks of a tag, including inherited ones, add up to more than the duration across all files. Can be given multiple times."
+    )]
+    tag_daily_caps: Vec<(String, Duration)>,
 
-        if opts.report_long_duration && clock.duration() > long_duration {
-            let allowed = KNOWN_LONG_DURATIONS.iter().any(|k| {
-                file_name.ends_with(k.file) && title == k.title && k.duration == duration_string
-            });
-            if !allowed {
-                println!("[{file_name}:{line}] LONG DURATION: {duration_string} in {title:?}",);
-            }
-        }
+    #[arg(
+        long = "conflict-exclude-tag",
+        value_name = "TAG",
+        global = true,
+        help = "Ignore clocks of headlines with this tag, or below a headline with it, when finding and fixing clock conflicts. Can be given multiple times."
+    )]
+    conflict_exclude_tags: Vec<String>,
 
-        if opts.report_running_clock && clock.is_running() {
-            println!("[{file_name}:{line}] RUNNING CLOCK {title:?}");
-        }
+    #[arg(
+        long = "report-clock-indentation",
+        default_value_t = false,
+        help = "Report clocks in a drawer that are indented differently than the other clocks of the drawer. [default: false]"
+    )]
+    report_clock_indentation: bool,
 
-        if opts.report_negative_duration && clock.duration() < Duration::zero() {
-            println!("[{file_name}:{line}] NEGATIVE DURATION {title:?}: {duration_string}");
-        }
+    #[arg(
+        long = "report-cross-file-duplicate",
+        default_value_t = false,
+        help = "Report clocks with the same start and end as a clock in another file, e.g. after copying a file. [default: false]"
+    )]
+    report_cross_file_duplicate: bool,
 
-        if opts.report_zero_clocks && clock.duration() == Duration::zero() && !clock.is_running() {
-            println!("[{file_name}:{line}] ZERO DURATION {title:?}: {duration_string}");
-        }
+    #[arg(
+        long = "report-level-skip",
+        default_value_t = false,
+        help = "Report headlines that are more than one level deeper than their parent. [default: false]"
+    )]
+    report_level_skip: bool,
+
+    #[arg(
+        long = "report-headless-file",
+        default_value_t = false,
+        help = "Report files that contain clocks but no headlines. [default: false]"
+    )]
+    report_headless_file: bool,
+
+    #[arg(
+        long = "round-report",
+        value_name = "MINUTES",
+        global = true,
+        help = "Round the totals of reports, like the total of --format summary-json, to multiples of MINUTES, e.g. 15 for quarter hours. Findings always use exact minutes."
+    )]
+    round_report: Option<i64>,
+
+    #[arg(
+        long = "json-schema",
+        default_value_t = false,
+        help = "Print the JSON Schema of the output of --format json and summary-json and exit. [default: false]"
+    )]
+    json_schema: bool,
+
+    #[arg(
+        long = "report-midnight-placeholder",
+        default_value_t = false,
+        help = "Report running clocks that start at --placeholder-time, usually left over from capture templates. [default: false]"
+    )]
+    report_midnight_placeholder: bool,
+
+    #[arg(
+        long = "placeholder-time",
+        value_name = "HH:MM",
+        value_parser = parse_time_from_cli,
+        default_value = "00:00",
+        help = "Start time of running clocks reported by --report-midnight-placeholder."
+    )]
+    placeholder_time: NaiveTime,
+
+    #[arg(
+        long = "timing",
+        default_value_t = false,
+        help = "Print how long reading and parsing each file took, and the slowest files, to stderr. [default: false]"
+    )]
+    timing: bool,
+
+    #[arg(
+        long = "report-misplaced-appointment",
+        default_value_t = false,
+        help = "Report clocks with an active timestamp <...> in the future, most likely appointments placed in a LOGBOOK by mistake. [default: false]"
+    )]
+    report_misplaced_appointment: bool,
+
+    #[arg(
+        long = "report-duplicate-tags",
+        default_value_t = false,
+        help = "Report headlines that have the same tag more than once, e.g. :work:work:. [default: false]"
+    )]
+    report_duplicate_tags: bool,
+
+    #[arg(
+        long = "heading-char",
+        value_name = "CHAR",
+        default_value_t = '*',
+        help = "Character marking headlines, repeated once per level, for files that use e.g. # instead of *."
+    )]
+    heading_char: char,
+
+    #[arg(
+        long = "headline-pattern",
+        value_name = "REGEX",
+        value_parser = parse_headline_pattern,
+        help = "Detect headlines with this regex instead of the org syntax, for unusual imported files. Group 1 is the level marker, its length the level, group 2 title and tags."
+    )]
+    headline_pattern: Option<Regex>,
+
+    #[arg(
+        long = "report-running-under-done",
+        default_value_t = false,
+        help = "Report running clocks below a headline in a done state, see --done-keyword. [default: false]"
+    )]
+    report_running_under_done: bool,
+
+    #[arg(
+        long = "done-keyword",
+        value_name = "KEYWORD",
+        default_value = "DONE",
+        help = "TODO keyword of finished tasks for --report-running-under-done. Can be given multiple times."
+    )]
+    done_keywords: Vec<String>,
+
+    #[arg(
+        long = "follow-includes",
+        default_value_t = false,
+        help = "Also check the files included with #+INCLUDE, relative to the including file. Missing files and include cycles are reported as warnings. [default: false]"
+    )]
+    follow_includes: bool,
+
+    #[arg(
+        long = "assert-no-running",
+        default_value_t = false,
+        help = "Only check for running clocks: print them and exit with 1 if there are any, with 0 otherwise. For scripts, e.g. before suspending. [default: false]"
+    )]
+    assert_no_running: bool,
+
+    #[arg(
+        long = "report-dst-spanning",
+        default_value_t = false,
+        help = "Note clocks that cross a DST change. Their => total may be wall clock or elapsed time and is not reported as mismatch either way. [default: false]"
+    )]
+    report_dst_spanning: bool,
+
+    #[arg(
+        long = "max-line-length",
+        value_name = "BYTES",
+        default_value_t = 64 * 1024,
+        help = "Skip lines longer than this, with a warning, instead of parsing them. Protects against e.g. minified exports pasted into a file."
+    )]
+    max_line_length: usize,
+
+    #[arg(
+        long = "report-split-subtree",
+        default_value_t = false,
+        help = "Report headlines that have clocks in both a file and its archive file, e.g. foo.org and foo_archive.org, which usually means archiving was incomplete. [default: false]"
+    )]
+    report_split_subtree: bool,
+
+    #[arg(
+        long = "json-pretty",
+        default_value_t = false,
+        global = true,
+        help = "Indent JSON output of --format json and summary-json, merge and diff, e.g. for golden files under version control. [default: false]"
+    )]
+    json_pretty: bool,
+
+    #[arg(
+        long = "report-stale-archive-stub",
+        value_name = "DATE",
+        value_parser = parse_date_from_cli,
+        help = "Report clocks from DATE or later below a done headline with the ARCHIVE tag, usually a stub left behind when archiving. See --done-keyword."
+    )]
+    report_stale_archive_stub: Option<NaiveDate>,
+
+    #[arg(
+        long = "report-stale-running",
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Report running clocks that started more than DURATION ago, e.g. 12:00. Measured up to --now if given."
+    )]
+    report_stale_running: Option<Duration>,
+
+    #[arg(
+        long = "now",
+        value_name = "DATETIME",
+        value_parser = parse_datetime_from_cli,
+        global = true,
+        hide = true,
+        help = "Use this time, YYYY-MM-DD HH:MM, instead of the current time for running clocks and other checks that depend on it. For tests and reviews as of a past date."
+    )]
+    now: Option<NaiveDateTime>,
+
+    #[arg(
+        long = "report-broken-links",
+        default_value_t = false,
+        help = "Report file: links in headline titles to files that do not exist. Relative paths start at the directory of the org file. [default: false]"
+    )]
+    report_broken_links: bool,
+
+    #[arg(
+        long = "report-stale-clocking",
+        value_name = "DAYS",
+        help = "Report files modified more than DAYS days after their newest clock, e.g. notes added without clocking time."
+    )]
+    report_stale_clocking: Option<u32>,
+
+    #[arg(
+        long = "strict",
+        default_value_t = false,
+        help = "Treat warnings as errors: report parser warnings like clocks before the first headline as findings, report headlines that skip a level and raise findings of warning severity to errors. [default: false]"
+    )]
+    strict: bool,
+
+    #[arg(
+        long = "show-suppressed",
+        default_value_t = false,
+        help = "Also print findings that are suppressed, e.g. long clocks in the allowlist of known long durations, marked with the reason like (suppressed: allowlist). They don't count towards the exit code. [default: false]"
+    )]
+    show_suppressed: bool,
+
+    #[arg(
+        long = "min-confidence",
+        value_name = "CONFIDENCE",
+        default_value_t = 0.0,
+        value_parser = parse_confidence,
+        help = "Drop findings of heuristic checks with a confidence below CONFIDENCE, from 0 to 1, e.g. long clocks just past --long-duration. Findings of exact checks always have a confidence of 1."
+    )]
+    min_confidence: f32,
+
+    #[arg(
+        long = "max-in-flight",
+        value_name = "N",
+        help = "Check files concurrently with at most N files read but not yet checked, to bound memory use. Only applies when no check compares files, like --report-clock-conflicts, and without --timing."
+    )]
+    max_in_flight: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "report-rounding-inconsistency",
+        default_value_t = false,
+        help = "Report clocks with seconds like [10:00:40] whose => total is neither the duration rounded down nor rounded to the nearest minute, e.g. totals computed by another tool, instead of as a duration mismatch. [default: false]"
+    )]
+    report_rounding_inconsistency: bool,
+
+    #[arg(
+        long = "org-dir",
+        global = true,
+        help = "The org directory that contains the org files. [default: ~/org]",
+        group = "org-dir"
+    )]
+    org_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "recursive",
+        global = true,
+        default_value_t = false,
+        help = "Recursively find .org files in --org-dir. [default: false]"
+    )]
+    recursive: bool,
+
+    #[arg(
+        long = "markdown",
+        global = true,
+        default_value_t = false,
+        help = "Also read .md files in --org-dir and lint the content of their ```org code blocks. [default: false]"
+    )]
+    markdown: bool,
+
+    #[arg(
+        long = "org-file",
+        global = true,
+        help = "Specify individual org files to lint. Overrides --org-dir."
+    )]
+    org_files: Option<Vec<PathBuf>>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// One line per finding.
+    Human,
+    /// A JSON array of findings.
+    Json,
+    /// A JSON object with stats about the org files and the findings.
+    SummaryJson,
+    /// `file:line:column: message` lines for Emacs' compilation-mode.
+    Emacs,
+    /// `::warning file=...` workflow commands for GitHub Actions annotations.
+    Github,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Modify org files to fix problems.
+    Fix(FixOptions),
+    /// Print how much time was clocked.
+    Report(ReportOptions),
+    /// Combine the findings of several --format json runs, dropping duplicates.
+    Merge(MergeOptions),
+    /// Print the clocks matching a query.
+    Query(QueryOptions),
+    /// Lint two versions of an org directory and print the findings that were
+    /// added or removed.
+    Diff(DiffOptions),
+    /// Write the headlines and clocks of the org files to a database.
+    #[cfg(feature = "sqlite")]
+    Export(ExportOptions),
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Args)]
+struct ExportOptions {
+    #[arg(
+        long = "sqlite",
+        value_name = "FILE",
+        help = "SQLite database to write, replaced if it exists."
+    )]
+    sqlite: PathBuf,
+}
+
+#[derive(Args, Clone)]
+struct DiffOptions {
+    /// The org directory before the change.
+    old_dir: PathBuf,
+    /// The org directory after the change.
+    new_dir: PathBuf,
+}
+
+#[derive(Args)]
+struct QueryOptions {
+    /// Predicates combined with and, or and not, e.g.
+    /// "duration > 2:00 and tag:work and date >= 2023-01-01".
+    ///
+    /// duration OP DURATION: length of finished clocks, e.g. 2:00, 90m or 1.5h.
+    /// date OP YYYY-MM-DD: start date of the clock.
+    /// tag:NAME: the headline, an ancestor or the file has the tag.
+    /// running: the clock has no end.
+    ///
+    /// OP is one of =, !=, <, <=, > and >=. and binds stronger than or.
+    #[arg(verbatim_doc_comment)]
+    expression: Query,
+}
+
+#[derive(Args)]
+struct MergeOptions {
+    /// Files with the output of --format json or summary-json.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct ReportOptions {
+    #[arg(
+        long = "by-project",
+        default_value_t = false,
+        help = "Total time per project, i.e. per top-level headline, across all files. [default: false]"
+    )]
+    by_project: bool,
+
+    #[arg(
+        long = "estimation",
+        default_value_t = false,
+        conflicts_with = "by_project",
+        help = "Effort against clocked time of the headlines with an :Effort: property, including the time of subheadlines, sorted by the largest difference. [default: false]"
+    )]
+    estimation: bool,
+
+    #[arg(
+        long = "coverage",
+        default_value_t = false,
+        conflicts_with_all = ["by_project", "estimation"],
+        help = "Times within the working hours that no clock covers, for each working day from --from to --to. [default: false]"
+    )]
+    coverage: bool,
+
+    #[arg(
+        long = "invoice",
+        default_value_t = false,
+        conflicts_with_all = ["by_project", "estimation", "coverage"],
+        help = "CSV of the time per day and task, i.e. headline path, for invoicing. Rounded per row with --round-report. [default: false]"
+    )]
+    invoice: bool,
+
+    #[arg(
+        long = "weekly-email",
+        default_value_t = false,
+        conflicts_with_all = ["by_project", "estimation", "coverage", "invoice"],
+        help = "Markdown summary of a week for a review: total, projects, days, long sessions and clock conflicts. [default: false]"
+    )]
+    weekly_email: bool,
+
+    #[arg(
+        long = "monthly-table",
+        default_value_t = false,
+        conflicts_with_all = ["by_project", "estimation", "coverage", "invoice", "weekly_email"],
+        help = "Org table of the total time per month, with a total row. [default: false]"
+    )]
+    monthly_table: bool,
+
+    #[arg(
+        long = "tags",
+        default_value_t = false,
+        conflicts_with_all = ["by_project", "estimation", "coverage", "invoice", "weekly_email", "monthly_table"],
+        help = "Every tag with the number of headlines using it and the time clocked under it, including inherited tags, most time first. [default: false]"
+    )]
+    tags: bool,
+
+    #[arg(
+        long = "long-session",
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        default_value = "4:00",
+        help = "Clocks of at least this duration are listed as long sessions by --weekly-email."
+    )]
+    long_session: Duration,
+
+    #[arg(
+        long = "tag",
+        value_name = "TAG",
+        help = "Only include the clocks of headlines with this tag, including inherited ones, in --invoice."
+    )]
+    tag: Option<String>,
+
+    #[arg(
+        long = "hours",
+        value_enum,
+        default_value = "decimal",
+        help = "How --invoice writes hours."
+    )]
+    hours: HoursFormat,
+
+    #[arg(
+        long = "work-start",
+        value_name = "HH:MM",
+        value_parser = parse_time_from_cli,
+        default_value = "09:00",
+        help = "Start of the working hours for --coverage, HH:MM."
+    )]
+    work_start: NaiveTime,
+
+    #[arg(
+        long = "work-end",
+        value_name = "HH:MM",
+        value_parser = parse_time_from_cli,
+        default_value = "17:00",
+        help = "End of the working hours for --coverage, HH:MM."
+    )]
+    work_end: NaiveTime,
+
+    #[arg(
+        long = "work-days",
+        value_name = "DAYS",
+        value_parser = parse_weekday,
+        value_delimiter = ',',
+        default_value = "mon,tue,wed,thu,fri",
+        help = "Comma-separated days with working hours for --coverage."
+    )]
+    work_days: Vec<Weekday>,
+
+    #[arg(
+        long = "from",
+        value_name = "DATE",
+        value_parser = parse_date_from_cli,
+        visible_alias = "since",
+        help = "First day of --coverage, --invoice and --weekly-email, YYYY-MM-DD. Defaults to the day of the first clock, for --weekly-email to Monday of the current week."
+    )]
+    from: Option<NaiveDate>,
+
+    #[arg(
+        long = "to",
+        value_name = "DATE",
+        value_parser = parse_date_from_cli,
+        visible_alias = "until",
+        help = "Last day of --coverage, --invoice and --weekly-email, YYYY-MM-DD. Defaults to the day of the last clock, for --weekly-email to six days after --from."
+    )]
+    to: Option<NaiveDate>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum HoursFormat {
+    /// Decimal hours like 1.5, for spreadsheet formulas.
+    Decimal,
+    /// H:MM like 1:30.
+    Clock,
+}
+
+#[derive(Args)]
+struct FixOptions {
+    #[arg(
+        long = "conflicts",
+        default_value_t = false,
+        help = "Fix clock conflicts, i.e. clocks that overlap. Interactive unless --auto is given. [default: false]"
+    )]
+    conflicts: bool,
+
+    #[arg(
+        long = "auto",
+        default_value_t = false,
+        help = "Resolve clock conflicts without asking until none are left. [default: false]"
+    )]
+    auto: bool,
+
+    #[arg(
+        long = "max-rounds",
+        default_value_t = 1000,
+        help = "Give up on --auto conflict resolution if conflicts remain after this many resolutions."
+    )]
+    max_rounds: usize,
+
+    #[arg(
+        long = "pad-minutes",
+        default_value_t = false,
+        help = "Zero pad the minutes of clock durations, e.g. 1:3 becomes 1:03. [default: false]"
+    )]
+    pad_minutes: bool,
+
+    #[arg(
+        long = "normalize-timestamp-types",
+        default_value_t = false,
+        help = "Rewrite clocks that mix active and inactive timestamps with inactive ones. [default: false]"
+    )]
+    normalize_timestamp_types: bool,
+
+    #[arg(
+        long = "normalize",
+        default_value_t = false,
+        help = "Rewrite all clock lines in canonical form: day names, spacing, zero padding and recomputed totals. Day names are written in --locale if given. [default: false]"
+    )]
+    normalize: bool,
+
+    #[arg(
+        long = "remove-orphan-totals",
+        default_value_t = false,
+        help = "Remove the --total-property of headlines that have no clocks in their subtree. [default: false]"
+    )]
+    remove_orphan_totals: bool,
+
+    #[arg(
+        long = "close-running-under-done",
+        default_value_t = false,
+        help = "End running clocks below a headline in a done state at the CLOSED time of the headline, see --done-keyword. [default: false]"
+    )]
+    close_running_under_done: bool,
+
+    #[arg(
+        long = "dedupe-tags",
+        default_value_t = false,
+        help = "Remove repeated tags of headlines, keeping the first of each. [default: false]"
+    )]
+    dedupe_tags: bool,
+
+    #[arg(
+        long = "clock-indentation",
+        default_value_t = false,
+        help = "Indent clocks in drawers like the other clocks of the drawer. [default: false]"
+    )]
+    clock_indentation: bool,
+
+    #[arg(
+        long = "all",
+        default_value_t = false,
+        help = "Run all automatic fixers. Fixes that touch a line another fixer already changes are skipped with a warning. [default: false]"
+    )]
+    all: bool,
+
+    #[arg(
+        long = "dry-run",
+        default_value_t = false,
+        requires = "all",
+        help = "With --all, print the changes as a diff instead of writing them. [default: false]"
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long = "split",
+        num_args = 2,
+        value_names = ["FILE:LINE", "HH:MM"],
+        help = "Split the clock on LINE of FILE at the first HH:MM after its start. The later part is written above the earlier one."
+    )]
+    split: Option<Vec<String>>,
+}
+
+impl CheckOrgOptions {
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            warn_non_consecutive_clocks: !self.no_consecutive_clock_warning,
+            allow_notes_between_clocks: !self.strict_consecutive_clocks,
+            heading_char: self.heading_char,
+            headline_pattern: self.headline_pattern.clone(),
+            max_line_length: self.max_line_length,
+            locale: self.locale,
+            ..Default::default()
+        }
+    }
+
+    fn lint_options(&self) -> LintOptions {
+        let mut opts = LintOptions {
+            report_long_duration: self.report_long_duration,
+            long_duration: self.long_duration,
+            long_duration_inclusive: self.long_duration_inclusive,
+            known_long_durations: KNOWN_LONG_DURATIONS,
+            report_duration_mismatch: self.report_duration_mismatch,
+            report_running_clock: self.report_running_clock,
+            report_negative_duration: self.report_negative_duration,
+            report_zero_clocks: self.report_zero_clocks,
+            report_phantom_duration: self.report_phantom_duration,
+            report_unsorted_clocks: self.report_unsorted_clocks,
+            report_nonpadded_minutes: self.report_nonpadded_minutes,
+            report_missing_id: self.report_missing_id,
+            id_property: self.id_property.clone(),
+            report_multiday: self.report_multiday,
+            report_mixed_timestamp_type: self.report_mixed_timestamp_type,
+            report_orphan_total: self.report_orphan_total,
+            total_property: self.total_property.clone(),
+            report_sibling_overlap: self.report_sibling_overlap,
+            report_wrong_weekday: self.report_wrong_weekday,
+            report_clock_indentation: self.report_clock_indentation,
+            report_cross_file_duplicate: self.report_cross_file_duplicate,
+            report_level_skip: self.report_level_skip,
+            report_headless_file: self.report_headless_file,
+            report_midnight_placeholder: self.report_midnight_placeholder,
+            placeholder_time: self.placeholder_time,
+            report_misplaced_appointment: self.report_misplaced_appointment,
+            now: self.now().naive_local(),
+            report_duplicate_tags: self.report_duplicate_tags,
+            tag_daily_caps: self.tag_daily_caps.clone(),
+            report_running_under_done: self.report_running_under_done,
+            done_keywords: self.done_keywords.clone(),
+            report_dst_spanning: self.report_dst_spanning,
+            report_split_subtree: self.report_split_subtree,
+            report_stale_archive_stub: self.report_stale_archive_stub,
+            report_stale_running: self.report_stale_running,
+            report_broken_links: self.report_broken_links,
+            report_stale_clocking: self.report_stale_clocking,
+            strict: self.strict,
+            show_suppressed: self.show_suppressed,
+            min_confidence: self.min_confidence,
+            report_rounding_inconsistency: self.report_rounding_inconsistency,
+            severities: Default::default(),
+        };
+        self.config.apply(&mut opts);
+        opts
+    }
+
+    /// Reads --config, or the config file in --org-dir if there is one.
+    fn load_config(&mut self) -> Result<()> {
+        if let Ok(org_dir) = self.org_dir() {
+            self.config = self.config_in(&org_dir)?;
+        } else if let Some(file) = &self.config_file {
+            self.config = Config::from_file(file)?;
+        }
+        Ok(())
+    }
+
+    /// --config, or the config file in `org_dir` if there is one.
+    fn config_in(&self, org_dir: &Path) -> Result<Config> {
+        if let Some(file) = &self.config_file {
+            return Config::from_file(file);
+        }
+        let file = org_dir.join(Config::FILE_NAME);
+        if file.exists() {
+            Config::from_file(file)
+        } else {
+            Ok(Config::default())
+        }
+    }
+
+    /// The current time, or --now if given.
+    fn now(&self) -> DateTime<Local> {
+        self.now
+            .and_then(|now| Local.from_local_datetime(&now).earliest())
+            .unwrap_or_else(Local::now)
+    }
+
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.now().with_timezone(&Utc)
+    }
+
+    /// Whether a pass needs all documents in memory at once: clock conflicts,
+    /// cross-file duplicates, split subtrees, tag daily caps and the summary
+    /// stats. Otherwise files are checked one by one.
+    fn needs_all_documents(&self) -> bool {
+        self.report_clock_conflicts
+            || self.report_cross_file_duplicate
+            || self.report_split_subtree
+            || !self.tag_daily_caps.is_empty()
+            || (self.format == OutputFormat::SummaryJson && !self.count_only)
+    }
+
+    /// The directory paths in fingerprints are relative to: the org directory
+    /// or, with --org-file, the current directory.
+    fn fingerprint_root(&self) -> Result<PathBuf> {
+        match &self.org_files {
+            Some(_) => Ok(std::env::current_dir()?),
+            None => self.org_dir(),
+        }
+    }
+
+    /// --org-dir or, if not given, ~/org.
+    fn org_dir(&self) -> Result<PathBuf> {
+        match &self.org_dir {
+            Some(org_dir) => Ok(org_dir.clone()),
+            #[allow(deprecated)]
+            None => default_org_dir(std::env::home_dir()),
+        }
+    }
+}
+
+/// `~/org`. Errors instead of guessing if there is no home directory, e.g. in
+/// some containers.
+fn default_org_dir(home_dir: Option<PathBuf>) -> Result<PathBuf> {
+    home_dir.map(|home| home.join("org")).ok_or_else(|| {
+        anyhow::anyhow!(
+            "cannot determine the home directory for the default --org-dir ~/org, please pass --org-dir or --org-file"
+        )
+    })
+}
+
+fn parse_weekday_locale(s: &str) -> Result<WeekdayLocale, String> {
+    s.parse().map_err(|err: anyhow::Error| err.to_string())
+}
+
+fn parse_tag_cap(s: &str) -> Result<(String, Duration), String> {
+    let (tag, cap) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected TAG=H:MM, got {s:?}"))?;
+    Ok((tag.trim().to_string(), parse_duration(cap)?))
+}
+
+fn parse_confidence(s: &str) -> Result<f32, String> {
+    s.trim()
+        .parse()
+        .ok()
+        .filter(|confidence| (0.0..=1.0).contains(confidence))
+        .ok_or_else(|| format!("expected a number from 0 to 1, got {s:?}"))
+}
+
+fn parse_headline_pattern(s: &str) -> Result<Regex, String> {
+    headline_pattern(s).map_err(|err| err.to_string())
+}
+
+fn parse_severity(s: &str) -> Result<Severity, String> {
+    s.parse().map_err(|err: anyhow::Error| err.to_string())
+}
+
+fn parse_date_from_cli(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("cannot parse date {s:?}, expected YYYY-MM-DD"))
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("cannot parse day {s:?}, expected e.g. mon"))
+}
+
+fn parse_datetime_from_cli(s: &str) -> Result<NaiveDateTime, String> {
+    let s = s.trim();
+    ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| parse_date_from_cli(s).ok()?.and_hms_opt(0, 0, 0))
+        .ok_or_else(|| format!("cannot parse time {s:?}, expected YYYY-MM-DD HH:MM"))
+}
+
+fn parse_time_from_cli(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| format!("cannot parse time {s:?}, expected HH:MM"))
+}
+
+#[rustfmt::skip]
+const KNOWN_LONG_DURATIONS: &[KnownLongDuration] = &[
+    KnownLongDuration {file:"clockin.org", duration: "12:59", title: "privacy setup"},
+    KnownLongDuration {file:"clockin.org", duration: "9:10", title: "ClojureD"},
+    KnownLongDuration {file:"clockin.org", duration: "11:04", title: "Testing wasm"},
+    KnownLongDuration {file:"clockin.org", duration: "10:08", title: "emacs python setup"},
+    KnownLongDuration {file:"clockin.org", duration: "9:47", title: "[[file:books.org][organizing my books]]"},
+    KnownLongDuration {file:"clockin.org", duration: "10:04", title: "Testing live reload with rust [[https://fasterthanli.me/articles/so-you-want-to-live-reload-rust][So you want to live-reload Rust - fasterthanli.me]]"},
+    KnownLongDuration {file:"clockin.org", duration: "9:22", title: "blog post: how does bevy component query work?"},
+    KnownLongDuration {file:"clockin.org", duration: "8:08", title: "blog post: setting up a Rust web / wasm project like it's 2022"},
+
+    KnownLongDuration {file: "coscreen.org",duration: "9:30", title: "Create objective means to profile and determine end to end latency that users perceive when interacting with our user interface."},
+    KnownLongDuration {file: "coscreen.org",duration: "10:41", title: "DONE implement messaging on top of electrons window messaging api"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:23", title: "mojave user gets extra \"coscreen helper\" permission request"},
+    KnownLongDuration {file: "coscreen.org",duration: "9:18", title: "single window picking"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:04", title: "[node-wrtc] capture window content"},
+    KnownLongDuration {file: "coscreen.org",duration: "9:48", title: "[node-wrtc] capture window content"},
+    KnownLongDuration {file: "coscreen.org",duration: "9:44", title: "i420 yuv conversion"},
+    KnownLongDuration {file: "coscreen.org",duration: "11:29", title: "ACTIVE profiling support for coscreen native"},
+    KnownLongDuration {file: "coscreen.org",duration: "11:47", title: "Learning about GTK & libwebrtc screen capturing"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:13", title: "sending libwebrtc screen capture to browser"},
+    KnownLongDuration {file: "coscreen.org",duration: "14:36", title: "testing native client with rust"},
+    KnownLongDuration {file: "coscreen.org",duration: "9:02", title: "testing native client with rust"},
+    KnownLongDuration {file: "coscreen.org",duration: "11:03", title: "fix oauth"},
+    KnownLongDuration {file: "coscreen.org",duration: "9:46", title: "remote control for full desktop / display capturing macos"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:46", title: "remote control for full desktop / display capturing macos"},
+    KnownLongDuration {file: "coscreen.org",duration: "9:51", title: "setup"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:28", title: "admin.coscreen.org: retention stats"},
+    KnownLongDuration {file: "coscreen.org",duration: "11:21", title: "REVIEW Capture CPU/System and actual screen resolution info statistics to Cloudwatch at a regular interval. :Beta1.1:"},
+    KnownLongDuration {file: "coscreen.org",duration: "11:14", title: "REVIEW Capture CPU/System and actual screen resolution info statistics to Cloudwatch at a regular interval. :Beta1.1:"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:53", title: "[[https://docs.google.com/spreadsheets/d/1ovnzpuIW7bY0Fexc8HDpfGdtni3ZXWKJqN7y5pviCZ8/edit#gid=0][Till's metrics]]"},
+    KnownLongDuration {file: "coscreen.org",duration: "9:53", title: "[admin panel] Better reporting on teams & team activity"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:15", title: "call 2.0 refactoring"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:01", title: "invite link copying can take very long"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:39", title: "create & make use of @coscreen/backend"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:34", title: "[[file:~/projects/coscreen/coscreen-backend-rs][coscreen-backend-rs]]"},
+    KnownLongDuration {file: "coscreen.org",duration: "14:23", title: "user stats with rust"},
+    KnownLongDuration {file: "coscreen.org",duration: "8:07", title: "building an API prototype"},
+    KnownLongDuration {file: "coscreen.org",duration: "14:48", title: "alerts based on firebase audit logs"},
+
+    KnownLongDuration {file: "google.org", duration: "13:03", title: "[[https://drive.google.com/corp/drive/u/0/folders/1B1TWxkV-1Al8xX5KDu4l-tJlxWBsrgoV][Defcon 26 videos davidtomaschik@]]"},
+
+    KnownLongDuration {file: "haskell.org", duration: "10:00", title: "coding-challenges"},
+
+    KnownLongDuration {file: "private.org", duration: "9:43", title: "tax return 2020"},
+
+    KnownLongDuration {file: "codium.org", duration: "8:27", title: "Codium go backend for genx realizer"},
+
+    KnownLongDuration {file: "projects.org", duration: "8:18", title: "rust hot reloading"},
+    KnownLongDuration {file: "projects.org", duration: "10:27", title: "[2020-02-21] Rust twitter fetch followers / [[file:~/projects/rust/star_counter][rust/star_counter]]"},
+    KnownLongDuration {file: "projects.org", duration: "19:06", title: "twitter yet again 2020-11-29"},
+    KnownLongDuration {file: "projects.org", duration: "9:55", title: "twitter yet again 2020-11-29"},
+    KnownLongDuration {file: "projects.org", duration: "11:41", title: "twitter yet again 2020-11-29"},
+    KnownLongDuration {file: "projects.org", duration: "8:21", title: "[2021-05-21] [[file:~/projects/python/twitter-viz/twipycli][python/twitter-viz/twipycli]] - twitter analysis with python"},
+    KnownLongDuration {file: "projects.org", duration: "8:30", title: "[2022-04-30] twitter analysis one more time [[file:~/projects/rust/twitter-analyzer][rust/twitter-analyzer]]"},
+    KnownLongDuration {file: "projects.org", duration: "21:09", title: "[2022-04-30] twitter analysis one more time [[file:~/projects/rust/twitter-analyzer][rust/twitter-analyzer]]"},
+    KnownLongDuration {file: "projects.org", duration: "10:26", title: "lynn datenauswertung"},
+    KnownLongDuration {file: "projects.org", duration: "10:21", title: "playing around with lisp twitter api via chirp / common lisp"},
+    KnownLongDuration {file: "projects.org", duration: "10:57", title: "[[file:~/projects/rust/fritz-homeautomation][fritz rust app]]"},
+    KnownLongDuration {file: "projects.org", duration: "12:49", title: "[[file:~/projects/rust/homeautomation][homeautomation framework]]"},
+];
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
+
+    let mut opts = CheckOrgOptions::parse();
+    if opts.json_schema {
+        println!("{}", serde_json::to_string_pretty(&json_schema())?);
+        return Ok(());
+    }
+    if let Some(Command::Merge(merge_opts)) = &opts.command {
+        return merge_finding_files(&merge_opts.files, opts.json_pretty);
+    }
+    if let Some(Command::Diff(diff_opts)) = &opts.command {
+        // each tree is checked with its own config
+        let diff_opts = diff_opts.clone();
+        let parse_options = opts.parse_options();
+        return run_diff(&diff_opts, &mut opts, &parse_options);
+    }
+    opts.load_config()?;
+    let parse_options = opts.parse_options();
+    let files = find_org_files(&opts)?;
+
+    if opts.assert_no_running {
+        return assert_no_running(&files, &parse_options);
+    }
+
+    match &opts.command {
+        Some(Command::Fix(fix_opts)) => return run_fix(fix_opts, &opts, &files, &parse_options),
+        Some(Command::Report(report_opts)) => {
+            return run_report(report_opts, &opts, &files, &parse_options)
+        }
+        Some(Command::Query(query_opts)) => {
+            return run_query(&query_opts.expression, &files, &parse_options)
+        }
+        #[cfg(feature = "sqlite")]
+        Some(Command::Export(export_opts)) => {
+            let org_files = files
+                .iter()
+                .map(OrgFile::from_file)
+                .collect::<Result<Vec<_>>>()?;
+            let docs = org_files
+                .iter()
+                .map(|org_file| org_file.document_with_options(&parse_options))
+                .collect::<Vec<_>>();
+            return org_linter::export_sqlite(&docs, &export_opts.sqlite);
+        }
+        Some(Command::Merge(_) | Command::Diff(_)) | None => {}
+    }
+
+    if opts.watch {
+        return watch_org_files(&opts, &files, parse_options);
+    }
+
+    // one current time for every file and pass of this run
+    opts.now.get_or_insert_with(|| Local::now().naive_local());
+    let lint_options = opts.lint_options();
+
+    // Without passes across files each file is dropped once it is checked.
+    let keep_documents = opts.needs_all_documents();
+    let mut timings = Timings::new(opts.timing);
+    let org_files = if keep_documents {
+        files
+            .iter()
+            .map(|file| timings.read(file))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        Vec::new()
+    };
+
+    let docs = org_files
+        .iter()
+        .enumerate()
+        .map(|(i, org_file)| timings.parse(i, org_file, &parse_options))
+        .collect::<Vec<_>>();
+
+    // with --count-only nothing but the count is printed
+    let human = opts.format == OutputFormat::Human && !opts.count_only;
+
+    // check docs
+    if human {
+        println!("checking org files...");
+    }
+    let mut findings = Vec::new();
+    let mut limit = OutputLimit::new(opts.limit, opts.limit_per_file);
+    let mut check = |doc: &OrgDocument| {
+        let doc_findings = check_org(doc, &opts, &lint_options);
+        if human {
+            for finding in &doc_findings {
+                if limit.admit(finding) {
+                    println!("{}", human_line(finding));
+                }
+            }
+        }
+        findings.extend(doc_findings);
+    };
+    if keep_documents {
+        docs.iter().for_each(&mut check);
+    } else if let Some(max_in_flight) = opts.max_in_flight.filter(|_| !opts.timing) {
+        let checked = check_files_pipelined(&files, max_in_flight, &parse_options, |doc| {
+            check_org(doc, &opts, &lint_options)
+        })?;
+        checked.iter().for_each(|finding| {
+            if human && limit.admit(finding) {
+                println!("{}", human_line(finding));
+            }
+        });
+        findings.extend(checked);
+    } else {
+        for (i, file) in files.iter().enumerate() {
+            let org_file = timings.read(file)?;
+            check(&timings.parse(i, &org_file, &parse_options));
+        }
+    }
+    if let Some(summary) = timings.summary() {
+        eprint!("{summary}");
+    }
+
+    for finding in lint_documents(&docs, &lint_options) {
+        if human && limit.admit(&finding) {
+            println!("{}", human_line(&finding));
+        }
+        findings.push(finding);
+    }
+
+    // clock conflicts
+    if opts.report_clock_conflicts {
+        if human {
+            println!("finding clock conflicts...");
+        }
+        for conflict in ClockConflict::find_conflicts_excluding(
+            &docs,
+            &opts.conflict_exclude_tags,
+            opts.now_utc(),
+        ) {
+            let mut finding = conflict.finding();
+            if human && limit.admit(&finding) {
+                println!("{}", conflict.report());
+            }
+            finding.severity = lint_options.severity(finding.kind);
+            findings.push(finding);
+        }
+    } else if opts.fix_clock_conflicts {
+        fix_conflicts_interactively(
+            &files,
+            &parse_options,
+            &opts.conflict_exclude_tags,
+            opts.now_utc(),
+        )?;
+    }
+
+    if opts.merge_adjacent {
+        if human {
+            println!("merging adjacent clocks...");
+        }
+        // read again, fixing conflicts may have changed the files
+        fix_files(&files, &parse_options, |doc, _| {
+            let changes = merge_adjacent(doc);
+            let rejected = changes
+                .iter()
+                .filter_map(|change| change.negative_clock("merge-adjacent"))
+                .collect::<Vec<_>>();
+            if !rejected.is_empty() {
+                for finding in rejected {
+                    eprintln!("{finding}");
+                }
+                return Ok(None);
+            }
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            if human {
+                println!(
+                    "[{}] merging {} clock changes",
+                    doc.file_name(),
+                    changes.len()
+                );
+            }
+            doc.apply_changes(changes).map(Some)
+        })?;
+    }
+
+    let root = opts.fingerprint_root()?;
+    for finding in &mut findings {
+        finding.fingerprint = Some(finding.fingerprint_in(&root));
+    }
+
+    // suppressed findings are only shown, with --show-suppressed
+    let reported = findings
+        .iter()
+        .filter(|finding| finding.suppressed.is_none())
+        .collect::<Vec<_>>();
+    if opts.count_only {
+        match opts.format {
+            OutputFormat::Human | OutputFormat::Emacs | OutputFormat::Github => {
+                println!("{}", reported.len())
+            }
+            OutputFormat::Json | OutputFormat::SummaryJson => {
+                println!("{}", serde_json::json!({ "count": reported.len() }))
+            }
+        }
+    } else {
+        let mut output_limit = OutputLimit::new(opts.limit, opts.limit_per_file);
+        let shown = if human {
+            Vec::new()
+        } else {
+            output_limit.apply(&findings)
+        };
+        match opts.format {
+            OutputFormat::Human => {
+                if let Some(trailer) = limit.trailer() {
+                    println!("{trailer}");
+                }
+            }
+            OutputFormat::Emacs => {
+                for finding in &shown {
+                    for line in emacs_lines(finding) {
+                        println!("{line}");
+                    }
+                }
+                if let Some(trailer) = output_limit.trailer() {
+                    println!("{trailer}");
+                }
+            }
+            OutputFormat::Github => {
+                let workspace = std::env::var_os("GITHUB_WORKSPACE").map(PathBuf::from);
+                for finding in &shown {
+                    for line in github_lines(finding, workspace.as_deref()) {
+                        println!("{line}");
+                    }
+                }
+                if let Some(trailer) = output_limit.trailer() {
+                    println!("::notice::{trailer}");
+                }
+            }
+            OutputFormat::Json => println!("{}", to_json(&shown, opts.json_pretty)?),
+            OutputFormat::SummaryJson => {
+                let mut stats = Stats::collect(&docs);
+                if let Some(step) = opts.round_report {
+                    stats.total = format_rounded(Duration::minutes(stats.total_minutes), step);
+                }
+                let summary = Summary {
+                    stats,
+                    findings: &shown,
+                };
+                println!("{}", to_json(&summary, opts.json_pretty)?);
+            }
+        }
+    }
+
+    // the explanation is plain text, it would break the machine readable formats
+    let plain_text = matches!(opts.format, OutputFormat::Human | OutputFormat::Emacs);
+    if opts.explain && plain_text && !opts.count_only && !reported.is_empty() {
+        println!();
+        print!("{}", explain(reported.iter().copied()));
+    }
+
+    if opts.fix_sort_clocks {
+        if human {
+            println!("sorting clocks...");
+        }
+        fix_files(&files, &parse_options, |doc, _| {
+            let changes = sort_clocks(doc);
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            if human {
+                println!("[{}] sorting {} clocks", doc.file_name(), changes.len());
+            }
+            doc.apply_changes(changes).map(Some)
+        })?;
+    }
+
+    if opts.fix_weekday {
+        if human {
+            println!("fixing weekdays...");
+        }
+        fix_files(&files, &parse_options, |doc, content| {
+            let fixed = fix_weekdays(doc, content, opts.locale);
+            if fixed == content {
+                return Ok(None);
+            }
+            if human {
+                println!("[{}] fixing weekdays", doc.file_name());
+            }
+            Ok(Some(fixed))
+        })?;
+    }
+
+    let failed = reported
+        .iter()
+        .any(|finding| finding.severity >= opts.fail_on_severity);
+    let exit_code = if failed { 1 } else { 0 };
+    if !opts.quiet {
+        eprintln!("{}", exit_summary(reported.len(), files.len(), exit_code));
+    }
+    std::process::exit(exit_code);
+}
+
+/// A finding for human output, colored by severity when printed to a terminal.
+fn human_line(finding: &Finding) -> String {
+    if !std::io::stdout().is_terminal() {
+        return finding.to_string();
+    }
+    let color = match finding.severity {
+        Severity::Error => "31",
+        Severity::Warning => "33",
+        Severity::Info => "36",
+    };
+    format!("\x1b[{color}m{finding}\x1b[0m")
+}
+
+/// A finding in the format of Emacs' compilation-mode, with absolute paths so
+/// that `next-error` finds the file. Findings with a related location, like
+/// clock conflicts, get a second line for it.
+fn emacs_lines(finding: &Finding) -> Vec<String> {
+    let line = |file: &Path, line: usize| {
+        let file = std::path::absolute(file).unwrap_or_else(|_| file.to_path_buf());
+        format!(
+            "{}:{line}:1: {} {}",
+            file.display(),
+            finding.kind,
+            finding.message
+        )
+    };
+    let mut lines = vec![line(&finding.file, finding.line)];
+    if let Some(related) = &finding.related {
+        lines.push(line(&related.file, related.line));
+    }
+    lines
+}
+
+/// A finding as GitHub Actions workflow command, which shows up as annotation
+/// of the file. Paths are made relative to `workspace`, the checkout, if they
+/// are in it. Findings with a related location get a second annotation.
+fn github_lines(finding: &Finding, workspace: Option<&Path>) -> Vec<String> {
+    let command = match finding.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "notice",
+    };
+    // data and property values are escaped as described in the workflow
+    // commands documentation
+    let escape_data = |s: &str| {
+        s.replace('%', "%25")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    };
+    let escape_property = |s: &str| escape_data(s).replace(':', "%3A").replace(',', "%2C");
+    let line = |file: &Path, line: usize| {
+        let file = workspace
+            .and_then(|workspace| file.strip_prefix(workspace).ok())
+            .unwrap_or(file);
+        format!(
+            "::{command} file={},line={line},title={}::{}",
+            escape_property(&file.to_string_lossy()),
+            finding.kind,
+            escape_data(&finding.message)
+        )
+    };
+    let mut lines = vec![line(&finding.file, finding.line)];
+    if let Some(related) = &finding.related {
+        lines.push(line(&related.file, related.line));
+    }
+    lines
+}
+
+/// Read and parse times per file for `--timing`. When disabled nothing is
+/// measured.
+struct Timings {
+    enabled: bool,
+    /// File, read and parse time in the order the files were read.
+    files: Vec<(PathBuf, std::time::Duration, std::time::Duration)>,
+}
+
+impl Timings {
+    /// Number of files in the slowest files list.
+    const SLOWEST: usize = 5;
+
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            files: Vec::new(),
+        }
+    }
+
+    fn read(&mut self, file: &Path) -> Result<OrgFile> {
+        if !self.enabled {
+            return OrgFile::from_file(file);
+        }
+        let start = Instant::now();
+        let org_file = OrgFile::from_file(file)?;
+        self.files
+            .push((file.to_path_buf(), start.elapsed(), Default::default()));
+        Ok(org_file)
+    }
+
+    /// Parses the `index`th file that was read.
+    fn parse<'a>(
+        &mut self,
+        index: usize,
+        org_file: &'a OrgFile,
+        parse_options: &ParseOptions,
+    ) -> OrgDocument<'a> {
+        if !self.enabled {
+            return org_file.document_with_options(parse_options);
+        }
+        let start = Instant::now();
+        let doc = org_file.document_with_options(parse_options);
+        if let Some((_, _, parse)) = self.files.get_mut(index) {
+            *parse = start.elapsed();
+        }
+        doc
+    }
+
+    /// One line per file, the total and the slowest files. `None` when
+    /// disabled.
+    fn summary(&self) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let mut summary = String::new();
+        for (file, read, parse) in &self.files {
+            summary.push_str(&format!(
+                "timing: {} read {read:.2?} parse {parse:.2?}\n",
+                file.display()
+            ));
+        }
+        let total = |f: &(PathBuf, std::time::Duration, std::time::Duration)| f.1 + f.2;
+        let sum = self.files.iter().map(total).sum::<std::time::Duration>();
+        summary.push_str(&format!(
+            "timing: {} files in {sum:.2?}, slowest:\n",
+            self.files.len()
+        ));
+        let mut slowest = self.files.iter().collect::<Vec<_>>();
+        slowest.sort_by_key(|f| std::cmp::Reverse(total(f)));
+        for file in slowest.into_iter().take(Self::SLOWEST) {
+            summary.push_str(&format!("  {:>10.2?}  {}\n", total(file), file.0.display()));
+        }
+        Some(summary)
+    }
+}
+
+/// Caps how many findings are printed with `--limit` and `--limit-per-file`.
+struct OutputLimit {
+    limit: Option<usize>,
+    limit_per_file: Option<usize>,
+    shown: usize,
+    shown_per_file: HashMap<PathBuf, usize>,
+    hidden: usize,
+}
+
+impl OutputLimit {
+    fn new(limit: Option<usize>, limit_per_file: Option<usize>) -> Self {
+        Self {
+            limit,
+            limit_per_file,
+            shown: 0,
+            shown_per_file: HashMap::new(),
+            hidden: 0,
+        }
+    }
+
+    /// Whether `finding` should be printed. Findings are counted as hidden
+    /// otherwise.
+    fn admit(&mut self, finding: &Finding) -> bool {
+        let shown_in_file = self.shown_per_file.entry(finding.file.clone()).or_default();
+        if self.limit.is_some_and(|limit| self.shown >= limit)
+            || self
+                .limit_per_file
+                .is_some_and(|limit| *shown_in_file >= limit)
+        {
+            self.hidden += 1;
+            return false;
+        }
+        *shown_in_file += 1;
+        self.shown += 1;
+        true
+    }
+
+    /// The findings that are admitted, in order.
+    fn apply(&mut self, findings: &[Finding]) -> Vec<Finding> {
+        findings
+            .iter()
+            .filter(|finding| self.admit(finding))
+            .cloned()
+            .collect()
+    }
+
+    fn trailer(&self) -> Option<String> {
+        (self.hidden > 0).then(|| format!("... and {} more", self.hidden))
+    }
+}
+
+/// The last line printed to stderr, meant to be grepped by scripts.
+fn exit_summary(findings: usize, files: usize, exit_code: i32) -> String {
+    let findings = if findings == 1 {
+        "1 finding".to_string()
+    } else {
+        format!("{findings} findings")
+    };
+    let files = if files == 1 {
+        "1 file".to_string()
+    } else {
+        format!("{files} files")
+    };
+    format!("org-linter: {findings} across {files} (exit {exit_code})")
+}
+
+fn watch_org_files(
+    opts: &CheckOrgOptions,
+    files: &[PathBuf],
+    parse_options: ParseOptions,
+) -> Result<()> {
+    let mut session = WatchSession::new(
+        files,
+        parse_options,
+        opts.lint_options(),
+        opts.report_clock_conflicts,
+    )?
+    .exclude_conflict_tags(opts.conflict_exclude_tags.clone());
+    let paths = if opts.org_files.is_some() {
+        files.to_vec()
+    } else {
+        // pick up files created while watching
+        session = session.discover_with(|| find_org_files(opts));
+        vec![opts.org_dir()?]
+    };
+    watch(
+        &paths,
+        opts.recursive,
+        session,
+        std::time::Duration::from_millis(300),
+        |session| {
+            // clear the terminal
+            print!("\x1b[2J\x1b[H");
+            for finding in session.findings() {
+                println!("{}", human_line(&finding));
+            }
+        },
+    )
+}
+
+fn find_org_files(opts: &CheckOrgOptions) -> Result<Vec<PathBuf>> {
+    let files = match &opts.org_files {
+        Some(files) => files.clone(),
+        None => find_org_files_in(opts, &opts.org_dir()?)?,
+    };
+    Ok(with_includes(opts, files))
+}
+
+/// `files` and, with --follow-includes, the files they include.
+fn with_includes(opts: &CheckOrgOptions, files: Vec<PathBuf>) -> Vec<PathBuf> {
+    if !opts.follow_includes {
+        return files;
+    }
+    let (files, warnings) = follow_includes(&files);
+    for warning in warnings {
+        eprintln!("WARNING: {warning}");
+    }
+    files
+}
+
+/// The org files in `org_dir` that are not excluded by its ignore file.
+fn find_org_files_in(opts: &CheckOrgOptions, org_dir: &Path) -> Result<Vec<PathBuf>> {
+    let ignore_file = org_dir.join(IgnoreFile::FILE_NAME);
+    let ignore = if ignore_file.exists() {
+        IgnoreFile::from_file(ignore_file)?
+    } else {
+        IgnoreFile::default()
+    };
+    let mut files = Vec::new();
+    collect_org_files(opts, org_dir, org_dir, &ignore, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Adds the org (and with --markdown markdown) files in `dir` to `files`.
+/// With --recursive, subdirectories except hidden ones are searched as well.
+fn collect_org_files(
+    opts: &CheckOrgOptions,
+    org_dir: &Path,
+    dir: &Path,
+    ignore: &IgnoreFile,
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+        let relative = path.strip_prefix(org_dir).unwrap_or(&path);
+        if ignore.is_ignored(relative) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if file_type.is_dir() && opts.recursive && !hidden {
+            collect_org_files(opts, org_dir, &path, ignore, files)?;
+        } else if file_type.is_file()
+            && (path.extension() == Some(OsStr::new("org"))
+                || (opts.markdown && is_markdown_file(&path)))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// All findings of `files`: the checks per document, those across documents
+/// and, if enabled, clock conflicts.
+fn collect_findings(
+    opts: &CheckOrgOptions,
+    org_dir: &Path,
+    files: &[PathBuf],
+    parse_options: &ParseOptions,
+) -> Result<Vec<Finding>> {
+    let org_files = files
+        .iter()
+        .map(OrgFile::from_file)
+        .collect::<Result<Vec<_>>>()?;
+    let docs = org_files
+        .iter()
+        .map(|ea| ea.document_with_options(parse_options))
+        .collect::<Vec<_>>();
+    let lint_options = opts.lint_options();
+    let mut findings = docs
+        .iter()
+        .flat_map(|doc| check_org(doc, opts, &lint_options))
+        .collect::<Vec<_>>();
+    findings.extend(lint_documents(&docs, &lint_options));
+    if opts.report_clock_conflicts {
+        let conflicts = ClockConflict::find_conflicts_excluding(
+            &docs,
+            &opts.conflict_exclude_tags,
+            opts.now_utc(),
+        );
+        let mut conflicts = conflicts.map(|c| c.finding()).collect::<Vec<_>>();
+        lint_options.classify(&mut conflicts);
+        findings.extend(conflicts);
+    }
+    Ok(findings
+        .into_iter()
+        .map(|finding| finding.with_fingerprint(org_dir))
+        .collect())
+}
+
+/// JSON output, indented with `pretty`. Fields are in declaration order and
+/// there are no maps, so the output is stable for golden files.
+fn to_json<T: serde::Serialize + ?Sized>(value: &T, pretty: bool) -> Result<String> {
+    Ok(if pretty {
+        serde_json::to_string_pretty(value)?
+    } else {
+        serde_json::to_string(value)?
+    })
+}
+
+/// Prints the findings introduced and fixed between two org directories.
+/// The files of each directory are found like those of --org-dir and checked
+/// with the config in that directory, unless there is --config. Fails like a
+/// normal run if an added finding reaches --fail-on-severity.
+fn run_diff(
+    diff_opts: &DiffOptions,
+    opts: &mut CheckOrgOptions,
+    parse_options: &ParseOptions,
+) -> Result<()> {
+    // both trees as of the same time
+    opts.now.get_or_insert_with(|| Local::now().naive_local());
+    let mut findings = Vec::new();
+    for dir in [&diff_opts.old_dir, &diff_opts.new_dir] {
+        opts.config = opts.config_in(dir)?;
+        let files = with_includes(opts, find_org_files_in(opts, dir)?);
+        findings.push(collect_findings(opts, dir, &files, parse_options)?);
+    }
+    let diff = diff_findings(&findings[0], &findings[1]);
+    match opts.format {
+        OutputFormat::Json | OutputFormat::SummaryJson => {
+            println!("{}", to_json(&diff, opts.json_pretty)?)
+        }
+        OutputFormat::Human | OutputFormat::Emacs | OutputFormat::Github => {
+            for finding in &diff.added {
+                println!("added:   {finding}");
+            }
+            for finding in &diff.removed {
+                println!("removed: {finding}");
+            }
+        }
+    }
+    if diff
+        .added
+        .iter()
+        .any(|finding| finding.suppressed.is_none() && finding.severity >= opts.fail_on_severity)
+    {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints the union of the findings in `files` as JSON, see [`merge_findings`].
+fn merge_finding_files(files: &[PathBuf], pretty: bool) -> Result<()> {
+    let runs = files
+        .iter()
+        .map(|file| {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("cannot read findings file {file:?}"))?;
+            let json: serde_json::Value = serde_json::from_str(&content)
+                .with_context(|| format!("invalid findings file {file:?}"))?;
+            // summary-json has the findings in a field
+            let findings = match json.get("findings") {
+                Some(findings) => findings.clone(),
+                None => json,
+            };
+            serde_json::from_value::<Vec<Finding>>(findings)
+                .with_context(|| format!("invalid findings file {file:?}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    println!("{}", to_json(&merge_findings(runs), pretty)?);
+    Ok(())
+}
+
+fn run_report(
+    report_opts: &ReportOptions,
+    opts: &CheckOrgOptions,
+    files: &[PathBuf],
+    parse_options: &ParseOptions,
+) -> Result<()> {
+    if !report_opts.by_project
+        && !report_opts.estimation
+        && !report_opts.coverage
+        && !report_opts.invoice
+        && !report_opts.weekly_email
+        && !report_opts.monthly_table
+        && !report_opts.tags
+    {
+        return Err(anyhow::anyhow!(
+            "no report selected, use --by-project, --estimation, --coverage, --invoice, --weekly-email, --monthly-table or --tags"
+        ));
+    }
+    let org_files = files
+        .iter()
+        .map(OrgFile::from_file)
+        .collect::<Result<Vec<_>>>()?;
+    let docs = org_files
+        .iter()
+        .map(|ea| ea.document_with_options(parse_options))
+        .collect::<Vec<_>>();
+    let step = opts.round_report.unwrap_or(1);
+    if report_opts.weekly_email {
+        let today = opts.now().date_naive();
+        let from = report_opts.from.unwrap_or_else(|| {
+            today - Duration::days(today.weekday().num_days_from_monday() as i64)
+        });
+        let to = report_opts.to.unwrap_or(from + Duration::days(6));
+        let summary = WeeklySummary::collect(
+            &docs,
+            from,
+            to,
+            report_opts.long_session,
+            &opts.conflict_exclude_tags,
+            opts.now_utc(),
+        );
+        print!("{}", summary.markdown(step));
+        return Ok(());
+    }
+    if report_opts.tags {
+        let header = ["TIME", "HEADLINES", "TAG"].map(String::from);
+        let rows = tag_usage(&docs)
+            .iter()
+            .map(|usage| {
+                [
+                    format_rounded(usage.total, step),
+                    usage.headlines.to_string(),
+                    usage.tag.to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let widths = [0, 1].map(|i| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[i].len())
+                .max()
+                .unwrap_or(0)
+        });
+        for [time, headlines, tag] in std::iter::once(&header).chain(&rows) {
+            println!("{time:>0$}  {headlines:>1$}  {tag}", widths[0], widths[1]);
+        }
+        return Ok(());
+    }
+    if report_opts.monthly_table {
+        print!("{}", monthly_org_table(&time_by_month(&docs), step));
+        return Ok(());
+    }
+    if report_opts.invoice {
+        let rows = invoice_rows(
+            &docs,
+            report_opts.tag.as_deref(),
+            report_opts.from,
+            report_opts.to,
+        );
+        println!("date,task,hours");
+        for row in rows {
+            let hours = match report_opts.hours {
+                HoursFormat::Decimal => format_decimal_hours(row.duration, step),
+                HoursFormat::Clock => format_rounded(row.duration, step),
+            };
+            println!("{},{},{hours}", row.date, csv_field(&row.task));
+        }
+        return Ok(());
+    }
+    if report_opts.coverage {
+        let clocks = docs.iter().flat_map(|doc| &doc.clocks);
+        let first = clocks.clone().map(|clock| clock.start.date()).min();
+        let last = clocks
+            .filter_map(|clock| clock.end)
+            .map(|end| end.date())
+            .max();
+        let (Some(from), Some(to)) = (report_opts.from.or(first), report_opts.to.or(last)) else {
+            return Ok(());
+        };
+        let hours = WorkingHours {
+            start: report_opts.work_start,
+            end: report_opts.work_end,
+            days: report_opts.work_days.clone(),
+        };
+        let uncovered = uncovered_work_time(&docs, &hours, from, to);
+        let total = uncovered
+            .iter()
+            .fold(Duration::zero(), |total, (start, end)| {
+                total + (*end - *start)
+            });
+        let rows = uncovered
+            .iter()
+            .map(|(start, end)| {
+                let interval = format!(
+                    "{}-{}",
+                    start.format("%Y-%m-%d %a %H:%M"),
+                    end.format("%H:%M")
+                );
+                (format_rounded(*end - *start, step), interval)
+            })
+            .chain(std::iter::once((
+                format_rounded(total, step),
+                "TOTAL".to_string(),
+            )))
+            .collect::<Vec<_>>();
+        let width = rows.iter().map(|(d, _)| d.len()).max().unwrap_or(0);
+        for (d, interval) in rows {
+            println!("{d:>width$}  {interval}");
+        }
+        return Ok(());
+    }
+    if report_opts.estimation {
+        let header = ["EFFORT", "ACTUAL", "VARIANCE", "TITLE"].map(String::from);
+        let rows = effort_variances(&docs)
+            .iter()
+            .map(|v| {
+                let variance = format_rounded(v.variance(), step);
+                [
+                    format_rounded(v.effort, step),
+                    format_rounded(v.actual, step),
+                    if variance.starts_with('-') {
+                        variance
+                    } else {
+                        format!("+{variance}")
+                    },
+                    v.title.to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let widths = [0, 1, 2].map(|i| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[i].len())
+                .max()
+                .unwrap_or(0)
+        });
+        for [effort, actual, variance, title] in std::iter::once(&header).chain(&rows) {
+            println!(
+                "{effort:>0$}  {actual:>1$}  {variance:>2$}  {title}",
+                widths[0], widths[1], widths[2]
+            );
+        }
+        return Ok(());
+    }
+    let projects = time_by_project(&docs);
+    let total = projects
+        .iter()
+        .fold(Duration::zero(), |total, (_, d)| total + *d);
+    let rows = projects
+        .iter()
+        .map(|(title, d)| (format_rounded(*d, step), *title))
+        .chain(std::iter::once((format_rounded(total, step), "TOTAL")))
+        .collect::<Vec<_>>();
+    let width = rows.iter().map(|(d, _)| d.len()).max().unwrap_or(0);
+    for (d, title) in rows {
+        println!("{d:>width$}  {title}");
+    }
+    Ok(())
+}
+
+/// Quotes `field` for CSV if it contains a comma, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Prints the running clocks of `files` and exits with 1 if there are any.
+fn assert_no_running(files: &[PathBuf], parse_options: &ParseOptions) -> Result<()> {
+    let mut running = 0;
+    for file in files {
+        let org_file = OrgFile::from_file(file)?;
+        let doc = org_file.document_with_options(parse_options);
+        for (headline, clock) in doc.clocks_with_headlines() {
+            if clock.is_running() {
+                running += 1;
+                println!(
+                    "[{}:{}] {clock}  {}",
+                    doc.file_name(),
+                    clock.line,
+                    headline.title
+                );
+            }
+        }
+    }
+    std::process::exit(if running > 0 { 1 } else { 0 });
+}
+
+fn run_query(query: &Query, files: &[PathBuf], parse_options: &ParseOptions) -> Result<()> {
+    let org_files = files
+        .iter()
+        .map(OrgFile::from_file)
+        .collect::<Result<Vec<_>>>()?;
+    for org_file in &org_files {
+        let doc = org_file.document_with_options(parse_options);
+        for (headline, clock) in doc.clocks_with_headlines() {
+            if query.matches(&doc, clock) {
+                println!(
+                    "[{}:{}] {clock}  {}",
+                    doc.file_name(),
+                    clock.line,
+                    headline.title
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_fix(
+    fix_opts: &FixOptions,
+    opts: &CheckOrgOptions,
+    files: &[PathBuf],
+    parse_options: &ParseOptions,
+) -> Result<()> {
+    let total_property = &opts.total_property;
+    let exclude_tags = &opts.conflict_exclude_tags;
+    if fix_opts.all {
+        return fix_all(files, parse_options, fix_opts.dry_run);
+    }
+
+    if let Some([location, time]) = fix_opts.split.as_deref() {
+        let (file, line) = location
+            .rsplit_once(':')
+            .and_then(|(file, line)| Some((file, line.parse::<usize>().ok()?)))
+            .ok_or_else(|| anyhow::anyhow!("cannot parse {location:?}, expected FILE:LINE"))?;
+        let time = parse_time_from_cli(time).map_err(anyhow::Error::msg)?;
+        fix_files(&[PathBuf::from(file)], parse_options, |doc, _| {
+            let changes = split_clock(doc, line, time)?;
+            println!("[{}] splitting clock on line {line}", doc.file_name());
+            doc.apply_changes(changes).map(Some)
+        })?;
+    }
+
+    if fix_opts.conflicts {
+        if fix_opts.auto {
+            let mut org_files = files
+                .iter()
+                .map(OrgFile::from_file)
+                .collect::<Result<Vec<_>>>()?;
+            let original = org_files
+                .iter()
+                .map(|f| f.content().to_string())
+                .collect::<Vec<_>>();
+            let resolved = resolve_conflicts_automatically(
+                &mut org_files,
+                fix_opts.max_rounds,
+                exclude_tags,
+                opts.now_utc(),
+            )?;
+            for (org_file, original) in org_files.iter_mut().zip(original) {
+                if org_file.content() != original {
+                    org_file.save()?;
+                }
+            }
+            println!("resolved {resolved} clock conflicts");
+        } else {
+            fix_conflicts_interactively(files, parse_options, exclude_tags, opts.now_utc())?;
+        }
+    }
+
+    if fix_opts.pad_minutes {
+        fix_files(files, parse_options, |doc, _| {
+            let changes = pad_minutes(doc);
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            println!("[{}] padding {} clocks", doc.file_name(), changes.len());
+            doc.apply_changes(changes).map(Some)
+        })?;
+    }
+
+    if fix_opts.normalize_timestamp_types {
+        fix_files(files, parse_options, |doc, _| {
+            let changes = normalize_timestamp_types(doc);
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            println!(
+                "[{}] normalizing timestamps of {} clocks",
+                doc.file_name(),
+                changes.len()
+            );
+            doc.apply_changes(changes).map(Some)
+        })?;
+    }
+
+    if fix_opts.normalize {
+        fix_files(files, parse_options, |doc, _| {
+            let changes = normalize_clocks(doc, opts.locale);
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            println!("[{}] normalizing {} clocks", doc.file_name(), changes.len());
+            doc.apply_changes(changes).map(Some)
+        })?;
+    }
+
+    if fix_opts.remove_orphan_totals {
+        fix_files(files, parse_options, |doc, content| {
+            let fixed = remove_orphan_totals(doc, content, total_property);
+            if fixed == content {
+                return Ok(None);
+            }
+            println!(
+                "[{}] removing orphan {total_property} properties",
+                doc.file_name()
+            );
+            Ok(Some(fixed))
+        })?;
+    }
+
+    if fix_opts.close_running_under_done {
+        fix_files(files, parse_options, |doc, _| {
+            let changes = close_running_under_done(doc, &opts.done_keywords);
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            println!("[{}] closing {} clocks", doc.file_name(), changes.len());
+            doc.apply_changes(changes).map(Some)
+        })?;
+    }
+
+    if fix_opts.dedupe_tags {
+        fix_files(files, parse_options, |doc, content| {
+            let fixed = dedupe_tags(doc, content);
+            if fixed == content {
+                return Ok(None);
+            }
+            println!("[{}] removing duplicate tags", doc.file_name());
+            Ok(Some(fixed))
+        })?;
+    }
+
+    if fix_opts.clock_indentation {
+        fix_files(files, parse_options, |doc, content| {
+            let fixed = normalize_clock_indentation(doc, content);
+            if fixed == content {
+                return Ok(None);
+            }
+            println!("[{}] re-indenting clocks", doc.file_name());
+            Ok(Some(fixed))
+        })?;
+    }
+
+    Ok(())
+}
+
+fn fix_all(files: &[PathBuf], parse_options: &ParseOptions, dry_run: bool) -> Result<()> {
+    fix_files(files, parse_options, |doc, content| {
+        let preview = preview_fixes(doc);
+        for overlap in &preview.overlaps {
+            eprintln!("[{}] WARNING: {overlap}", doc.file_name());
+        }
+        for finding in &preview.rejected {
+            eprintln!("{finding}");
+        }
+        if preview.changes.is_empty() {
+            return Ok(None);
+        }
+        let counts = preview
+            .counts
+            .iter()
+            .map(|(name, n)| format!("{name}: {n}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("[{}] {counts}", doc.file_name());
+        let fixed = FileChange::apply_to_string(preview.changes, content)?;
+        if dry_run {
+            print!("{}", fix_diff(&doc.file, content, &fixed));
+            return Ok(None);
+        }
+        Ok(Some(fixed.into_owned()))
+    })
+}
+
+/// Re-reads each of `files` and saves the content `fix` returns for it, if
+/// any and different, with the hash-checked [`OrgFile::save`]: the file is
+/// not overwritten if it was edited since it was read, e.g. in Emacs. `fix`
+/// gets the parsed document and its content.
+fn fix_files(
+    files: &[PathBuf],
+    parse_options: &ParseOptions,
+    mut fix: impl FnMut(&OrgDocument, &str) -> Result<Option<String>>,
+) -> Result<()> {
+    for file in files {
+        let mut org_file = OrgFile::from_file(file)?;
+        let fixed = {
+            let doc = org_file.document_with_options(parse_options);
+            fix(&doc, org_file.content())?
+        };
+        if let Some(fixed) = fixed.filter(|fixed| fixed != org_file.content()) {
+            org_file.save_content(fixed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders the change from `old` to `new`, the content of `file` before and
+/// after fixing, as a diff: each run of removed (`-`) and added (`+`) lines
+/// under the line of `old` where it starts.
+fn fix_diff(file: &std::path::Path, old: &str, new: &str) -> String {
+    // fixes change few lines far apart, so after a difference the next line
+    // both have in common is found within a few lines
+    const MAX_RUN: usize = 100;
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let mut diff = format!("--- {0}\n+++ {0}\n", file.display());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (removed, added) = (0..=MAX_RUN)
+            .flat_map(|run| (0..=run).map(move |removed| (removed, run - removed)))
+            .find(|&(removed, added)| {
+                i + removed < old.len()
+                    && j + added < new.len()
+                    && old[i + removed] == new[j + added]
+            })
+            .unwrap_or((old.len() - i, new.len() - j));
+        diff.push_str(&format!("@@ line {} @@\n", i + 1));
+        for line in &old[i..i + removed] {
+            diff.push_str(&format!("-{line}\n"));
+        }
+        for line in &new[j..j + added] {
+            diff.push_str(&format!("+{line}\n"));
+        }
+        i += removed;
+        j += added;
+    }
+    diff
+}
+
+fn fix_conflicts_interactively(
+    files: &[PathBuf],
+    parse_options: &ParseOptions,
+    exclude_tags: &[String],
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let mut org_files = files
+        .iter()
+        .map(OrgFile::from_file)
+        .collect::<Result<Vec<_>>>()?;
+    let report = resolve_all(
+        &mut org_files,
+        parse_options,
+        exclude_tags,
+        now,
+        |conflict, resolutions| {
+            println!("{}", conflict.report());
+            println!("Select resolution:");
+            for (i, resolution) in resolutions.iter().enumerate() {
+                println!("  {i}) {}", resolution.explanation());
+            }
+            let mut stdin = std::io::stdin().lock();
+            loop {
+                let mut input = String::new();
+                stdin.read_line(&mut input).expect("readline");
+                match input.trim().parse::<usize>() {
+                    Ok(i) if i < resolutions.len() => break resolutions[i],
+                    _ => println!("invalid input"),
+                };
+            }
+        },
+    )?;
+    for notice in &report.notices {
+        eprintln!("{notice}");
+    }
+    Ok(())
+}
+
+fn check_org(
+    doc: &OrgDocument,
+    opts: &CheckOrgOptions,
+    lint_options: &LintOptions,
+) -> Vec<Finding> {
+    let mut findings = lint_document(doc, lint_options);
+    for command in &opts.custom_checks {
+        match run_custom_check(command, doc) {
+            Ok(mut custom) => {
+                lint_options.classify(&mut custom);
+                findings.extend(custom)
+            }
+            Err(err) => eprintln!("[{}] ERROR: {err:#}", doc.file_name()),
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use clap::Parser;
+
+    use super::{
+        default_org_dir, emacs_lines, fix_diff, github_lines, to_json, CheckOrgOptions, OutputLimit,
+    };
+    use org_linter::{Finding, FindingKind};
+
+    #[test]
+    fn cross_file_passes_need_all_documents() {
+        let opts = |args: &[&str]| {
+            CheckOrgOptions::parse_from(std::iter::once("org-linter").chain(args.iter().copied()))
+        };
+        assert!(!opts(&[]).needs_all_documents());
+        assert!(!opts(&["--format", "json"]).needs_all_documents());
+        assert!(opts(&["--clock-conflicts"]).needs_all_documents());
+        assert!(opts(&["--report-cross-file-duplicate"]).needs_all_documents());
+        assert!(opts(&["--format", "summary-json"]).needs_all_documents());
+    }
+
+    #[test]
+    fn default_org_dir_without_home() {
+        assert_eq!(
+            default_org_dir(Some(PathBuf::from("/home/me"))).unwrap(),
+            PathBuf::from("/home/me/org")
+        );
+        assert!(default_org_dir(None).is_err());
+    }
+
+    #[test]
+    fn output_limit() {
+        let findings = ["a.org", "a.org", "a.org", "b.org", "b.org"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, file)| Finding::new(FindingKind::ZeroDuration, file, i + 1, "zero"))
+            .collect::<Vec<_>>();
+
+        let mut limit = OutputLimit::new(Some(2), None);
+        let shown = limit.apply(&findings);
+        assert_eq!(shown, findings[..2]);
+        assert_eq!(limit.trailer().as_deref(), Some("... and 3 more"));
+
+        let mut limit = OutputLimit::new(None, Some(1));
+        let shown = limit.apply(&findings);
+        assert_eq!(shown, vec![findings[0].clone(), findings[3].clone()]);
+        assert_eq!(limit.trailer().as_deref(), Some("... and 3 more"));
+
+        let mut limit = OutputLimit::new(Some(10), None);
+        assert_eq!(limit.apply(&findings).len(), 5);
+        assert_eq!(limit.trailer(), None);
+    }
+
+    #[test]
+    fn fix_diff_shows_written_lines() {
+        let old = "* task
+  :LOGBOOK:
+  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+  CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:00] =>  0:00
+  :END:
+";
+        let new = "* task
+  :LOGBOOK:
+  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+  :END:
+";
+        assert_eq!(
+            fix_diff(Path::new("a.org"), old, new),
+            "--- a.org
++++ a.org
+@@ line 3 @@
+-  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+-  CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:00] =>  0:00
++  CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+"
+        );
+        assert_eq!(
+            fix_diff(Path::new("a.org"), old, old),
+            "--- a.org\n+++ a.org\n"
+        );
+        assert!(fix_diff(Path::new("a.org"), "", "* new\n").ends_with("@@ line 1 @@\n+* new\n"));
+    }
+
+    #[test]
+    fn github_format() {
+        let finding = Finding::new(
+            FindingKind::ClockConflict,
+            "/repo/org/a.org",
+            3,
+            "CONFLICT: 50% overlap",
+        )
+        .with_related("/repo/org/b,c.org", 7);
+        assert_eq!(
+            github_lines(&finding, Some(Path::new("/repo"))),
+            vec![
+                "::error file=org/a.org,line=3,title=clock-conflict::CONFLICT: 50%25 overlap",
+                "::error file=org/b%2Cc.org,line=7,title=clock-conflict::CONFLICT: 50%25 overlap",
+            ]
+        );
+
+        let finding = Finding::new(FindingKind::ZeroDuration, "a.org", 3, "ZERO DURATION");
+        assert_eq!(
+            github_lines(&finding, Some(Path::new("/repo"))),
+            vec!["::warning file=a.org,line=3,title=zero-duration::ZERO DURATION"]
+        );
+    }
+
+    #[test]
+    fn pretty_json() {
+        let findings = [
+            Finding::new(FindingKind::ZeroDuration, "a.org", 3, "ZERO DURATION"),
+            Finding::new(FindingKind::ClockConflict, "a.org", 5, "CONFLICT")
+                .with_related("b.org", 7),
+        ];
+        let expected = r#"[
+  {
+    "kind": "zero-duration",
+    "severity": "warning",
+    "file": "a.org",
+    "line": 3,
+    "message": "ZERO DURATION"
+  },
+  {
+    "kind": "clock-conflict",
+    "severity": "error",
+    "file": "a.org",
+    "line": 5,
+    "message": "CONFLICT",
+    "related": {
+      "file": "b.org",
+      "line": 7
+    }
+  }
+]"#;
+        assert_eq!(to_json(&findings, true).unwrap(), expected);
+        assert_eq!(
+            to_json(&findings[..1], false).unwrap(),
+            r#"[{"kind":"zero-duration","severity":"warning","file":"a.org","line":3,"message":"ZERO DURATION"}]"#
+        );
+    }
+    #[test]
+    fn emacs_format() {
+        let finding = Finding::new(FindingKind::ZeroDuration, "/org/a.org", 3, "ZERO DURATION")
+            .with_related("/org/b.org", 7);
+        assert_eq!(
+            emacs_lines(&finding),
+            vec![
+                "/org/a.org:3:1: zero-duration ZERO DURATION",
+                "/org/b.org:7:1: zero-duration ZERO DURATION",
+            ]
+        );
+
+        let finding = Finding::new(FindingKind::ZeroDuration, "a.org", 3, "ZERO DURATION");
+        let lines = emacs_lines(&finding);
+        assert_eq!(lines.len(), 1);
+        let (file, rest) = lines[0].split_once(':').unwrap();
+        assert!(std::path::Path::new(file).is_absolute());
+        assert!(file.ends_with("a.org"));
+        assert_eq!(rest, "3:1: zero-duration ZERO DURATION");
     }
 }
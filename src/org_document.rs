@@ -1,28 +1,263 @@
+use chrono::{Duration, NaiveDateTime};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-use crate::block::Block;
-use crate::clock::Clock;
-use crate::headline::Headline;
+use crate::block::{maybe_block, Block};
+use crate::clock::{maybe_clock, Clock, WeekdayLocale};
+use crate::clock_conflict::FileChange;
+use crate::headline::{maybe_headline, parse_property, Headline};
+use crate::org_file::content_hash;
+use crate::planning::{maybe_planning, Planning};
+use crate::state_change::{maybe_state_change, StateChange};
+
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Warn when a clock does not directly follow the previous clock of the
+    /// same headline.
+    pub warn_non_consecutive_clocks: bool,
+    /// Only consider clocks non-consecutive if a drawer or block boundary is
+    /// between them, i.e. allow blank lines and notes in between.
+    pub allow_notes_between_clocks: bool,
+    /// Collect TODO state change notes like `- State "DONE" from "TODO" [...]`
+    /// into [`OrgDocument::state_changes`].
+    pub parse_state_changes: bool,
+    /// The character marking headlines, repeated once per level. Org uses
+    /// `*`, files imported from other tools may use e.g. `#`.
+    pub heading_char: char,
+    /// Replaces the headline syntax, see [`crate::headline_pattern`]. Takes
+    /// precedence over `heading_char`.
+    pub headline_pattern: Option<Regex>,
+    /// Lines longer than this many bytes are skipped with a
+    /// [`ParseWarning::LongLine`] instead of being matched, so that e.g. a
+    /// minified export pasted into a file can't stall the parser.
+    pub max_line_length: usize,
+    /// Language of the day names when clocks are written back, e.g. by the
+    /// fixers. By default each clock keeps the language it is written in.
+    pub locale: Option<WeekdayLocale>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            warn_non_consecutive_clocks: true,
+            allow_notes_between_clocks: true,
+            parse_state_changes: false,
+            heading_char: '*',
+            headline_pattern: None,
+            max_line_length: 64 * 1024,
+            locale: None,
+        }
+    }
+}
+
+/// Suspicious structure noticed while parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    NonConsecutiveClock {
+        line: usize,
+        previous_line: usize,
+    },
+    OrphanClock {
+        line: usize,
+    },
+    /// A `#+BEGIN_` block without `#+END_`. Everything after it is ignored.
+    UnterminatedBlock {
+        line: usize,
+    },
+    /// A line starting with `CLOCK:` that can't be parsed.
+    MalformedClock {
+        line: usize,
+    },
+    /// A line longer than [`ParseOptions::max_line_length`], skipped.
+    LongLine {
+        line: usize,
+        length: usize,
+    },
+}
+
+impl ParseWarning {
+    pub fn line(&self) -> usize {
+        match self {
+            ParseWarning::NonConsecutiveClock { line, .. }
+            | ParseWarning::OrphanClock { line }
+            | ParseWarning::UnterminatedBlock { line }
+            | ParseWarning::MalformedClock { line }
+            | ParseWarning::LongLine { line, .. } => *line,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseWarning::NonConsecutiveClock {
+                line,
+                previous_line,
+            } => write!(
+                f,
+                "found clock on line {line}. Previous clock was on line {previous_line}"
+            ),
+            ParseWarning::OrphanClock { line } => {
+                write!(f, "found clock on line {line} but have no headline")
+            }
+            ParseWarning::UnterminatedBlock { line } => {
+                write!(f, "block starting on line {line} is never closed")
+            }
+            ParseWarning::MalformedClock { line } => {
+                write!(f, "cannot parse clock on line {line}")
+            }
+            ParseWarning::LongLine { line, length } => {
+                write!(f, "skipped line {line} with {length} bytes")
+            }
+        }
+    }
+}
+
+/// A problem with the structure of a document, as opposed to the lints about
+/// the clocked times. See [`OrgDocument::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructuralError {
+    UnterminatedBlock {
+        line: usize,
+    },
+    OrphanClock {
+        line: usize,
+    },
+    MalformedClock {
+        line: usize,
+    },
+    LevelSkip {
+        line: usize,
+        level: usize,
+        parent_line: usize,
+        parent_level: usize,
+    },
+}
+
+impl StructuralError {
+    pub fn line(&self) -> usize {
+        match self {
+            StructuralError::UnterminatedBlock { line }
+            | StructuralError::OrphanClock { line }
+            | StructuralError::MalformedClock { line }
+            | StructuralError::LevelSkip { line, .. } => *line,
+        }
+    }
+}
+
+impl std::fmt::Display for StructuralError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StructuralError::UnterminatedBlock { line } => {
+                write!(f, "block starting on line {line} is never closed")
+            }
+            StructuralError::OrphanClock { line } => {
+                write!(f, "clock on line {line} has no headline")
+            }
+            StructuralError::MalformedClock { line } => {
+                write!(f, "cannot parse clock on line {line}")
+            }
+            StructuralError::LevelSkip {
+                line,
+                level,
+                parent_line,
+                parent_level,
+            } => write!(
+                f,
+                "headline on line {line} is on level {level} below level {parent_level} on line {parent_line}"
+            ),
+        }
+    }
+}
+
+/// Indentation of a plain list item like `- foo`, `+ foo` or `1. foo`. `*`
+/// bullets are left out, they are ambiguous with headlines.
+fn list_item_indent(line: &str) -> Option<usize> {
+    let item = line.trim_start();
+    let indent = line.len() - item.len();
+    let number = item.trim_start_matches(|c: char| c.is_ascii_digit());
+    let bullet = item.strip_prefix(['-', '+']).or_else(|| {
+        (number.len() < item.len())
+            .then(|| number.strip_prefix(['.', ')']))
+            .flatten()
+    })?;
+    (bullet.is_empty() || bullet.starts_with(char::is_whitespace)).then_some(indent)
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+static FILETAGS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^#\+FILETAGS:(.*)$").expect("filetags re"));
+
+static DRAWER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*:[a-zA-Z0-9_-]+:\s*$").expect("drawer re"));
 
 #[derive(Debug)]
 pub struct OrgDocument<'a> {
     pub file: PathBuf,
     pub headlines: Vec<Headline<'a>>,
     pub clocks: Vec<Clock<'a>>,
+    /// Only filled when [`ParseOptions::parse_state_changes`] is set.
+    pub state_changes: Vec<StateChange<'a>>,
+    /// Tags of `#+FILETAGS:` lines before the first headline. They are
+    /// inherited by all headlines.
+    pub file_tags: Vec<&'a str>,
+    /// Entries of a `:PROPERTIES:` drawer before the first headline.
+    pub file_properties: Vec<(&'a str, &'a str)>,
+    pub warnings: Vec<ParseWarning>,
+    /// The text whose line numbers `headlines` and `clocks` refer to, used by
+    /// [`OrgDocument::apply_changes`].
+    pub source: Option<&'a str>,
+    /// Local modification time of the file, set by [`crate::OrgFile`] for
+    /// files read from disk.
+    pub modified: Option<NaiveDateTime>,
 }
 
 impl<'a> OrgDocument<'a> {
     pub fn parse(file: impl Into<PathBuf>, content: &'a str) -> Self {
-        let mut headlines = Vec::new();
+        Self::parse_with_options(file, content, &ParseOptions::default())
+    }
+
+    pub fn parse_with_options(
+        file: impl Into<PathBuf>,
+        content: &'a str,
+        opts: &ParseOptions,
+    ) -> Self {
+        let mut headlines: Vec<Headline> = Vec::new();
         let mut clocks: Vec<Clock> = Vec::new();
+        let mut state_changes: Vec<StateChange> = Vec::new();
+        let mut file_tags: Vec<&str> = Vec::new();
+        let mut file_properties: Vec<(&str, &str)> = Vec::new();
         let mut blocks: Vec<Block> = Vec::new();
         let mut parents: Vec<(usize, usize)> = Vec::new();
         let mut current_block = Option::<Block>::None;
+        let mut in_properties = false;
+        let mut drawer = Option::<usize>::None;
+        let mut warnings = Vec::new();
+        // last line that was a block or drawer boundary
+        let mut structure_line = 0;
+        // indentation of the plain list items the current line is nested in
+        let mut list_items: Vec<usize> = Vec::new();
 
         for (i, line) in content.lines().enumerate() {
             let line_no = i + 1;
+            if line.len() > opts.max_line_length {
+                let warning = ParseWarning::LongLine {
+                    line: line_no,
+                    length: line.len(),
+                };
+                warn!("WARNING: {warning}");
+                warnings.push(warning);
+                continue;
+            }
+
             if let Some(mut block) = current_block.take() {
                 if block.parse_end(line, line_no) {
+                    structure_line = line_no;
                     blocks.push(block);
                 } else {
                     current_block = Some(block);
@@ -30,13 +265,96 @@ impl<'a> OrgDocument<'a> {
                 continue;
             };
 
+            if in_properties {
+                if line.trim().eq_ignore_ascii_case(":END:") {
+                    structure_line = line_no;
+                    in_properties = false;
+                } else if let Some(property) = parse_property(line) {
+                    match headlines.last_mut() {
+                        Some(headline) => headline.properties.push(property),
+                        None => file_properties.push(property),
+                    }
+                }
+                continue;
+            }
+
+            if headlines.is_empty() && line.starts_with("#+") {
+                if let Some(captures) = FILETAGS_RE.captures(line) {
+                    let tags = captures.get(1).unwrap().as_str();
+                    file_tags.extend(
+                        tags.split(|c: char| c == ':' || c.is_whitespace())
+                            .filter(|tag| !tag.is_empty()),
+                    );
+                    continue;
+                }
+            }
+
+            if line.trim().eq_ignore_ascii_case(":PROPERTIES:") {
+                structure_line = line_no;
+                in_properties = true;
+                continue;
+            }
+
+            if line.trim_start().starts_with(':') && DRAWER_RE.is_match(line) {
+                structure_line = line_no;
+                drawer = if line.trim().eq_ignore_ascii_case(":END:") {
+                    None
+                } else {
+                    Some(line_no)
+                };
+                continue;
+            }
+
+            if !line.trim().is_empty() {
+                let indent = indentation(line);
+                while list_items.last().is_some_and(|&item| item >= indent) {
+                    list_items.pop();
+                }
+                if let Some(indent) = list_item_indent(line) {
+                    list_items.push(indent);
+                }
+            }
+
+            // The pre-checks avoid running the regexes (and allocating an
+            // error) for the vast majority of lines that are plain text.
+            if let Some(headline) = headlines.last_mut() {
+                if headline.line + 1 == line_no && maybe_planning(line) {
+                    if let Ok(planning) = Planning::try_from(line) {
+                        headline.planning = planning;
+                        continue;
+                    }
+                }
+            }
+
+            if opts.parse_state_changes && maybe_state_change(line) {
+                if let (Ok(mut change), Some(&(index, _))) =
+                    (StateChange::try_from(line), parents.last())
+                {
+                    change.line = line_no;
+                    change.parent = index;
+                    state_changes.push(change);
+                    continue;
+                }
+            }
+
+            let maybe_headline =
+                opts.headline_pattern.is_some() || maybe_headline(line, opts.heading_char);
+            if !maybe_block(line) && !maybe_headline && !maybe_clock(line) {
+                continue;
+            }
+
             if let Ok(mut block) = Block::try_from(line) {
+                structure_line = line_no;
                 block.start_line = line_no;
                 current_block = Some(block);
                 continue;
             }
 
-            if let Ok(mut headline) = Headline::try_from(line) {
+            let headline = match &opts.headline_pattern {
+                Some(pattern) => Headline::parse_with_pattern(line, pattern),
+                None => Headline::parse_with_marker(line, opts.heading_char),
+            };
+            if let Ok(mut headline) = headline {
                 headline.line = line_no;
                 while !parents.is_empty() {
                     let (_, level) = parents.last().unwrap();
@@ -51,37 +369,637 @@ impl<'a> OrgDocument<'a> {
                 }
                 parents.push((headlines.len(), headline.level));
                 headlines.push(headline);
+                drawer = None;
+                list_items.clear();
                 continue;
             }
 
             if let Ok(mut clock) = Clock::try_from(line) {
                 clock.line = line_no;
+                clock.locale = opts.locale.unwrap_or(clock.locale);
+                clock.drawer = drawer;
+                clock.list_depth = list_items.len();
                 if let Some(&(index, _)) = parents.last() {
                     clock.parent = index;
                     if let Some(last_clock) = clocks.last() {
-                        if last_clock.parent == index && last_clock.line != line_no - 1 {
-                            warn!(
-                                "WARNING: found clock on line {i}. Previous clock was on line {}",
-                                last_clock.line
-                            );
+                        let non_consecutive = if opts.allow_notes_between_clocks {
+                            structure_line > last_clock.line
+                        } else {
+                            last_clock.line != line_no - 1
+                        };
+                        if opts.warn_non_consecutive_clocks
+                            && last_clock.parent == index
+                            && non_consecutive
+                        {
+                            let warning = ParseWarning::NonConsecutiveClock {
+                                line: line_no,
+                                previous_line: last_clock.line,
+                            };
+                            warn!("WARNING: {warning}");
+                            warnings.push(warning);
                         }
                     }
                     clocks.push(clock);
                 } else {
-                    warn!("WARNING: found clock on line {i} but have no headline");
+                    let warning = ParseWarning::OrphanClock { line: line_no };
+                    warn!("WARNING: {warning}");
+                    warnings.push(warning);
                 }
                 continue;
             }
+
+            if line
+                .trim_start()
+                .get(..6)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("clock:"))
+            {
+                let warning = ParseWarning::MalformedClock { line: line_no };
+                warn!("WARNING: {warning}");
+                warnings.push(warning);
+            }
+        }
+
+        if let Some(block) = current_block {
+            let warning = ParseWarning::UnterminatedBlock {
+                line: block.start_line,
+            };
+            warn!("WARNING: {warning}");
+            warnings.push(warning);
         }
 
         Self {
             file: file.into(),
             headlines,
             clocks,
+            state_changes,
+            file_tags,
+            file_properties,
+            warnings,
+            source: Some(content),
+            modified: None,
         }
     }
 
+    /// Clocks grouped by the index of their headline, in file order.
+    pub(crate) fn clocks_by_headline(&self) -> BTreeMap<usize, Vec<&Clock<'a>>> {
+        let mut by_headline: BTreeMap<usize, Vec<&Clock<'a>>> = BTreeMap::new();
+        for clock in &self.clocks {
+            by_headline.entry(clock.parent).or_default().push(clock);
+        }
+        by_headline
+    }
+
+    /// Applies `changes` to the text the document was parsed from, without
+    /// reading the file. Errors if the document has no source.
+    pub fn apply_changes(&self, changes: Vec<FileChange>) -> anyhow::Result<String> {
+        let Some(source) = self.source else {
+            return Err(anyhow::anyhow!(
+                "{:?} has no source to apply changes to",
+                self.file
+            ));
+        };
+        Ok(FileChange::apply_to_string(changes, source)?.into_owned())
+    }
+
+    /// [`content_hash`] of the source, to check that the file did not change
+    /// before applying changes computed from this document.
+    pub fn content_hash(&self) -> u64 {
+        content_hash(self.source.unwrap_or_default())
+    }
+
+    /// Index of the parent of the headline at `index`, `None` for top-level
+    /// headlines.
+    pub fn parent_of(&self, index: usize) -> Option<usize> {
+        let headline = self.headlines.get(index)?;
+        let parent = self.headlines.get(headline.parent)?;
+        (parent.level < headline.level).then_some(headline.parent)
+    }
+
+    /// Problems with the structure of the document: unterminated blocks, clocks
+    /// before the first headline, unparsable clock lines and headlines that
+    /// skip a level. Unlike the lints these don't depend on the clocked times,
+    /// so they tell whether the file is well-formed at all. Sorted by line.
+    pub fn validate(&self) -> Vec<StructuralError> {
+        let mut errors = self
+            .warnings
+            .iter()
+            .filter_map(|warning| match warning {
+                ParseWarning::UnterminatedBlock { line } => {
+                    Some(StructuralError::UnterminatedBlock { line: *line })
+                }
+                ParseWarning::OrphanClock { line } => {
+                    Some(StructuralError::OrphanClock { line: *line })
+                }
+                ParseWarning::MalformedClock { line } => {
+                    Some(StructuralError::MalformedClock { line: *line })
+                }
+                ParseWarning::NonConsecutiveClock { .. } | ParseWarning::LongLine { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        for (i, headline) in self.headlines.iter().enumerate() {
+            let Some(parent) = self.parent_of(i).map(|parent| &self.headlines[parent]) else {
+                continue;
+            };
+            if headline.level > parent.level + 1 {
+                errors.push(StructuralError::LevelSkip {
+                    line: headline.line,
+                    level: headline.level,
+                    parent_line: parent.line,
+                    parent_level: parent.level,
+                });
+            }
+        }
+        errors.sort_by_key(StructuralError::line);
+        errors
+    }
+
+    /// Indexes of the ancestors of the headline at `index`, nearest first.
+    pub fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.parent_of(index), |&i| self.parent_of(i))
+    }
+
+    /// The headline at `index` or its nearest ancestor whose TODO keyword is
+    /// one of `done_keywords`.
+    pub fn done_headline(&self, index: usize, done_keywords: &[String]) -> Option<usize> {
+        std::iter::once(index)
+            .chain(self.ancestors(index))
+            .find(|&i| {
+                self.headlines[i]
+                    .keyword()
+                    .is_some_and(|keyword| done_keywords.iter().any(|done| done == keyword))
+            })
+    }
+
+    /// Titles from the outermost ancestor down to the headline at `index`.
+    pub fn title_path(&self, index: usize) -> Vec<&'a str> {
+        let mut path = std::iter::once(index)
+            .chain(self.ancestors(index))
+            .map(|i| self.headlines[i].title)
+            .collect::<Vec<_>>();
+        path.reverse();
+        path
+    }
+
+    /// Index of the outermost ancestor of the headline at `index`, usually
+    /// the level-1 headline. `index` itself for top-level headlines.
+    pub fn top_level_ancestor(&self, index: usize) -> usize {
+        self.ancestors(index).last().unwrap_or(index)
+    }
+
+    /// Whether the headline at `index`, one of its ancestors or the file
+    /// (via `#+FILETAGS:`) has `tag`.
+    pub fn has_inherited_tag(&self, index: usize, tag: &str) -> bool {
+        self.file_tags.contains(&tag)
+            || std::iter::once(index)
+                .chain(self.ancestors(index))
+                .any(|i| self.headlines[i].tags().any(|t| t == tag))
+    }
+
+    /// The tags of the file, the headline at `index` and its ancestors, each
+    /// once, in that order.
+    pub fn inherited_tags(&self, index: usize) -> Vec<&'a str> {
+        let mut tags = self.file_tags.clone();
+        let own = std::iter::once(index)
+            .chain(self.ancestors(index))
+            .flat_map(|i| self.headlines[i].tags());
+        for tag in own {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Looks up a file-level property, case-insensitive like
+    /// [`Headline::property`].
+    pub fn file_property(&self, name: &str) -> Option<&'a str> {
+        self.file_properties
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+
+    /// Number of clocks of each headline including those of its descendants,
+    /// indexed like `headlines`.
+    pub fn subtree_clock_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.headlines.len()];
+        for clock in &self.clocks {
+            counts[clock.parent] += 1;
+            for ancestor in self.ancestors(clock.parent) {
+                counts[ancestor] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Time of the closed clocks of each headline including those of its
+    /// descendants, indexed like `headlines`.
+    pub fn subtree_durations(&self) -> Vec<Duration> {
+        let mut durations = vec![Duration::zero(); self.headlines.len()];
+        for clock in self.clocks.iter().filter(|clock| !clock.is_running()) {
+            let (start, end) = clock.utc_interval();
+            for index in std::iter::once(clock.parent).chain(self.ancestors(clock.parent)) {
+                durations[index] = durations[index] + (end - start);
+            }
+        }
+        durations
+    }
+
+    /// All clocks together with the headline they belong to, in file order.
+    pub fn clocks_with_headlines(&self) -> impl Iterator<Item = (&Headline<'a>, &Clock<'a>)> {
+        self.clocks
+            .iter()
+            .map(|clock| (&self.headlines[clock.parent], clock))
+    }
+
     pub fn file_name(&self) -> &str {
         self.file.file_name().and_then(|f| f.to_str()).unwrap_or("")
     }
 }
+
+/// All clocks of `docs` together with their file and headline.
+pub fn clocks_with_context<'d, 'a>(
+    docs: &'d [OrgDocument<'a>],
+) -> impl Iterator<Item = (&'d PathBuf, &'d Headline<'a>, &'d Clock<'a>)> {
+    docs.iter().flat_map(|doc| {
+        doc.clocks_with_headlines()
+            .map(|(headline, clock)| (&doc.file, headline, clock))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrgDocument, ParseOptions, ParseWarning, StructuralError};
+    use crate::{FileChange, WeekdayLocale};
+
+    const INTERLEAVED: &str = "
+* fooo
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:15] =>  0:15
+  some notes about what happened
+
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+:LOGBOOK:
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:30] =>  0:30
+:END:
+";
+
+    #[test]
+    fn notes_between_clocks_dont_warn() {
+        let doc = OrgDocument::parse("test.org", INTERLEAVED);
+        assert_eq!(doc.clocks.len(), 3);
+        assert_eq!(
+            doc.warnings,
+            vec![ParseWarning::NonConsecutiveClock {
+                line: 8,
+                previous_line: 6
+            }]
+        );
+
+        let strict = ParseOptions {
+            allow_notes_between_clocks: false,
+            ..Default::default()
+        };
+        let doc = OrgDocument::parse_with_options("test.org", INTERLEAVED, &strict);
+        assert_eq!(doc.warnings.len(), 2);
+
+        let disabled = ParseOptions {
+            warn_non_consecutive_clocks: false,
+            ..Default::default()
+        };
+        let doc = OrgDocument::parse_with_options("test.org", INTERLEAVED, &disabled);
+        assert!(doc.warnings.is_empty());
+    }
+
+    #[test]
+    fn render_in_locale() {
+        let org_string = "* task
+CLOCK: [2022-12-12 Mon 23:00]--[2022-12-13 Tue 00:30] =>  1:30
+";
+        let opts = ParseOptions {
+            locale: Some(WeekdayLocale::De),
+            ..Default::default()
+        };
+        let doc = OrgDocument::parse_with_options("test.org", org_string, &opts);
+        assert_eq!(
+            doc.clocks[0].to_string(),
+            "[2022-12-12 Mo 23:00]--[2022-12-13 Di 00:30] =>  1:30"
+        );
+        // the day names as written are kept for the checks
+        assert_eq!(doc.clocks[0].start_weekday, "Mon");
+        assert!(!doc.clocks[0].has_wrong_weekday());
+
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(doc.clocks[0].locale, WeekdayLocale::En);
+    }
+
+    #[test]
+    fn skip_long_lines() {
+        let long = format!("* {}{}\n", "(a+)+".repeat(1 << 20), " :tag:");
+        let org_string = format!(
+            "* before
+{long}CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+* after
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30
+"
+        );
+        let doc = OrgDocument::parse("test.org", &org_string);
+        let titles = doc.headlines.iter().map(|h| h.title).collect::<Vec<_>>();
+        assert_eq!(titles, vec!["before", "after"]);
+        assert_eq!(doc.clocks.len(), 2);
+        assert_eq!(doc.clocks[0].parent, 0);
+        assert_eq!(
+            doc.warnings,
+            vec![ParseWarning::LongLine {
+                line: 2,
+                length: long.len() - 1
+            }]
+        );
+        assert!(doc.validate().is_empty());
+    }
+
+    #[test]
+    fn validate_structure() {
+        let org_string = "CLOCK: [2022-12-12 Mon 08:00]--[2022-12-12 Mon 08:30] =>  0:30
+* project
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+CLOCK: [2022-12-12 11:00]
+*** deep
+#+BEGIN_SRC rust
+fn main() {}
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(
+            doc.validate(),
+            vec![
+                StructuralError::OrphanClock { line: 1 },
+                StructuralError::MalformedClock { line: 4 },
+                StructuralError::LevelSkip {
+                    line: 5,
+                    level: 3,
+                    parent_line: 2,
+                    parent_level: 1
+                },
+                StructuralError::UnterminatedBlock { line: 6 },
+            ]
+        );
+
+        let doc = OrgDocument::parse("test.org", INTERLEAVED);
+        assert!(doc.validate().is_empty());
+    }
+
+    #[test]
+    fn clocks_with_headlines() {
+        let org_string = "
+* project
+CLOCK: [2022-12-12 Mon 08:00]--[2022-12-12 Mon 08:30] =>  0:30
+** task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+*** subtask
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30
+** other task
+CLOCK: [2022-12-12 Mon 12:00]--[2022-12-12 Mon 12:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(
+            doc.clocks_with_headlines()
+                .map(|(headline, clock)| (headline.title, clock.line))
+                .collect::<Vec<_>>(),
+            vec![
+                ("project", 3),
+                ("task", 5),
+                ("subtask", 7),
+                ("other task", 9)
+            ]
+        );
+
+        let docs = [
+            doc,
+            OrgDocument::parse(
+                "other.org",
+                "* foo\nCLOCK: [2022-12-13 Tue 10:00]--[2022-12-13 Tue 10:30] =>  0:30\n",
+            ),
+        ];
+        assert_eq!(
+            super::clocks_with_context(&docs)
+                .map(|(file, headline, _)| (file.to_str().unwrap(), headline.title))
+                .collect::<Vec<_>>(),
+            vec![
+                ("test.org", "project"),
+                ("test.org", "task"),
+                ("test.org", "subtask"),
+                ("test.org", "other task"),
+                ("other.org", "foo")
+            ]
+        );
+    }
+
+    #[test]
+    fn state_changes() {
+        let org_string = r#"
+* DONE task
+:LOGBOOK:
+- State "DONE"       from "TODO"       [2021-04-18 Sun 10:00]
+CLOCK: [2021-04-18 Sun 09:00]--[2021-04-18 Sun 10:00] =>  1:00
+- State "TODO"       from              [2021-04-17 Sat 09:15]
+:END:
+"#;
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert!(doc.state_changes.is_empty());
+
+        let opts = ParseOptions {
+            parse_state_changes: true,
+            ..Default::default()
+        };
+        let doc = OrgDocument::parse_with_options("test.org", org_string, &opts);
+        assert_eq!(doc.clocks.len(), 1);
+        assert_eq!(
+            doc.state_changes
+                .iter()
+                .map(|c| (c.line, c.parent, c.new_state, c.old_state))
+                .collect::<Vec<_>>(),
+            vec![(4, 0, "DONE", Some("TODO")), (6, 0, "TODO", None)]
+        );
+    }
+
+    #[test]
+    fn apply_changes_to_source() {
+        let org_string = "
+* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+";
+        let doc = OrgDocument::parse("does-not-exist.org", org_string);
+        let changes = vec![FileChange::update(&doc.file, doc.clocks[0].clone())];
+        assert_eq!(
+            doc.apply_changes(changes).unwrap(),
+            "
+* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+"
+        );
+    }
+
+    #[test]
+    fn clocks_in_plain_lists() {
+        let org_string = "
+* task
+CLOCK: [2023-01-09 Mon 09:00]--[2023-01-09 Mon 10:00] =>  1:00
+- design
+  CLOCK: [2023-01-09 Mon 10:00]--[2023-01-09 Mon 11:00] =>  1:00
+  1. mockups
+     CLOCK: [2023-01-09 Mon 11:00]--[2023-01-09 Mon 12:00] =>  1:00
+  2) review
+     CLOCK: [2023-01-09 Mon 12:00]--[2023-01-09 Mon 12:30] =>  0:30
+- testing
+  CLOCK: [2023-01-09 Mon 13:00]--[2023-01-09 Mon 14:00] =>  1:00
+CLOCK: [2023-01-09 Mon 14:00]--[2023-01-09 Mon 15:00] =>  1:00
+* next
+  :LOGBOOK:
+  CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:00] =>  1:00
+  :END:
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let depths = doc
+            .clocks
+            .iter()
+            .map(|clock| (clock.parent, clock.list_depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            depths,
+            vec![(0, 0), (0, 1), (0, 2), (0, 2), (0, 1), (0, 0), (1, 0)]
+        );
+    }
+
+    #[test]
+    fn planning_line() {
+        let org_string = "
+* DONE task
+CLOSED: [2021-04-18 Sun 10:00] SCHEDULED: <2021-04-17 Sat> DEADLINE: <2021-04-20 Tue>
+CLOCK: [2021-04-18 Sun 09:00]--[2021-04-18 Sun 10:00] =>  1:00
+SCHEDULED: <2021-04-17 Sat>
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        let planning = &doc.headlines[0].planning;
+        assert!(planning.closed.is_some());
+        assert!(planning.scheduled.is_some());
+        assert!(planning.deadline.is_some());
+        assert_eq!(doc.clocks.len(), 1);
+    }
+
+    #[test]
+    fn ancestors() {
+        let org_string = "
+* project
+** task
+*** subtask
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30
+** other task
+* other project
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(doc.ancestors(2).collect::<Vec<_>>(), vec![1, 0]);
+        assert_eq!(doc.ancestors(3).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(doc.ancestors(0).count(), 0);
+        assert_eq!(doc.ancestors(4).count(), 0);
+        assert_eq!(doc.subtree_clock_counts(), vec![1, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn asterisks_in_lists_are_not_headlines() {
+        let org_string = "
+* project
+** task
+- * not a heading
+  * neither is this
+  - CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(
+            doc.headlines.iter().map(|h| h.title).collect::<Vec<_>>(),
+            vec!["project", "task"]
+        );
+        assert_eq!(doc.clocks.len(), 1);
+        assert_eq!(doc.clocks[0].line, 6);
+        assert_eq!(doc.clocks[0].parent, 1);
+    }
+
+    #[test]
+    fn alternate_heading_char() {
+        let org_string = "
+# project :work:
+## task
+#+BEGIN_SRC sh
+* not a heading
+#+END_SRC
+:LOGBOOK:
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+:END:
+### subtask
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30
+# other
+";
+        let opts = ParseOptions {
+            heading_char: '#',
+            ..Default::default()
+        };
+        let doc = OrgDocument::parse_with_options("test.org", org_string, &opts);
+        assert_eq!(
+            doc.headlines
+                .iter()
+                .map(|h| (h.level, h.title))
+                .collect::<Vec<_>>(),
+            vec![(1, "project"), (2, "task"), (3, "subtask"), (1, "other")]
+        );
+        assert_eq!(doc.headlines[0].tags_string, Some(":work:"));
+        assert_eq!(doc.parent_of(2), Some(1));
+        assert_eq!(
+            doc.clocks.iter().map(|c| c.parent).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert!(doc.validate().is_empty());
+    }
+
+    #[test]
+    fn custom_headline_pattern() {
+        let org_string = "
+= project =
+== task ==
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+* a list item, not a heading here
+";
+        let opts = ParseOptions {
+            headline_pattern: Some(crate::headline_pattern(r"^(=+)\s+(.+?)\s*=+$").unwrap()),
+            ..Default::default()
+        };
+        let doc = OrgDocument::parse_with_options("test.org", org_string, &opts);
+        assert_eq!(
+            doc.headlines
+                .iter()
+                .map(|h| (h.level, h.title))
+                .collect::<Vec<_>>(),
+            vec![(1, "project"), (2, "task")]
+        );
+        assert_eq!(doc.clocks[0].parent, 1);
+    }
+
+    #[test]
+    fn file_tags_and_properties() {
+        let org_string = "#+TITLE: Work
+#+FILETAGS: :billable:acme:
+:PROPERTIES:
+:CATEGORY: acme
+:END:
+
+* project :internal:
+** task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30
+";
+        let doc = OrgDocument::parse("test.org", org_string);
+        assert_eq!(doc.file_tags, vec!["billable", "acme"]);
+        assert_eq!(doc.file_property("category"), Some("acme"));
+        assert!(doc.headlines.iter().all(|h| h.properties.is_empty()));
+        let clock = &doc.clocks[0];
+        assert!(doc.has_inherited_tag(clock.parent, "billable"));
+        assert!(doc.has_inherited_tag(clock.parent, "internal"));
+        assert!(!doc.has_inherited_tag(clock.parent, "private"));
+    }
+}
@@ -1,23 +1,400 @@
 use anyhow::Result;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
-use crate::org_document::OrgDocument;
+use chrono::{DateTime, Local};
+
+use crate::org_document::{OrgDocument, ParseOptions};
 
 #[derive(Debug)]
 pub struct OrgFile {
     pub(crate) file: PathBuf,
     pub(crate) content: String,
+    /// For markdown files: the content with everything outside of ```org
+    /// fences blanked out.
+    pub(crate) org_content: Option<String>,
+    /// Modification time of the file, for files read from disk.
+    pub(crate) modified: Option<SystemTime>,
+    /// [`content_hash`] of the file on disk when it was read or last saved.
+    pub(crate) disk_hash: Option<u64>,
 }
 
 impl OrgFile {
+    pub fn new(file: impl Into<PathBuf>, content: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            content: content.into(),
+            org_content: None,
+            modified: None,
+            disk_hash: None,
+        }
+    }
+
+    /// A markdown file whose ```org fenced code blocks are parsed as org.
+    pub fn new_markdown(file: impl Into<PathBuf>, content: impl Into<String>) -> Self {
+        let content = content.into();
+        Self {
+            file: file.into(),
+            org_content: Some(extract_org_from_markdown(&content)),
+            content,
+            modified: None,
+            disk_hash: None,
+        }
+    }
+
+    /// Reads an org file. Files with a `.md` or `.markdown` extension are read
+    /// as markdown, see [`OrgFile::new_markdown`].
     pub fn from_file(file: impl AsRef<Path>) -> Result<Self> {
         let file = file.as_ref().to_path_buf();
         let content = std::fs::read_to_string(&file)?;
-        Ok(Self { file, content })
+        let modified = std::fs::metadata(&file)?.modified().ok();
+        let org_file = if is_markdown_file(&file) {
+            Self::new_markdown(file, content)
+        } else {
+            Self::new(file, content)
+        };
+        Ok(Self {
+            modified,
+            disk_hash: Some(content_hash(&org_file.content)),
+            ..org_file
+        })
+    }
+
+    pub(crate) fn set_content(&mut self, content: String) {
+        if self.org_content.is_some() {
+            self.org_content = Some(extract_org_from_markdown(&content));
+        }
+        self.content = content;
+    }
+
+    /// The text that is parsed as org.
+    fn source(&self) -> &str {
+        self.org_content.as_deref().unwrap_or(&self.content)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Whether the file was read from disk and changed there since it was
+    /// read or last saved, e.g. because it was edited in Emacs while fixing.
+    pub fn changed_on_disk(&self) -> Result<bool> {
+        let Some(expected) = self.disk_hash else {
+            return Ok(false);
+        };
+        let on_disk = std::fs::read_to_string(&self.file)?;
+        Ok(content_hash(&on_disk) != expected)
     }
 
-    pub fn document(&self) -> OrgDocument {
+    /// Writes the (possibly modified) content back to the file. Errors
+    /// without writing if the file changed on disk, see
+    /// [`OrgFile::changed_on_disk`]. All fixes are written this way.
+    pub fn save(&mut self) -> Result<()> {
+        if self.changed_on_disk()? {
+            return Err(anyhow::anyhow!(
+                "{:?} changed on disk since it was read, not saving",
+                self.file
+            ));
+        }
+        std::fs::write(&self.file, &self.content)?;
+        self.disk_hash = Some(content_hash(&self.content));
+        Ok(())
+    }
+
+    /// Replaces the content with `content` and saves it, see
+    /// [`OrgFile::save`].
+    pub fn save_content(&mut self, content: impl Into<String>) -> Result<()> {
+        self.set_content(content.into());
+        self.save()
+    }
+
+    pub fn document(&self) -> OrgDocument<'_> {
+        self.document_with_options(&ParseOptions::default())
+    }
+
+    /// Parses the file. The document's source is the full file content, so
+    /// changes applied to it keep the markdown around ```org blocks.
+    pub fn document_with_options(&self, opts: &ParseOptions) -> OrgDocument<'_> {
         trace!("parsing file {:?}", self.file);
-        OrgDocument::parse(&self.file, &self.content)
+        let mut doc = OrgDocument::parse_with_options(&self.file, self.source(), opts);
+        doc.source = Some(&self.content);
+        doc.modified = self
+            .modified
+            .map(|modified| DateTime::<Local>::from(modified).naive_local());
+        doc
+    }
+}
+
+/// Hash of a file content to tell whether the file changed, see
+/// [`OrgFile::save`] and [`crate::FileChange::apply`].
+pub fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The files of the `#+INCLUDE:` directives in `content` with their lines,
+/// relative to the directory of `file`. Targets like `sub.org::*Section` and
+/// options after the file name are ignored, the whole file is included.
+pub fn includes(file: &Path, content: &str) -> Vec<(usize, PathBuf)> {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.trim_start();
+            let rest = line
+                .get(..10)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("#+include:"))
+                .map(|_| line[10..].trim())?;
+            let target = match rest.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next()?,
+                None => rest.split_whitespace().next()?,
+            };
+            let target = target.split("::").next().unwrap_or(target);
+            (!target.is_empty()).then(|| (i + 1, dir.join(target)))
+        })
+        .collect()
+}
+
+/// `files` and, recursively, the files they include with `#+INCLUDE:`. Each
+/// file is listed once. Includes of missing files and include cycles don't
+/// fail but are returned as warnings.
+pub fn follow_includes(files: &[PathBuf]) -> (Vec<PathBuf>, Vec<String>) {
+    fn visit(
+        file: &Path,
+        stack: &mut Vec<PathBuf>,
+        seen: &mut HashSet<PathBuf>,
+        result: &mut Vec<PathBuf>,
+        warnings: &mut Vec<String>,
+    ) {
+        let id = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        if !seen.insert(id.clone()) {
+            return;
+        }
+        result.push(file.to_path_buf());
+        let Ok(content) = std::fs::read_to_string(file) else {
+            return;
+        };
+        stack.push(id);
+        for (line, included) in includes(file, &content) {
+            let location = format!("{}:{line}", file.display());
+            match included.canonicalize() {
+                Err(err) => warnings.push(format!(
+                    "[{location}] cannot include {}: {err}",
+                    included.display()
+                )),
+                Ok(id) if stack.contains(&id) => warnings.push(format!(
+                    "[{location}] include cycle: {} includes itself",
+                    included.display()
+                )),
+                Ok(_) => visit(&included, stack, seen, result, warnings),
+            }
+        }
+        stack.pop();
+    }
+
+    let mut stack = Vec::new();
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    let mut warnings = Vec::new();
+    for file in files {
+        visit(file, &mut stack, &mut seen, &mut result, &mut warnings);
+    }
+    (result, warnings)
+}
+
+/// The file that `file` is the archive of: `foo.org` for `foo_archive.org`
+/// or for org's default archive location `foo.org_archive`.
+pub fn archive_origin(file: &Path) -> Option<PathBuf> {
+    let name = file.file_name()?.to_str()?;
+    let origin = name
+        .strip_suffix("_archive.org")
+        .map(|stem| format!("{stem}.org"))
+        .or_else(|| name.strip_suffix("_archive").map(String::from))
+        .filter(|origin| origin.len() > ".org".len() && origin.ends_with(".org"))?;
+    Some(file.with_file_name(origin))
+}
+
+pub fn is_markdown_file(file: &Path) -> bool {
+    matches!(
+        file.extension().and_then(|ext| ext.to_str()),
+        Some("md" | "markdown")
+    )
+}
+
+/// Blanks out all lines of a markdown document that are not inside of a
+/// ```org (or ~~~org) fenced code block. The line count stays the same so
+/// line numbers of the parsed org content match the markdown file.
+pub fn extract_org_from_markdown(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        match fence {
+            Some(marker) if trimmed == marker => fence = None,
+            Some(_) => result.push_str(line),
+            None => {
+                for marker in ["```", "~~~"] {
+                    if let Some(info) = trimmed.strip_prefix(marker) {
+                        if info.split_whitespace().next() == Some("org") {
+                            fence = Some(marker);
+                        }
+                    }
+                }
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::TimeZone;
+
+    use super::OrgFile;
+    use crate::{lint_document, FindingKind, LintOptions};
+
+    #[test]
+    fn save_refuses_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.org");
+        std::fs::write(&path, "* task\n").unwrap();
+        let mut org_file = OrgFile::from_file(&path).unwrap();
+        org_file.set_content("* fixed task\n".to_string());
+        org_file.save().unwrap();
+        // saving again is fine, the file is as we left it
+        org_file.save().unwrap();
+
+        std::fs::write(&path, "* edited in emacs\n").unwrap();
+        assert!(org_file.save().is_err());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "* edited in emacs\n"
+        );
+    }
+
+    #[test]
+    fn modification_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.org");
+        std::fs::write(
+            &path,
+            "* task\nCLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:00] =>  1:00\n",
+        )
+        .unwrap();
+        let modified = chrono::Local
+            .with_ymd_and_hms(2023, 6, 1, 12, 0, 0)
+            .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified.into())
+            .unwrap();
+
+        let org_file = OrgFile::from_file(&path).unwrap();
+        let doc = org_file.document();
+        assert_eq!(doc.modified, Some(modified.naive_local()));
+        let opts = LintOptions {
+            report_stale_clocking: Some(90),
+            ..Default::default()
+        };
+        let findings = lint_document(&doc, &opts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::StaleClocking);
+    }
+
+    #[test]
+    fn archive_origin() {
+        let origin = |file: &str| super::archive_origin(Path::new(file));
+        assert_eq!(origin("org/foo_archive.org"), Some("org/foo.org".into()));
+        assert_eq!(origin("foo.org_archive"), Some("foo.org".into()));
+        assert_eq!(origin("foo.org"), None);
+        assert_eq!(origin("_archive.org"), None);
+    }
+
+    #[test]
+    fn follow_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let master = dir.path().join("master.org");
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(
+            &master,
+            "#+INCLUDE: \"sub/work.org\" :minlevel 2
+#+include: missing.org
+* master
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 10:00] =>  1:00
+",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("sub/work.org"),
+            "#+INCLUDE: \"../master.org::*master\"
+* work
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+",
+        )
+        .unwrap();
+
+        let (files, warnings) = super::follow_includes(std::slice::from_ref(&master));
+        assert_eq!(files, vec![master.clone(), dir.path().join("sub/work.org")]);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("include cycle"));
+        assert!(warnings[0].starts_with(&format!("[{}:1]", files[1].display())));
+        assert!(warnings[1].contains("cannot include"));
+        assert!(warnings[1].starts_with(&format!("[{}:2]", master.display())));
+
+        let findings = files
+            .iter()
+            .flat_map(|file| {
+                let org_file = OrgFile::from_file(file).unwrap();
+                lint_document(&org_file.document(), &LintOptions::default())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::DurationMismatch);
+        assert_eq!(findings[0].file, dir.path().join("sub/work.org"));
+        assert_eq!(findings[0].line, 3);
+    }
+
+    #[test]
+    fn parse_org_in_markdown() {
+        let markdown = "# Notes
+
+* this is a markdown list, not a headline
+
+```org
+* project
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+** task
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30
+```
+
+```sh
+* not org CLOCK: [2022-12-12 Mon 12:00]--[2022-12-12 Mon 13:00] =>  1:00
+```
+";
+        let org_file = OrgFile::new_markdown("notes.md", markdown);
+        let doc = org_file.document();
+        assert_eq!(doc.headlines.len(), 2);
+        assert_eq!(doc.headlines[0].title, "project");
+        assert_eq!(doc.headlines[0].line, 6);
+        assert_eq!(
+            doc.clocks.iter().map(|c| c.line).collect::<Vec<_>>(),
+            vec![7, 9]
+        );
+        assert_eq!(doc.clocks[1].parent, 1);
     }
 }
@@ -0,0 +1,208 @@
+//! Checks files concurrently while holding only a bounded number of file
+//! contents in memory: a reader thread reads a file whenever one of a fixed
+//! number of permits is free and sends it to worker threads that parse and
+//! check it and then hand the permit back. The calling thread collects the
+//! findings.
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use anyhow::Result;
+
+use crate::{Finding, OrgDocument, OrgFile, ParseOptions};
+
+/// Runs `check` on each of `files` with at most `max_in_flight` file contents
+/// read but not yet checked. The findings are in the order of `files`, like
+/// checking one file after the other. Fails with the error of the first file
+/// that can't be read, files after it aren't read.
+pub fn check_files_pipelined<F>(
+    files: &[PathBuf],
+    max_in_flight: NonZeroUsize,
+    parse_options: &ParseOptions,
+    check: F,
+) -> Result<Vec<Finding>>
+where
+    F: Fn(&OrgDocument) -> Vec<Finding> + Sync,
+{
+    check_with_reader(
+        files,
+        max_in_flight,
+        parse_options,
+        |file| OrgFile::from_file(file),
+        check,
+    )
+}
+
+/// [`check_files_pipelined`] with `read` instead of reading from disk.
+fn check_with_reader<R, F>(
+    files: &[PathBuf],
+    max_in_flight: NonZeroUsize,
+    parse_options: &ParseOptions,
+    read: R,
+    check: F,
+) -> Result<Vec<Finding>>
+where
+    R: Fn(&PathBuf) -> Result<OrgFile> + Send,
+    F: Fn(&OrgDocument) -> Vec<Finding> + Sync,
+{
+    let cores = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let workers = cores.min(max_in_flight.get());
+    // a file is read only with a permit, which is returned once it is checked,
+    // so files waiting to be sent or received count against the bound too
+    let (permit_tx, permit_rx) = mpsc::sync_channel::<()>(max_in_flight.get());
+    for _ in 0..max_in_flight.get() {
+        permit_tx.send(()).unwrap();
+    }
+    let (file_tx, file_rx) = mpsc::channel::<(usize, Result<OrgFile>)>();
+    let file_rx = Mutex::new(file_rx);
+    let (result_tx, result_rx) = mpsc::channel::<(usize, Result<Vec<Finding>>)>();
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for (i, file) in files.iter().enumerate() {
+                if permit_rx.recv().is_err() {
+                    break;
+                }
+                let org_file = read(file);
+                // the first error fails the whole check, no need to read on
+                let failed = org_file.is_err();
+                if file_tx.send((i, org_file)).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        for _ in 0..workers {
+            let (result_tx, permit_tx) = (result_tx.clone(), permit_tx.clone());
+            let (file_rx, check) = (&file_rx, &check);
+            scope.spawn(move || loop {
+                // the lock is released before checking so others can receive
+                let received = file_rx.lock().unwrap().recv();
+                let Ok((i, org_file)) = received else {
+                    break;
+                };
+                let findings =
+                    org_file.map(|org_file| check(&org_file.document_with_options(parse_options)));
+                // fails only once the reader is done
+                let _ = permit_tx.send(());
+                if result_tx.send((i, findings)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop((result_tx, permit_tx));
+
+        let results = result_rx.into_iter().collect::<BTreeMap<_, _>>();
+        let mut findings = Vec::new();
+        for (_, result) in results {
+            findings.extend(result?);
+        }
+        Ok(findings)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::NonZeroUsize,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use crate::{lint_document, LintOptions, OrgFile, ParseOptions};
+
+    #[test]
+    fn same_findings_as_sequential() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut files = Vec::new();
+        for i in 0..20 {
+            let file = dir.path().join(format!("{i:02}.org"));
+            let day = chrono::NaiveDate::from_ymd_opt(2023, 1, i + 1)
+                .unwrap()
+                .format("%Y-%m-%d %a");
+            let content = format!(
+                "
+* task {i}
+CLOCK: [{day} 09:00]--[{day} {end:02}:00] =>  1:00
+CLOCK: [{day} 11:00]--[{day} 10:00] => -1:00
+",
+                end = 10 + i % 12,
+            );
+            std::fs::write(&file, content).unwrap();
+            files.push(file);
+        }
+        let opts = LintOptions {
+            report_long_duration: true,
+            long_duration: chrono::Duration::hours(4),
+            ..Default::default()
+        };
+        let parse_options = ParseOptions::default();
+
+        let sequential = files
+            .iter()
+            .flat_map(|file| {
+                let org_file = OrgFile::from_file(file).unwrap();
+                lint_document(&org_file.document_with_options(&parse_options), &opts)
+            })
+            .collect::<Vec<_>>();
+        assert!(sequential.len() > files.len());
+
+        for max_in_flight in [1, 3, 64] {
+            let pipelined = super::check_files_pipelined(
+                &files,
+                NonZeroUsize::new(max_in_flight).unwrap(),
+                &parse_options,
+                |doc| lint_document(doc, &opts),
+            )
+            .unwrap();
+            assert_eq!(pipelined, sequential);
+        }
+
+        files.insert(5, dir.path().join("missing.org"));
+        assert!(super::check_files_pipelined(
+            &files,
+            NonZeroUsize::new(2).unwrap(),
+            &parse_options,
+            |doc| lint_document(doc, &opts),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn bounds_contents_in_flight() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = (0..30)
+            .map(|i| {
+                let file = dir.path().join(format!("{i:02}.org"));
+                std::fs::write(&file, format!("* task {i}\n")).unwrap();
+                file
+            })
+            .collect::<Vec<_>>();
+        let parse_options = ParseOptions::default();
+
+        for max_in_flight in [1, 2, 5] {
+            let (in_flight, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+            let read = |file: &std::path::PathBuf| {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                OrgFile::from_file(file)
+            };
+            super::check_with_reader(
+                &files,
+                NonZeroUsize::new(max_in_flight).unwrap(),
+                &parse_options,
+                read,
+                |_| {
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Vec::new()
+                },
+            )
+            .unwrap();
+            assert!(peak.load(Ordering::SeqCst) <= max_in_flight);
+        }
+    }
+}
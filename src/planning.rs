@@ -0,0 +1,137 @@
+use chrono::{NaiveDate, NaiveTime};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::TimestampType;
+
+/// A timestamp of a planning line. Org allows omitting the time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlanningTimestamp {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    pub timestamp_type: TimestampType,
+}
+
+/// The `CLOSED:`, `SCHEDULED:` and `DEADLINE:` timestamps of a headline,
+/// written on the line directly after it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Planning {
+    pub closed: Option<PlanningTimestamp>,
+    pub scheduled: Option<PlanningTimestamp>,
+    pub deadline: Option<PlanningTimestamp>,
+}
+
+impl Planning {
+    pub fn is_empty(&self) -> bool {
+        self.closed.is_none() && self.scheduled.is_none() && self.deadline.is_none()
+    }
+}
+
+/// Cheap pre-check so that lines which cannot be a planning line skip
+/// [`PLANNING_RE`].
+#[inline]
+pub(crate) fn maybe_planning(line: &str) -> bool {
+    line.contains("CLOSED:") || line.contains("SCHEDULED:") || line.contains("DEADLINE:")
+}
+
+pub(crate) static PLANNING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?x)
+(CLOSED|SCHEDULED|DEADLINE):\s*
+([\[<])                                           # < or [ timestamp type
+([0-9]{4})-([0-9]{2})-([0-9]{2})                  # yyyy-mm-dd
+(?:\s+[^\s\]>0-9+.-][^\s\]>]*)?                   # day of week (can be localized)
+(?:\s+([0-9]{1,2}):([0-9]{2}))?                   # HH:MM
+[^\]>]*                                           # time range, repeater, warning
+[\]>]
+",
+    )
+    .expect("planning re")
+});
+
+impl TryFrom<&str> for Planning {
+    type Error = anyhow::Error;
+
+    /// Parses a planning line. The keywords can come in any order, the whole
+    /// line must consist of them.
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        if !maybe_planning(s) {
+            return Err(anyhow::anyhow!("not a planning line: {s:?}"));
+        }
+        let mut planning = Planning::default();
+        for captures in PLANNING_RE.captures_iter(s) {
+            let number = |i: usize| captures.get(i).map(|c| c.as_str().parse::<u32>());
+            let date = NaiveDate::from_ymd_opt(
+                number(3).unwrap()? as i32,
+                number(4).unwrap()?,
+                number(5).unwrap()?,
+            )
+            .ok_or_else(|| anyhow::anyhow!("invalid date in planning line: {s:?}"))?;
+            let time = match (number(6), number(7)) {
+                (Some(hour), Some(min)) => Some(
+                    NaiveTime::from_hms_opt(hour?, min?, 0)
+                        .ok_or_else(|| anyhow::anyhow!("invalid time in planning line: {s:?}"))?,
+                ),
+                _ => None,
+            };
+            let timestamp = PlanningTimestamp {
+                date,
+                time,
+                timestamp_type: captures
+                    .get(2)
+                    .unwrap()
+                    .as_str()
+                    .chars()
+                    .next()
+                    .unwrap()
+                    .into(),
+            };
+            match captures.get(1).unwrap().as_str() {
+                "CLOSED" => planning.closed = Some(timestamp),
+                "SCHEDULED" => planning.scheduled = Some(timestamp),
+                _ => planning.deadline = Some(timestamp),
+            }
+        }
+        let leftover = PLANNING_RE.replace_all(s, "");
+        if planning.is_empty() || !leftover.trim().is_empty() {
+            return Err(anyhow::anyhow!("not a planning line: {s:?}"));
+        }
+        Ok(planning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+
+    use super::Planning;
+    use crate::TimestampType;
+
+    #[test]
+    fn parse_planning_line() {
+        let planning = Planning::try_from(
+            "   DEADLINE: <2021-04-20 Tue> CLOSED: [2021-04-18 Sun 10:00] SCHEDULED: <2021-04-17 Sat 09:30 +1w>",
+        )
+        .expect("parse planning");
+        let closed = planning.closed.unwrap();
+        assert_eq!(closed.date, NaiveDate::from_ymd_opt(2021, 4, 18).unwrap());
+        assert_eq!(closed.time, NaiveTime::from_hms_opt(10, 0, 0));
+        assert_eq!(closed.timestamp_type, TimestampType::Inactive);
+        let scheduled = planning.scheduled.unwrap();
+        assert_eq!(
+            scheduled.date,
+            NaiveDate::from_ymd_opt(2021, 4, 17).unwrap()
+        );
+        assert_eq!(scheduled.time, NaiveTime::from_hms_opt(9, 30, 0));
+        assert_eq!(scheduled.timestamp_type, TimestampType::Active);
+        let deadline = planning.deadline.unwrap();
+        assert_eq!(deadline.date, NaiveDate::from_ymd_opt(2021, 4, 20).unwrap());
+        assert_eq!(deadline.time, None);
+
+        let planning = Planning::try_from("SCHEDULED: <2021-04-17 Sat>").unwrap();
+        assert!(planning.closed.is_none() && planning.deadline.is_none());
+
+        assert!(Planning::try_from("the DEADLINE: <2021-04-20 Tue> is near").is_err());
+        assert!(Planning::try_from("CLOCK: [2021-04-18 Sun 00:57]").is_err());
+    }
+}
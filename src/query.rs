@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{parse_duration, Clock, OrgDocument};
+
+/// A filter over clocks, parsed from expressions like
+///
+/// ```text
+/// duration > 2:00 and tag:work and date >= 2023-01-01
+/// running or not tag:billable
+/// ```
+///
+/// Predicates:
+/// - `duration <op> DURATION`, e.g. `2:00`, `90m` or `1.5h`, of finished clocks
+/// - `date <op> YYYY-MM-DD`, the start date
+/// - `tag:NAME`, the headline or one of its ancestors has the tag
+/// - `running`, the clock has no end
+///
+/// `<op>` is one of `=`, `!=`, `<`, `<=`, `>` and `>=`. Predicates are
+/// combined with `and`, `or` and `not`, `and` binds stronger than `or`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Duration(Comparison, Duration),
+    Date(Comparison, NaiveDate),
+    Tag(String),
+    Running,
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "=" | "==" => Self::Eq,
+            "!=" => Self::Ne,
+            "<" => Self::Lt,
+            "<=" => Self::Le,
+            ">" => Self::Gt,
+            ">=" => Self::Ge,
+            _ => return None,
+        })
+    }
+
+    fn compare<T: Ord>(&self, a: T, b: T) -> bool {
+        match self {
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+        }
+    }
+}
+
+static TOKEN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"==|!=|<=|>=|[<>=]|[^\s<>=!]+").expect("token re"));
+
+impl Query {
+    pub fn parse(s: &str) -> Result<Self> {
+        let tokens = TOKEN_RE
+            .find_iter(s)
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>();
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.peek() {
+            Some(token) => Err(anyhow!("unexpected {token:?} in query")),
+            None => Ok(query),
+        }
+    }
+
+    /// Whether `clock` of `doc` matches the query.
+    pub fn matches(&self, doc: &OrgDocument, clock: &Clock) -> bool {
+        match self {
+            Query::Duration(cmp, duration) => {
+                !clock.is_running() && cmp.compare(clock.duration(), *duration)
+            }
+            Query::Date(cmp, date) => cmp.compare(clock.start.date(), *date),
+            Query::Tag(tag) => doc.has_inherited_tag(clock.parent, tag),
+            Query::Running => clock.is_running(),
+            Query::Not(query) => !query.matches(doc, clock),
+            Query::And(a, b) => a.matches(doc, clock) && b.matches(doc, clock),
+            Query::Or(a, b) => a.matches(doc, clock) || b.matches(doc, clock),
+        }
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<&'a str> {
+        let token = self
+            .peek()
+            .ok_or_else(|| anyhow!("query ends unexpectedly"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn or(&mut self) -> Result<Query> {
+        let mut query = self.and()?;
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("or")) {
+            self.pos += 1;
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query> {
+        let mut query = self.not()?;
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("and")) {
+            self.pos += 1;
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+        Ok(query)
+    }
+
+    fn not(&mut self) -> Result<Query> {
+        if self.peek().is_some_and(|t| t.eq_ignore_ascii_case("not")) {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Query> {
+        let token = self.next()?;
+        if let Some(tag) = token.strip_prefix("tag:") {
+            return Ok(Query::Tag(tag.to_string()));
+        }
+        match token.to_ascii_lowercase().as_str() {
+            "running" => Ok(Query::Running),
+            "duration" => {
+                let cmp = self.comparison()?;
+                let value = self.next()?;
+                let duration = parse_duration(value).map_err(|err| anyhow!(err))?;
+                Ok(Query::Duration(cmp, duration))
+            }
+            "date" => {
+                let cmp = self.comparison()?;
+                let value = self.next()?;
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| anyhow!("cannot parse date {value:?}, expected YYYY-MM-DD"))?;
+                Ok(Query::Date(cmp, date))
+            }
+            _ => Err(anyhow!(
+                "unknown predicate {token:?}, expected duration, date, tag:NAME or running"
+            )),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Comparison> {
+        let token = self.next()?;
+        Comparison::parse(token).ok_or_else(|| anyhow!("expected a comparison, got {token:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Query;
+    use crate::OrgDocument;
+
+    const FIXTURE: &str = "
+* client :work:
+** meeting
+CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 12:00] =>  3:00
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 12:30] =>  3:30
+** email
+CLOCK: [2023-01-11 Wed 09:00]--[2023-01-11 Wed 09:30] =>  0:30
+CLOCK: [2023-01-12 Thu 09:00]
+* hobby
+CLOCK: [2023-01-14 Sat 10:00]--[2023-01-14 Sat 14:00] =>  4:00
+";
+
+    fn matching_lines(query: &str) -> Vec<usize> {
+        let doc = OrgDocument::parse("test.org", FIXTURE);
+        let query = Query::parse(query).expect("parse query");
+        doc.clocks
+            .iter()
+            .filter(|clock| query.matches(&doc, clock))
+            .map(|clock| clock.line)
+            .collect()
+    }
+
+    #[test]
+    fn compound_queries() {
+        assert_eq!(
+            matching_lines("duration > 2:00 and tag:work and date >= 2023-01-01"),
+            vec![4]
+        );
+        assert_eq!(matching_lines("running or not tag:work"), vec![8, 10]);
+        assert_eq!(
+            matching_lines("duration>=180 and not tag:work or date<2023-01-01"),
+            vec![5, 10]
+        );
+    }
+
+    #[test]
+    fn invalid_queries() {
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse("duration 2:00").is_err());
+        assert!(Query::parse("date > yesterday").is_err());
+        assert!(Query::parse("running and").is_err());
+        assert!(Query::parse("running running").is_err());
+        assert!(Query::parse("effort > 1:00").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use serde_json::{json, Value};
+
+use crate::{FindingKind, Severity, Suppression};
+
+/// JSON Schema (draft 2020-12) of the JSON output: the array of findings of
+/// `--format json`, the object of `--format summary-json` and the count of
+/// `--count-only`. Kinds and severities are taken from the enums so that they
+/// can't get out of sync.
+pub fn json_schema() -> Value {
+    let kinds = FindingKind::ALL
+        .iter()
+        .map(|kind| kind.name())
+        .collect::<Vec<_>>();
+    let severities = [Severity::Info, Severity::Warning, Severity::Error]
+        .iter()
+        .map(|severity| severity.name())
+        .collect::<Vec<_>>();
+    let suppressions = Suppression::ALL
+        .iter()
+        .map(|suppression| suppression.name())
+        .collect::<Vec<_>>();
+    let nullable_string = json!({ "type": ["string", "null"] });
+    let count = json!({ "type": "integer", "minimum": 0 });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "org-linter output",
+        "oneOf": [
+            { "type": "array", "items": { "$ref": "#/$defs/Finding" } },
+            { "$ref": "#/$defs/Summary" },
+            {
+                "type": "object",
+                "properties": { "count": count },
+                "required": ["count"],
+                "additionalProperties": false
+            }
+        ],
+        "$defs": {
+            "FindingKind": { "enum": kinds },
+            "Severity": { "enum": severities },
+            "Location": {
+                "type": "object",
+                "properties": {
+                    "file": { "type": "string" },
+                    "line": count
+                },
+                "required": ["file", "line"],
+                "additionalProperties": false
+            },
+            "Finding": {
+                "type": "object",
+                "properties": {
+                    "kind": { "$ref": "#/$defs/FindingKind" },
+                    "severity": { "$ref": "#/$defs/Severity" },
+                    "file": { "type": "string" },
+                    "line": count,
+                    "message": { "type": "string" },
+                    "related": { "$ref": "#/$defs/Location" },
+                    "suppressed": { "enum": suppressions },
+                    "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "fingerprint": { "type": "string" }
+                },
+                "required": ["kind", "severity", "file", "line", "message"],
+                "additionalProperties": false
+            },
+            "Stats": {
+                "type": "object",
+                "properties": {
+                    "files": count,
+                    "headlines": count,
+                    "clocks": count,
+                    "running_clocks": count,
+                    "total_minutes": { "type": "integer" },
+                    "total": { "type": "string" },
+                    "first_clock": nullable_string,
+                    "last_clock": nullable_string
+                },
+                "required": [
+                    "files", "headlines", "clocks", "running_clocks",
+                    "total_minutes", "total", "first_clock", "last_clock"
+                ],
+                "additionalProperties": false
+            },
+            "Summary": {
+                "type": "object",
+                "properties": {
+                    "stats": { "$ref": "#/$defs/Stats" },
+                    "findings": { "type": "array", "items": { "$ref": "#/$defs/Finding" } }
+                },
+                "required": ["stats", "findings"],
+                "additionalProperties": false
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::json_schema;
+    use crate::{
+        lint_document, Finding, FindingKind, LintOptions, OrgDocument, Stats, Summary, Suppression,
+    };
+
+    #[test]
+    fn output_validates_against_schema() {
+        let schema = json_schema();
+        let validator = jsonschema::JSONSchema::compile(&schema).expect("valid schema");
+
+        let org_string = "
+* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+CLOCK: [2022-12-12 Mon 09:00]
+";
+        let docs = [OrgDocument::parse("test.org", org_string)];
+        let mut findings = lint_document(&docs[0], &LintOptions::default());
+        findings.push(
+            Finding::new(FindingKind::ClockConflict, "a.org", 1, "conflict")
+                .with_related("b.org", 2),
+        );
+        findings.push(
+            Finding::new(FindingKind::LongDuration, "a.org", 3, "long")
+                .suppressed_by(Suppression::Allowlist),
+        );
+
+        let json = serde_json::to_value(&findings).unwrap();
+        assert!(validator.is_valid(&json));
+
+        let summary = Summary {
+            stats: Stats::collect(&docs),
+            findings: &findings,
+        };
+        let json = serde_json::to_value(&summary).unwrap();
+        assert!(validator.is_valid(&json));
+
+        assert!(validator.is_valid(&serde_json::json!({ "count": 3 })));
+
+        let mut invalid = serde_json::to_value(&findings).unwrap();
+        invalid[0]["kind"] = "no-such-kind".into();
+        assert!(!validator.is_valid(&invalid));
+    }
+}
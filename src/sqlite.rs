@@ -0,0 +1,159 @@
+//! Export of the parsed documents to a SQLite database, behind the `sqlite`
+//! feature.
+//!
+//! ```sql
+//! CREATE TABLE files (id INTEGER PRIMARY KEY, path TEXT NOT NULL);
+//! CREATE TABLE headlines (
+//!     id INTEGER PRIMARY KEY, file INTEGER NOT NULL, line INTEGER NOT NULL,
+//!     level INTEGER NOT NULL, parent INTEGER, title TEXT NOT NULL, tags TEXT NOT NULL
+//! );
+//! CREATE TABLE clocks (
+//!     id INTEGER PRIMARY KEY, file INTEGER NOT NULL, headline INTEGER NOT NULL,
+//!     line INTEGER NOT NULL, start TEXT NOT NULL, end TEXT, duration_minutes INTEGER
+//! );
+//! ```
+//!
+//! `file`, `parent` and `headline` reference the `id` of the other tables.
+//! `tags` are the headline's own tags like in org, e.g. `:work:urgent:`, or
+//! empty. Timestamps are local ISO 8601 strings like `2022-12-12T10:00:00`,
+//! so they sort and work with SQLite's date functions. `end` and
+//! `duration_minutes` are `NULL` for running clocks.
+
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+use crate::{DocumentJson, OrgDocument};
+
+const SCHEMA: &str = "
+CREATE TABLE files (id INTEGER PRIMARY KEY, path TEXT NOT NULL);
+CREATE TABLE headlines (
+    id INTEGER PRIMARY KEY, file INTEGER NOT NULL REFERENCES files(id), line INTEGER NOT NULL,
+    level INTEGER NOT NULL, parent INTEGER REFERENCES headlines(id), title TEXT NOT NULL,
+    tags TEXT NOT NULL
+);
+CREATE TABLE clocks (
+    id INTEGER PRIMARY KEY, file INTEGER NOT NULL REFERENCES files(id),
+    headline INTEGER NOT NULL REFERENCES headlines(id), line INTEGER NOT NULL,
+    start TEXT NOT NULL, end TEXT, duration_minutes INTEGER
+);
+";
+
+/// Writes `docs` to a new SQLite database at `path`. An existing file is
+/// replaced.
+pub fn export_sqlite(docs: &[OrgDocument], path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let mut conn = Connection::open(path)?;
+    write_documents(&mut conn, docs)
+}
+
+/// Creates the tables in `conn` and inserts `docs` in one transaction.
+pub fn write_documents(conn: &mut Connection, docs: &[OrgDocument]) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+    let mut next_headline = 0;
+    for (file_id, doc) in docs.iter().enumerate() {
+        let json = DocumentJson::new(doc);
+        tx.execute(
+            "INSERT INTO files (id, path) VALUES (?1, ?2)",
+            params![file_id, json.file.to_string_lossy()],
+        )?;
+        // headline indexes are per document, ids are global
+        let first_headline = next_headline;
+        for (i, headline) in json.headlines.iter().enumerate() {
+            let tags = if headline.tags.is_empty() {
+                String::new()
+            } else {
+                format!(":{}:", headline.tags.join(":"))
+            };
+            tx.execute(
+                "INSERT INTO headlines (id, file, line, level, parent, title, tags)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    first_headline + i,
+                    file_id,
+                    headline.line,
+                    headline.level,
+                    headline.parent.map(|parent| first_headline + parent),
+                    headline.title,
+                    tags
+                ],
+            )?;
+        }
+        next_headline += json.headlines.len();
+        for clock in &json.clocks {
+            tx.execute(
+                "INSERT INTO clocks (file, headline, line, start, end, duration_minutes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    file_id,
+                    first_headline + clock.headline,
+                    clock.line,
+                    clock.start,
+                    clock.end,
+                    clock.duration_minutes
+                ],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::OrgDocument;
+
+    #[test]
+    fn export_and_query() {
+        let a = "
+* project :work:
+** task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+CLOCK: [2022-12-13 Tue 10:00]--[2022-12-13 Tue 10:30] =>  0:30
+";
+        let b = "
+* other
+CLOCK: [2022-12-14 Wed 10:00]
+";
+        let docs = [
+            OrgDocument::parse("a.org", a),
+            OrgDocument::parse("b.org", b),
+        ];
+        let mut conn = Connection::open_in_memory().unwrap();
+        super::write_documents(&mut conn, &docs).unwrap();
+
+        let count: i64 = conn
+            .query_row("SELECT count(*) FROM clocks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let (title, minutes): (String, i64) = conn
+            .query_row(
+                "SELECT p.title, sum(c.duration_minutes) FROM clocks c
+                 JOIN headlines h ON c.headline = h.id
+                 JOIN headlines p ON h.parent = p.id
+                 WHERE p.tags LIKE '%:work:%'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((title.as_str(), minutes), ("project", 90));
+
+        let (path, end): (String, Option<String>) = conn
+            .query_row(
+                "SELECT f.path, c.end FROM clocks c JOIN files f ON c.file = f.id
+                 WHERE c.start = '2022-12-14T10:00:00'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((path.as_str(), end), ("b.org", None));
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// A TODO state change note as org logs it, e.g.
+/// `- State "DONE"       from "TODO"       [2021-04-18 Sun 10:00]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StateChange<'a> {
+    pub line: usize,
+    pub parent: usize,
+    pub new_state: &'a str,
+    /// `None` if the headline had no TODO keyword before.
+    pub old_state: Option<&'a str>,
+    pub timestamp: NaiveDateTime,
+}
+
+/// Cheap pre-check so that lines which cannot be a state change skip
+/// [`STATE_CHANGE_RE`].
+#[inline]
+pub(crate) fn maybe_state_change(line: &str) -> bool {
+    line.trim_start().starts_with("- State ")
+}
+
+pub(crate) static STATE_CHANGE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?x)
+^\s*-\s+State\s+
+"([^"]*)"                                         # new state
+\s+from\s*
+(?:"([^"]*)")?                                    # old state
+\s*
+[\[<]
+([0-9]{4})-([0-9]{2})-([0-9]{2})                  # yyyy-mm-dd
+\s+[^\s\]>]+\s+                                   # day of week (can be localized)
+([0-9]{2}):([0-9]{2})                             # HH:MM
+[\]>]
+"#,
+    )
+    .expect("state change re")
+});
+
+impl<'a> TryFrom<&'a str> for StateChange<'a> {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        if !maybe_state_change(s) {
+            return Err(anyhow::anyhow!("not a state change: {s:?}"));
+        }
+        let Some(captures) = STATE_CHANGE_RE.captures(s) else {
+            return Err(anyhow::anyhow!("not a state change: {s:?}"));
+        };
+        let number = |i: usize| captures.get(i).unwrap().as_str().parse::<u32>();
+        let (hour, min) = (number(6)?, number(7)?);
+        let timestamp = NaiveDate::from_ymd_opt(number(3)? as i32, number(4)?, number(5)?)
+            .and_then(|date| date.and_hms_opt(hour, min, 0))
+            .ok_or_else(|| anyhow::anyhow!("invalid timestamp in state change: {s:?}"))?;
+
+        Ok(Self {
+            line: 0,
+            parent: 0,
+            new_state: captures.get(1).unwrap().as_str(),
+            old_state: captures
+                .get(2)
+                .map(|c| c.as_str())
+                .filter(|state| !state.is_empty()),
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::StateChange;
+
+    #[test]
+    fn parse_state_change() {
+        let change = StateChange::try_from(
+            r#"- State "DONE"       from "TODO"       [2021-04-18 Sun 10:00]"#,
+        )
+        .expect("parse state change");
+        assert_eq!(change.new_state, "DONE");
+        assert_eq!(change.old_state, Some("TODO"));
+        assert_eq!(
+            change.timestamp,
+            NaiveDateTime::parse_from_str("2021-04-18 10:00", "%Y-%m-%d %H:%M").unwrap()
+        );
+
+        let change = StateChange::try_from(
+            r#"  - State "TODO"       from              [2021-04-17 Sat 09:15]"#,
+        )
+        .expect("parse state change");
+        assert_eq!(change.new_state, "TODO");
+        assert_eq!(change.old_state, None);
+
+        assert!(StateChange::try_from("- a list item").is_err());
+        assert!(StateChange::try_from("CLOCK: [2021-04-18 Sun 10:00]").is_err());
+    }
+}
@@ -0,0 +1,919 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use serde::Serialize;
+
+use crate::{Clock, ClockConflict, Finding, OrgDocument};
+
+/// Sum of all closed clocks of `docs`. Start and end are resolved in their
+/// timezone, so a clock spanning a DST change counts the time actually spent.
+/// Running clocks are skipped, see [`total_duration_including_running`].
+pub fn total_duration(docs: &[OrgDocument]) -> Duration {
+    sum_durations(docs, None)
+}
+
+/// Like [`total_duration`] but running clocks count until `now`.
+pub fn total_duration_including_running(docs: &[OrgDocument], now: DateTime<Utc>) -> Duration {
+    sum_durations(docs, Some(now))
+}
+
+/// Formats `d` as `H:MM` after rounding it to the nearest multiple of
+/// `step_minutes`, e.g. quarter hours for billing. Halfway values round up. A
+/// step of 1 (or less) keeps minute precision.
+pub fn format_rounded(d: Duration, step_minutes: i64) -> String {
+    let rounded = round_minutes(d, step_minutes);
+    let sign = if rounded < 0 { "-" } else { "" };
+    format!("{sign}{}:{:0>2}", rounded.abs() / 60, rounded.abs() % 60)
+}
+
+/// Formats `d` like the total of an org clocktable, e.g. `*12:15*`. Like
+/// org's default `h:mm` duration format, large totals stay in hours instead
+/// of turning into days.
+pub fn format_org_total(d: Duration, step_minutes: i64) -> String {
+    format!("*{}*", format_rounded(d, step_minutes))
+}
+
+/// Like [`format_rounded`] but as decimal hours for spreadsheets, e.g. `1.5`
+/// or `0.25`. At most two decimals, trailing zeros are dropped.
+pub fn format_decimal_hours(d: Duration, step_minutes: i64) -> String {
+    let hours = format!("{:.2}", round_minutes(d, step_minutes) as f64 / 60.0);
+    hours
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Minutes of `d` rounded to the nearest multiple of `step_minutes`, halfway
+/// values away from zero.
+fn round_minutes(d: Duration, step_minutes: i64) -> i64 {
+    let minutes = d.num_minutes();
+    let step = step_minutes.max(1);
+    let rounded = (minutes.abs() + step / 2) / step * step;
+    if minutes < 0 {
+        -rounded
+    } else {
+        rounded
+    }
+}
+
+fn sum_durations(docs: &[OrgDocument], now: Option<DateTime<Utc>>) -> Duration {
+    docs.iter()
+        .flat_map(|doc| &doc.clocks)
+        .filter_map(|clock| {
+            let (start, end) = clock.utc_interval();
+            match (clock.is_running(), now) {
+                (false, _) => Some(end - start),
+                (true, Some(now)) => Some((now - start).max(Duration::zero())),
+                (true, None) => None,
+            }
+        })
+        .fold(Duration::zero(), |total, d| total + d)
+}
+
+/// Time of the closed clocks of `docs` per project, i.e. per top-level
+/// headline, see [`OrgDocument::top_level_ancestor`]. Projects with the same
+/// title in different files are combined. Sorted by time, longest first.
+pub fn time_by_project<'a>(docs: &[OrgDocument<'a>]) -> Vec<(&'a str, Duration)> {
+    project_totals(docs, |_| true)
+}
+
+fn project_totals<'a>(
+    docs: &[OrgDocument<'a>],
+    include: impl Fn(&Clock) -> bool,
+) -> Vec<(&'a str, Duration)> {
+    let mut totals: Vec<(&'a str, Duration)> = Vec::new();
+    for doc in docs {
+        for clock in doc.clocks.iter().filter(|clock| !clock.is_running()) {
+            if !include(clock) {
+                continue;
+            }
+            let project = doc.headlines[doc.top_level_ancestor(clock.parent)].title;
+            let (start, end) = clock.utc_interval();
+            match totals.iter_mut().find(|(title, _)| *title == project) {
+                Some((_, total)) => *total = *total + (end - start),
+                None => totals.push((project, end - start)),
+            }
+        }
+    }
+    totals.sort_by(|(title1, total1), (title2, total2)| {
+        total2.cmp(total1).then_with(|| title1.cmp(title2))
+    });
+    totals
+}
+
+/// How a tag is used, see [`tag_usage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagUsage<'a> {
+    pub tag: &'a str,
+    /// Headlines with the tag themselves, not inherited.
+    pub headlines: usize,
+    /// Time of the closed clocks with the tag, including inherited tags and
+    /// `#+FILETAGS:`.
+    pub total: Duration,
+}
+
+/// Every tag of `docs` with the number of headlines using it and the time
+/// clocked under it, most time first. Tags only used in `#+FILETAGS:` have no
+/// headlines.
+pub fn tag_usage<'a>(docs: &[OrgDocument<'a>]) -> Vec<TagUsage<'a>> {
+    let mut usage = BTreeMap::<&str, (usize, Duration)>::new();
+    for doc in docs {
+        for tag in &doc.file_tags {
+            usage.entry(tag).or_insert((0, Duration::zero()));
+        }
+        for headline in &doc.headlines {
+            let mut tags = headline.tags().collect::<Vec<_>>();
+            tags.sort_unstable();
+            tags.dedup();
+            for tag in tags {
+                usage.entry(tag).or_insert((0, Duration::zero())).0 += 1;
+            }
+        }
+        for clock in doc.clocks.iter().filter(|clock| !clock.is_running()) {
+            let (start, end) = clock.utc_interval();
+            for tag in doc.inherited_tags(clock.parent) {
+                let (_, total) = usage.entry(tag).or_insert((0, Duration::zero()));
+                *total = *total + (end - start);
+            }
+        }
+    }
+    let mut usage = usage
+        .into_iter()
+        .map(|(tag, (headlines, total))| TagUsage {
+            tag,
+            headlines,
+            total,
+        })
+        .collect::<Vec<_>>();
+    usage.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.tag.cmp(b.tag)));
+    usage
+}
+
+/// Time clocked on one task on one day, see [`invoice_rows`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvoiceRow {
+    pub date: NaiveDate,
+    /// Titles from the top-level headline down to the clocked one, joined
+    /// with ` / `.
+    pub task: String,
+    pub duration: Duration,
+}
+
+/// Time of the closed clocks of `docs` per day and headline, for invoicing.
+/// Only clocks of headlines with `tag`, including inherited tags, and clocks
+/// starting from `from` to `to` (inclusive) are counted if given. A clock
+/// belongs to the day it starts on. Sorted by date, then task.
+pub fn invoice_rows(
+    docs: &[OrgDocument],
+    tag: Option<&str>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Vec<InvoiceRow> {
+    let mut rows: Vec<InvoiceRow> = Vec::new();
+    for doc in docs {
+        for clock in doc.clocks.iter().filter(|clock| !clock.is_running()) {
+            let date = clock.start.date();
+            if tag.is_some_and(|tag| !doc.has_inherited_tag(clock.parent, tag))
+                || from.is_some_and(|from| date < from)
+                || to.is_some_and(|to| date > to)
+            {
+                continue;
+            }
+            let task = doc.title_path(clock.parent).join(" / ");
+            let (start, end) = clock.utc_interval();
+            match rows
+                .iter_mut()
+                .find(|row| row.date == date && row.task == task)
+            {
+                Some(row) => row.duration = row.duration + (end - start),
+                None => rows.push(InvoiceRow {
+                    date,
+                    task,
+                    duration: end - start,
+                }),
+            }
+        }
+    }
+    rows.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.task.cmp(&b.task)));
+    rows
+}
+
+/// The daily window in which clocks are expected, see [`uncovered_work_time`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub days: Vec<Weekday>,
+}
+
+impl Default for WorkingHours {
+    /// 09:00 to 17:00 on weekdays.
+    fn default() -> Self {
+        Self {
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+        }
+    }
+}
+
+/// The parts of the working hours on the days from `from` to `to`
+/// (inclusive) that no closed clock of `docs` covers. Overlapping clocks are
+/// merged first, clocks are compared in local time. Sorted by start.
+pub fn uncovered_work_time(
+    docs: &[OrgDocument],
+    hours: &WorkingHours,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut clocks = docs
+        .iter()
+        .flat_map(|doc| &doc.clocks)
+        .filter_map(|clock| Some((clock.start, clock.end?)))
+        .filter(|(start, end)| start < end)
+        .collect::<Vec<_>>();
+    clocks.sort();
+    let mut covered: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+    for (start, end) in clocks {
+        match covered.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => covered.push((start, end)),
+        }
+    }
+
+    let mut uncovered = Vec::new();
+    for date in from.iter_days().take_while(|date| *date <= to) {
+        if !hours.days.contains(&date.weekday()) {
+            continue;
+        }
+        let (window_start, window_end) = (date.and_time(hours.start), date.and_time(hours.end));
+        let mut cursor = window_start;
+        for (start, end) in covered
+            .iter()
+            .filter(|(start, end)| *end > window_start && *start < window_end)
+        {
+            if *start > cursor {
+                uncovered.push((cursor, *start));
+            }
+            cursor = cursor.max(*end);
+        }
+        if cursor < window_end {
+            uncovered.push((cursor, window_end));
+        }
+    }
+    uncovered
+}
+
+/// Effort estimate of a headline compared to the time clocked on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffortVariance<'a> {
+    pub title: &'a str,
+    /// The `:Effort:` property.
+    pub effort: Duration,
+    /// Closed clocks of the headline and its descendants.
+    pub actual: Duration,
+}
+
+impl<'a> EffortVariance<'a> {
+    /// How much longer than estimated the headline took, negative if it was
+    /// overestimated.
+    pub fn variance(&self) -> Duration {
+        self.actual - self.effort
+    }
+}
+
+/// Effort against clocked time for all headlines of `docs` with an
+/// `:Effort:` property, sorted by absolute variance, largest first.
+pub fn effort_variances<'a>(docs: &[OrgDocument<'a>]) -> Vec<EffortVariance<'a>> {
+    let mut variances = Vec::new();
+    for doc in docs {
+        let durations = doc.subtree_durations();
+        for (headline, actual) in doc.headlines.iter().zip(durations) {
+            if let Some(effort) = headline.effort() {
+                variances.push(EffortVariance {
+                    title: headline.title,
+                    effort,
+                    actual,
+                });
+            }
+        }
+    }
+    variances.sort_by(|a, b| {
+        let abs = |v: &EffortVariance| v.variance().num_minutes().abs();
+        abs(b).cmp(&abs(a)).then_with(|| a.title.cmp(b.title))
+    });
+    variances
+}
+
+/// Aggregated numbers about a set of org documents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub files: usize,
+    pub headlines: usize,
+    pub clocks: usize,
+    pub running_clocks: usize,
+    /// Sum of all closed clocks in minutes.
+    pub total_minutes: i64,
+    /// `total_minutes` as H:MM, possibly rounded with [`format_rounded`].
+    pub total: String,
+    /// Start of the earliest clock, formatted as `%Y-%m-%d %H:%M`.
+    pub first_clock: Option<String>,
+    /// End (or start if running) of the latest clock, formatted as `%Y-%m-%d %H:%M`.
+    pub last_clock: Option<String>,
+}
+
+impl Stats {
+    pub fn collect(docs: &[OrgDocument]) -> Self {
+        let clocks = docs.iter().flat_map(|doc| &doc.clocks);
+        let total_minutes = total_duration(docs).num_minutes();
+        let first_clock = clocks.clone().map(|clock| clock.start).min();
+        let last_clock = clocks
+            .clone()
+            .map(|clock| clock.end.unwrap_or(clock.start))
+            .max();
+        let format = |d: chrono::NaiveDateTime| d.format("%Y-%m-%d %H:%M").to_string();
+
+        Self {
+            files: docs.len(),
+            headlines: docs.iter().map(|doc| doc.headlines.len()).sum(),
+            clocks: clocks.clone().count(),
+            running_clocks: clocks.filter(|clock| clock.is_running()).count(),
+            total_minutes,
+            total: format_rounded(Duration::minutes(total_minutes), 1),
+            first_clock: first_clock.map(format),
+            last_clock: last_clock.map(format),
+        }
+    }
+}
+
+/// Total time of the closed clocks per month of their start, `(year, month)`,
+/// sorted chronologically. Months without clocks are left out.
+pub fn time_by_month(docs: &[OrgDocument]) -> Vec<((i32, u32), Duration)> {
+    let mut months = BTreeMap::<(i32, u32), Duration>::new();
+    for clock in docs.iter().flat_map(|doc| &doc.clocks) {
+        if clock.is_running() {
+            continue;
+        }
+        let (start, end) = clock.utc_interval();
+        let month = (clock.start.year(), clock.start.month());
+        let total = months.entry(month).or_insert_with(Duration::zero);
+        *total = *total + (end - start);
+    }
+    months.into_iter().collect()
+}
+
+/// `months` of [`time_by_month`] as an org table with a `*Total time*` row
+/// like org's clocktables, durations rounded with [`format_rounded`].
+pub fn monthly_org_table(months: &[((i32, u32), Duration)], step_minutes: i64) -> String {
+    let total = months
+        .iter()
+        .fold(Duration::zero(), |total, (_, d)| total + *d);
+    let rows = months
+        .iter()
+        .map(|((year, month), d)| {
+            (
+                format!("{year}-{month:02}"),
+                format_rounded(*d, step_minutes),
+            )
+        })
+        .collect::<Vec<_>>();
+    let total = (
+        "*Total time*".to_string(),
+        format_org_total(total, step_minutes),
+    );
+    let width = |len: fn(&(String, String)) -> usize| {
+        rows.iter()
+            .chain([&total])
+            .map(len)
+            .max()
+            .unwrap_or(0)
+            .max("Month".len())
+    };
+    let month_width = width(|(month, _)| month.len());
+    let hours_width = width(|(_, hours)| hours.len());
+    let row =
+        |month: &str, hours: &str| format!("| {month:<month_width$} | {hours:>hours_width$} |\n");
+    let rule = format!(
+        "|{}+{}|\n",
+        "-".repeat(month_width + 2),
+        "-".repeat(hours_width + 2)
+    );
+    let mut out = row("Month", "Hours");
+    out.push_str(&rule);
+    for (month, hours) in &rows {
+        out.push_str(&row(month, hours));
+    }
+    out.push_str(&rule);
+    out.push_str(&row(&total.0, &total.1));
+    out
+}
+
+/// A clock of [`WeeklySummary::long_sessions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongSession {
+    pub start: NaiveDateTime,
+    pub duration: Duration,
+    /// See [`InvoiceRow::task`].
+    pub task: String,
+}
+
+/// The clocks of one week, or any range of days, for a review, see
+/// [`WeeklySummary::markdown`]. Closed clocks count towards the day they
+/// start on.
+#[derive(Debug, Clone)]
+pub struct WeeklySummary<'a> {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total: Duration,
+    /// Like [`time_by_project`].
+    pub projects: Vec<(&'a str, Duration)>,
+    /// Every day from `from` to `to`, including days without clocks.
+    pub days: Vec<(NaiveDate, Duration)>,
+    /// Clocks of at least the `long_session` duration, by start.
+    pub long_sessions: Vec<LongSession>,
+    /// Clock conflicts with at least one clock in the range, running clocks
+    /// end at the `now` passed to [`WeeklySummary::collect`].
+    pub conflicts: Vec<Finding>,
+}
+
+impl<'a> WeeklySummary<'a> {
+    pub fn collect(
+        docs: &[OrgDocument<'a>],
+        from: NaiveDate,
+        to: NaiveDate,
+        long_session: Duration,
+        exclude_tags: &[String],
+        now: DateTime<Utc>,
+    ) -> Self {
+        let in_range = |clock: &Clock| (from..=to).contains(&clock.start.date());
+        let mut days = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .map(|date| (date, Duration::zero()))
+            .collect::<Vec<_>>();
+        let mut long_sessions = Vec::new();
+        for doc in docs {
+            for clock in doc.clocks.iter().filter(|clock| !clock.is_running()) {
+                if !in_range(clock) {
+                    continue;
+                }
+                let (start, end) = clock.utc_interval();
+                let duration = end - start;
+                let day = (clock.start.date() - from).num_days() as usize;
+                days[day].1 = days[day].1 + duration;
+                if duration >= long_session {
+                    long_sessions.push(LongSession {
+                        start: clock.start,
+                        duration,
+                        task: doc.title_path(clock.parent).join(" / "),
+                    });
+                }
+            }
+        }
+        long_sessions.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.task.cmp(&b.task)));
+        let conflicts = ClockConflict::find_conflicts_excluding(docs, exclude_tags, now)
+            .filter(|conflict| {
+                let (clock1, clock2) = conflict.clocks();
+                in_range(clock1) || in_range(clock2)
+            })
+            .map(|conflict| conflict.finding())
+            .collect();
+        Self {
+            from,
+            to,
+            total: days
+                .iter()
+                .fold(Duration::zero(), |total, (_, d)| total + *d),
+            projects: project_totals(docs, in_range),
+            days,
+            long_sessions,
+            conflicts,
+        }
+    }
+
+    /// The summary as Markdown, ready to paste into an email. Durations are
+    /// rounded with [`format_rounded`].
+    pub fn markdown(&self, step_minutes: i64) -> String {
+        let d = |d: Duration| format_rounded(d, step_minutes);
+        let mut out = format!("# Week {} to {}\n\n", self.from, self.to);
+        out.push_str(&format!("Total: {}\n", d(self.total)));
+        out.push_str("\n## Projects\n\n");
+        for (title, total) in &self.projects {
+            out.push_str(&format!("- {} {title}\n", d(*total)));
+        }
+        if self.projects.is_empty() {
+            out.push_str("None\n");
+        }
+        out.push_str("\n## Days\n\n");
+        for (date, total) in &self.days {
+            out.push_str(&format!("- {} {}\n", date.format("%Y-%m-%d %a"), d(*total)));
+        }
+        out.push_str("\n## Long sessions\n\n");
+        for session in &self.long_sessions {
+            out.push_str(&format!(
+                "- {} {} {}\n",
+                session.start.format("%Y-%m-%d %a %H:%M"),
+                d(session.duration),
+                session.task
+            ));
+        }
+        if self.long_sessions.is_empty() {
+            out.push_str("None\n");
+        }
+        out.push_str("\n## Conflicts\n\n");
+        for conflict in &self.conflicts {
+            out.push_str(&format!("- {conflict}\n"));
+        }
+        if self.conflicts.is_empty() {
+            out.push_str("None\n");
+        }
+        out
+    }
+}
+
+/// Stats and findings combined, used for `--format summary-json`.
+#[derive(Debug, Serialize)]
+pub struct Summary<'a> {
+    pub stats: Stats,
+    pub findings: &'a [Finding],
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
+
+    use super::{
+        effort_variances, format_decimal_hours, format_org_total, format_rounded, invoice_rows,
+        monthly_org_table, tag_usage, time_by_month, time_by_project, total_duration,
+        total_duration_including_running, uncovered_work_time, Stats, Summary, WeeklySummary,
+        WorkingHours,
+    };
+    use crate::{lint_document, LintOptions, OrgDocument};
+
+    #[test]
+    fn total_duration_across_documents() {
+        let foo = "
+* foo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:30] =>  1:30
+";
+        let bar = "
+* bar
+CLOCK: [2022-12-14 Wed 10:00]--[2022-12-14 Wed 10:45] =>  0:45
+CLOCK: [2022-12-15 Thu 08:00]
+";
+        let docs = [
+            OrgDocument::parse("foo.org", foo),
+            OrgDocument::parse("bar.org", bar),
+        ];
+        assert_eq!(total_duration(&docs), Duration::minutes(135));
+
+        // 08:00 CET is 07:00 UTC
+        let now = Utc.with_ymd_and_hms(2022, 12, 15, 8, 0, 0).unwrap();
+        assert_eq!(
+            total_duration_including_running(&docs, now),
+            Duration::minutes(195)
+        );
+    }
+
+    #[test]
+    fn time_by_project_rolls_up_subtasks() {
+        let work = "
+* website
+** design
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+*** logo
+CLOCK: [2022-12-12 Mon 12:00]--[2022-12-12 Mon 12:30] =>  0:30
+* admin
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:15] =>  0:15
+CLOCK: [2022-12-12 Mon 13:00]
+";
+        let more = "
+** loose task
+CLOCK: [2022-12-13 Tue 09:00]--[2022-12-13 Tue 09:20] =>  0:20
+* website
+** deploy
+CLOCK: [2022-12-13 Tue 10:00]--[2022-12-13 Tue 10:45] =>  0:45
+";
+        let docs = [
+            OrgDocument::parse("work.org", work),
+            OrgDocument::parse("more.org", more),
+        ];
+        assert_eq!(
+            time_by_project(&docs),
+            vec![
+                ("website", Duration::minutes(135)),
+                ("loose task", Duration::minutes(20)),
+                ("admin", Duration::minutes(15)),
+            ]
+        );
+    }
+
+    #[test]
+    fn effort_variance() {
+        let org_string = "
+* estimated
+:PROPERTIES:
+:Effort:   2:00
+:END:
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+** subtask
+CLOCK: [2022-12-13 Tue 10:00]--[2022-12-13 Tue 12:00] =>  2:00
+* quick
+:PROPERTIES:
+:Effort:   1:00
+:END:
+CLOCK: [2022-12-14 Wed 10:00]--[2022-12-14 Wed 10:30] =>  0:30
+* no estimate
+CLOCK: [2022-12-15 Thu 10:00]--[2022-12-15 Thu 13:00] =>  3:00
+";
+        let docs = [OrgDocument::parse("test.org", org_string)];
+        let variances = effort_variances(&docs);
+        assert_eq!(variances.len(), 2);
+        assert_eq!(variances[0].title, "estimated");
+        assert_eq!(variances[0].effort, Duration::hours(2));
+        assert_eq!(variances[0].actual, Duration::hours(3));
+        assert_eq!(variances[0].variance(), Duration::hours(1));
+        assert_eq!(variances[1].title, "quick");
+        assert_eq!(variances[1].variance(), Duration::minutes(-30));
+    }
+
+    #[test]
+    fn uncovered_working_hours() {
+        let org_string = "
+* work
+CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 11:00] =>  2:00
+CLOCK: [2023-01-10 Tue 10:30]--[2023-01-10 Tue 12:00] =>  1:30
+CLOCK: [2023-01-10 Tue 13:00]--[2023-01-10 Tue 18:00] =>  5:00
+CLOCK: [2023-01-11 Wed 08:00]--[2023-01-11 Wed 17:30] =>  9:30
+CLOCK: [2023-01-12 Thu 09:00]
+";
+        let docs = [OrgDocument::parse("test.org", org_string)];
+        let date = |d| NaiveDate::from_ymd_opt(2023, 1, d).unwrap();
+        let at = |d, h| date(d).and_hms_opt(h, 0, 0).unwrap();
+        let hours = WorkingHours::default();
+        assert_eq!(
+            uncovered_work_time(&docs, &hours, date(10), date(15)),
+            vec![
+                (at(10, 12), at(10, 13)),
+                // the running clock doesn't count, the weekend is not expected
+                (at(12, 9), at(12, 17)),
+                (at(13, 9), at(13, 17)),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_to_quarter_hours() {
+        assert_eq!(format_rounded(Duration::minutes(67), 15), "1:00");
+        assert_eq!(format_rounded(Duration::minutes(68), 15), "1:15");
+        assert_eq!(format_rounded(Duration::minutes(67), 1), "1:07");
+        assert_eq!(format_rounded(Duration::minutes(-68), 15), "-1:15");
+    }
+
+    #[test]
+    fn weekly_summary() {
+        let work = "
+* client
+** design
+CLOCK: [2023-01-09 Mon 09:00]--[2023-01-09 Mon 14:00] =>  5:00
+CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:30] =>  1:30
+** review
+CLOCK: [2023-01-10 Tue 10:00]--[2023-01-10 Tue 11:00] =>  1:00
+* last week
+CLOCK: [2023-01-06 Fri 09:00]--[2023-01-06 Fri 17:00] =>  8:00
+";
+        let home = "
+* chores
+CLOCK: [2023-01-14 Sat 10:00]--[2023-01-14 Sat 10:45] =>  0:45
+";
+        let docs = [
+            OrgDocument::parse("work.org", work),
+            OrgDocument::parse("home.org", home),
+        ];
+        let date = |d| NaiveDate::from_ymd_opt(2023, 1, d).unwrap();
+        let summary = WeeklySummary::collect(
+            &docs,
+            date(9),
+            date(15),
+            Duration::hours(4),
+            &[],
+            Utc::now(),
+        );
+        assert_eq!(summary.total, Duration::minutes(8 * 60 + 15));
+        assert_eq!(
+            summary.projects,
+            vec![
+                ("client", Duration::hours(7) + Duration::minutes(30)),
+                ("chores", Duration::minutes(45)),
+            ]
+        );
+        assert_eq!(summary.days.len(), 7);
+        assert_eq!(summary.long_sessions.len(), 1);
+        assert_eq!(summary.conflicts.len(), 1);
+
+        let expected = "# Week 2023-01-09 to 2023-01-15
+
+Total: 8:15
+
+## Projects
+
+- 7:30 client
+- 0:45 chores
+
+## Days
+
+- 2023-01-09 Mon 5:00
+- 2023-01-10 Tue 2:30
+- 2023-01-11 Wed 0:00
+- 2023-01-12 Thu 0:00
+- 2023-01-13 Fri 0:00
+- 2023-01-14 Sat 0:45
+- 2023-01-15 Sun 0:00
+
+## Long sessions
+
+- 2023-01-09 Mon 09:00 5:00 client / design
+
+## Conflicts
+
+- [work.org:5] OVERLAPPING TIME [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:30] =>  1:30 \
+\"design\" with [2023-01-10 Tue 10:00]--[2023-01-10 Tue 11:00] =>  1:00 \"review\"
+";
+        assert_eq!(summary.markdown(1), expected);
+
+        let quiet = WeeklySummary::collect(
+            &docs,
+            date(2),
+            date(8),
+            Duration::hours(10),
+            &[],
+            Utc::now(),
+        );
+        assert!(quiet.markdown(1).contains("## Long sessions\n\nNone\n"));
+        assert!(quiet.markdown(1).contains("## Conflicts\n\nNone\n"));
+    }
+
+    #[test]
+    fn tags_with_counts_and_time() {
+        let work = "#+FILETAGS: :work:
+* client :billable:
+** design :ui:
+CLOCK: [2023-01-09 Mon 09:00]--[2023-01-09 Mon 11:00] =>  2:00
+** review :ui:ui:
+CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 09:30] =>  0:30
+* admin
+CLOCK: [2023-01-10 Tue 10:00]--[2023-01-10 Tue 10:15] =>  0:15
+";
+        let home = "
+* chores :billable:
+CLOCK: [2023-01-14 Sat 10:00]--[2023-01-14 Sat 11:00] =>  1:00
+* someday :ui:
+";
+        let docs = [
+            OrgDocument::parse("work.org", work),
+            OrgDocument::parse("home.org", home),
+        ];
+        let usage = tag_usage(&docs)
+            .into_iter()
+            .map(|u| (u.tag, u.headlines, u.total.num_minutes()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            usage,
+            vec![("billable", 2, 210), ("work", 0, 165), ("ui", 3, 150),]
+        );
+    }
+
+    #[test]
+    fn monthly_table() {
+        let org = "
+* project
+CLOCK: [2023-01-30 Mon 09:00]--[2023-01-30 Mon 17:00] =>  8:00
+CLOCK: [2023-01-31 Tue 22:00]--[2023-02-01 Wed 01:30] =>  3:30
+CLOCK: [2023-02-14 Tue 10:00]--[2023-02-14 Tue 10:45] =>  0:45
+CLOCK: [2023-03-01 Wed 10:00]
+";
+        let docs = [OrgDocument::parse("test.org", org)];
+        let months = time_by_month(&docs);
+        assert_eq!(
+            months,
+            vec![
+                ((2023, 1), Duration::minutes(11 * 60 + 30)),
+                ((2023, 2), Duration::minutes(45)),
+            ]
+        );
+        let expected = "\
+| Month        |   Hours |
+|--------------+---------|
+| 2023-01      |   11:30 |
+| 2023-02      |    0:45 |
+|--------------+---------|
+| *Total time* | *12:15* |
+";
+        assert_eq!(monthly_org_table(&months, 1), expected);
+    }
+
+    #[test]
+    fn org_total() {
+        let d = Duration::hours(250);
+        assert_eq!(format_rounded(d, 1), "250:00");
+        assert_eq!(format_org_total(d, 1), "*250:00*");
+        assert_eq!(format_org_total(Duration::minutes(68), 15), "*1:15*");
+    }
+
+    #[test]
+    fn decimal_hours() {
+        assert_eq!(format_decimal_hours(Duration::minutes(90), 1), "1.5");
+        assert_eq!(format_decimal_hours(Duration::minutes(68), 15), "1.25");
+        assert_eq!(format_decimal_hours(Duration::minutes(120), 15), "2");
+        assert_eq!(format_decimal_hours(Duration::minutes(20), 1), "0.33");
+    }
+
+    #[test]
+    fn invoice_rows_per_day_and_task() {
+        let org_string = "
+* client :billable:
+** design
+CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:10] =>  1:10
+CLOCK: [2023-01-10 Tue 14:00]--[2023-01-10 Tue 14:20] =>  0:20
+CLOCK: [2023-01-11 Wed 09:00]--[2023-01-11 Wed 09:50] =>  0:50
+** review
+CLOCK: [2023-01-11 Wed 11:00]--[2023-01-11 Wed 11:07] =>  0:07
+CLOCK: [2023-01-12 Thu 11:00]--[2023-01-12 Thu 12:00] =>  1:00
+* internal
+CLOCK: [2023-01-10 Tue 16:00]--[2023-01-10 Tue 17:00] =>  1:00
+";
+        let docs = [OrgDocument::parse("test.org", org_string)];
+        let date = |d| NaiveDate::from_ymd_opt(2023, 1, d).unwrap();
+        let rows = invoice_rows(&docs, Some("billable"), None, Some(date(11)))
+            .into_iter()
+            .map(|row| {
+                (
+                    row.date.to_string(),
+                    row.task,
+                    format_decimal_hours(row.duration, 15),
+                )
+            })
+            .collect::<Vec<_>>();
+        let row = |date: &str, task: &str, hours: &str| {
+            (date.to_string(), task.to_string(), hours.to_string())
+        };
+        assert_eq!(
+            rows,
+            vec![
+                row("2023-01-10", "client / design", "1.5"),
+                row("2023-01-11", "client / design", "0.75"),
+                row("2023-01-11", "client / review", "0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_json() {
+        let foo = "
+* foo
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:30] =>  1:30
+** bar
+CLOCK: [2022-12-13 Tue 09:00]--[2022-12-13 Tue 09:00] =>  0:00
+";
+        let baz = "
+* baz
+CLOCK: [2022-12-14 Wed 10:00]--[2022-12-14 Wed 10:45] =>  0:45
+CLOCK: [2022-12-15 Thu 08:00]
+";
+        let docs = [
+            OrgDocument::parse("foo.org", foo),
+            OrgDocument::parse("baz.org", baz),
+        ];
+        let findings = docs
+            .iter()
+            .flat_map(|doc| lint_document(doc, &LintOptions::default()))
+            .collect::<Vec<_>>();
+        let summary = Summary {
+            stats: Stats::collect(&docs),
+            findings: &findings,
+        };
+        let json = serde_json::to_value(&summary).unwrap();
+
+        let stats = &json["stats"];
+        assert_eq!(stats["files"], 2);
+        assert_eq!(stats["headlines"], 3);
+        assert_eq!(stats["clocks"], 4);
+        assert_eq!(stats["running_clocks"], 1);
+        assert_eq!(stats["total_minutes"], 135);
+        assert_eq!(stats["total"], "2:15");
+        assert_eq!(stats["first_clock"], "2022-12-12 10:00");
+        assert_eq!(stats["last_clock"], "2022-12-15 08:00");
+
+        let findings = json["findings"].as_array().unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0]["kind"], "zero-duration");
+        assert_eq!(findings[0]["file"], "foo.org");
+        assert_eq!(findings[0]["line"], 5);
+    }
+}
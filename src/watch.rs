@@ -0,0 +1,314 @@
+use anyhow::Result;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use crate::{
+    lint_document, lint_documents, ClockConflict, Finding, LintOptions, OrgFile, ParseOptions,
+};
+
+/// Finds the files to watch, e.g. by searching the org directory.
+type Discover<'a> = Box<dyn Fn() -> Result<Vec<PathBuf>> + 'a>;
+
+/// Keeps the linted org files in memory so that only changed files need to be
+/// re-read and re-checked. Clock conflicts and other checks comparing files are
+/// recomputed over all files.
+pub struct WatchSession<'a> {
+    org_files: BTreeMap<PathBuf, OrgFile>,
+    findings: BTreeMap<PathBuf, Vec<Finding>>,
+    parse_options: ParseOptions,
+    lint_options: LintOptions,
+    report_conflicts: bool,
+    conflict_exclude_tags: Vec<String>,
+    files: Vec<PathBuf>,
+    discover: Option<Discover<'a>>,
+}
+
+impl<'a> WatchSession<'a> {
+    pub fn new(
+        files: &[PathBuf],
+        parse_options: ParseOptions,
+        lint_options: LintOptions,
+        report_conflicts: bool,
+    ) -> Result<Self> {
+        let mut session = Self {
+            org_files: BTreeMap::new(),
+            findings: BTreeMap::new(),
+            parse_options,
+            lint_options,
+            report_conflicts,
+            conflict_exclude_tags: Vec::new(),
+            files: files.to_vec(),
+            discover: None,
+        };
+        for file in files {
+            session.reload(file)?;
+        }
+        Ok(session)
+    }
+
+    /// Ignore clocks below headlines with one of `tags` when finding conflicts.
+    pub fn exclude_conflict_tags(mut self, tags: Vec<String>) -> Self {
+        self.conflict_exclude_tags = tags;
+        self
+    }
+
+    /// Find the files to watch with `discover` when an event concerns a file
+    /// that is not watched, e.g. one created after the start, or one that
+    /// can't be read anymore. By default these are the files the session was
+    /// created with that exist.
+    pub fn discover_with(mut self, discover: impl Fn() -> Result<Vec<PathBuf>> + 'a) -> Self {
+        self.discover = Some(Box::new(discover));
+        self
+    }
+
+    fn reload(&mut self, file: &Path) -> Result<()> {
+        let org_file = OrgFile::from_file(file)?;
+        let doc = org_file.document_with_options(&self.parse_options);
+        let findings = lint_document(&doc, &self.lint_options);
+        let key = key(file);
+        self.findings.insert(key.clone(), findings);
+        self.org_files.insert(key, org_file);
+        Ok(())
+    }
+
+    fn forget(&mut self, file: &Path) {
+        self.org_files.remove(file);
+        self.findings.remove(file);
+    }
+
+    /// Re-reads and re-checks the watched files that were modified according
+    /// to `events`. Events for other paths, like a new file in a watched
+    /// directory, start a new search for files to watch. Files that can't be
+    /// read, e.g. because they were deleted, are logged and no longer
+    /// watched. Returns the re-checked and the no longer watched files.
+    pub fn handle_events(&mut self, events: &[Event]) -> Result<Vec<PathBuf>> {
+        let paths = events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event.kind,
+                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                )
+            })
+            .flat_map(|event| &event.paths)
+            .map(|path| key(path))
+            .collect::<BTreeSet<_>>();
+        let mut changed = paths
+            .iter()
+            .filter(|path| self.org_files.contains_key(*path))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let mut gone = BTreeSet::new();
+
+        if changed.len() < paths.len() {
+            let discovered = match &self.discover {
+                Some(discover) => discover(),
+                None => Ok(self.files.iter().filter(|f| f.exists()).cloned().collect()),
+            };
+            match discovered {
+                Ok(files) => {
+                    let files = files.iter().map(|file| key(file)).collect::<BTreeSet<_>>();
+                    gone.extend(
+                        self.org_files
+                            .keys()
+                            .filter(|file| !files.contains(*file))
+                            .cloned(),
+                    );
+                    changed.extend(
+                        files
+                            .into_iter()
+                            .filter(|file| !self.org_files.contains_key(file)),
+                    );
+                }
+                Err(err) => warn!("cannot find the files to watch: {err:#}"),
+            }
+        }
+
+        for file in &changed {
+            debug!("re-checking {file:?}");
+            if let Err(err) = self.reload(file) {
+                warn!("cannot re-check {file:?}: {err:#}");
+                gone.insert(file.clone());
+            }
+        }
+        for file in &gone {
+            self.forget(file);
+        }
+        changed.extend(gone);
+        Ok(changed.into_iter().collect())
+    }
+
+    /// The findings of all files plus clock conflicts between them.
+    pub fn findings(&self) -> Vec<Finding> {
+        let mut findings = self
+            .findings
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        let docs = self
+            .org_files
+            .values()
+            .map(|org_file| org_file.document_with_options(&self.parse_options))
+            .collect::<Vec<_>>();
+        findings.extend(lint_documents(&docs, &self.lint_options));
+        if self.report_conflicts {
+            let conflicts = ClockConflict::find_conflicts_excluding(
+                &docs,
+                &self.conflict_exclude_tags,
+                self.lint_options.now_utc(),
+            );
+            let mut conflicts = conflicts.map(|c| c.finding()).collect::<Vec<_>>();
+            self.lint_options.classify(&mut conflicts);
+            findings.extend(conflicts);
+        }
+        findings
+    }
+}
+
+/// The key of `file` in a [`WatchSession`], the canonical path if it exists.
+fn key(file: &Path) -> PathBuf {
+    file.canonicalize().unwrap_or_else(|_| file.to_path_buf())
+}
+
+/// Watches `paths` and calls `on_change` with the session whenever watched
+/// org files were modified, added or removed. Events arriving within
+/// `debounce` of each other are handled together. Runs until the watcher
+/// fails.
+pub fn watch(
+    paths: &[PathBuf],
+    recursive: bool,
+    mut session: WatchSession<'_>,
+    debounce: Duration,
+    mut on_change: impl FnMut(&WatchSession),
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    for path in paths {
+        watcher.watch(path, mode)?;
+    }
+
+    on_change(&session);
+    loop {
+        let mut events = vec![rx.recv()??];
+        while let Ok(event) = rx.recv_timeout(debounce) {
+            events.push(event?);
+        }
+        if !session.handle_events(&events)?.is_empty() {
+            on_change(&session);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use notify::{
+        event::{CreateKind, ModifyKind},
+        Event, EventKind,
+    };
+
+    use super::WatchSession;
+    use crate::{FindingKind, LintOptions, ParseOptions};
+
+    #[test]
+    fn modification_triggers_recheck() {
+        let dir = tempfile::tempdir().unwrap();
+        let foo = dir.path().join("foo.org");
+        let bar = dir.path().join("bar.org");
+        std::fs::write(
+            &foo,
+            "* foo\nCLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:30] =>  0:30\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &bar,
+            "* bar\nCLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  0:30\n",
+        )
+        .unwrap();
+
+        let mut session = WatchSession::new(
+            &[foo.clone(), bar.clone()],
+            ParseOptions::default(),
+            LintOptions::default(),
+            true,
+        )
+        .unwrap();
+        assert!(session.findings().is_empty());
+
+        std::fs::write(
+            &foo,
+            "* foo\nCLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:15] =>  0:30\n",
+        )
+        .unwrap();
+        let event = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(foo.clone());
+        let rechecked = session.handle_events(&[event]).unwrap();
+        assert_eq!(rechecked, vec![foo.canonicalize().unwrap()]);
+
+        let kinds = session
+            .findings()
+            .into_iter()
+            .map(|f| f.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds[0], FindingKind::DurationMismatch);
+        assert!(kinds.contains(&FindingKind::ClockConflict));
+
+        // events for files that are not watched are ignored
+        let other = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(dir.path().join("x"));
+        assert!(session.handle_events(&[other]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn removed_and_created_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let org_files = |dir: &Path| -> anyhow::Result<Vec<PathBuf>> {
+            let mut files = std::fs::read_dir(dir)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            files.sort();
+            Ok(files)
+        };
+        let foo = dir.path().join("foo.org");
+        std::fs::write(&foo, "* foo\n").unwrap();
+        let mut session = WatchSession::new(
+            &org_files(dir.path()).unwrap(),
+            ParseOptions::default(),
+            LintOptions::default(),
+            false,
+        )
+        .unwrap()
+        .discover_with(|| org_files(dir.path()));
+        let foo = foo.canonicalize().unwrap();
+
+        // a file deleted while saving is dropped instead of ending the session
+        std::fs::remove_file(&foo).unwrap();
+        let event = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(foo.clone());
+        assert_eq!(session.handle_events(&[event]).unwrap(), vec![foo.clone()]);
+        assert!(session.org_files.is_empty());
+
+        // new files are found when their directory changes
+        let bar = dir.path().join("bar.org");
+        std::fs::write(
+            &bar,
+            "* bar\nCLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 11:30] =>  1:00\n",
+        )
+        .unwrap();
+        let event = Event::new(EventKind::Create(CreateKind::Any)).add_path(bar.clone());
+        let bar = bar.canonicalize().unwrap();
+        assert_eq!(session.handle_events(&[event]).unwrap(), vec![bar.clone()]);
+        let findings = session.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, bar);
+    }
+}
@@ -0,0 +1,446 @@
+use std::process::Command;
+
+const ORG: &str = "* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  0:30
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:00] =>  0:00
+";
+
+fn org_linter(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_org-linter"))
+        .args(args)
+        .env("RUST_LOG", "off")
+        .output()
+        .expect("run org-linter")
+}
+
+#[test]
+fn exit_summary_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let dirty = dir.path().join("dirty.org");
+    let clean = dir.path().join("clean.org");
+    std::fs::write(&dirty, ORG).unwrap();
+    std::fs::write(&clean, "* nothing clocked\n").unwrap();
+
+    let output = org_linter(&[
+        "--format",
+        "json",
+        "--org-file",
+        dirty.to_str().unwrap(),
+        "--org-file",
+        clean.to_str().unwrap(),
+    ]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr.lines().last(),
+        Some("org-linter: 2 findings across 2 files (exit 1)")
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = org_linter(&["--org-file", clean.to_str().unwrap()]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr.lines().last(),
+        Some("org-linter: 0 findings across 1 file (exit 0)")
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = org_linter(&["--quiet", "--org-file", dirty.to_str().unwrap()]);
+    assert!(output.stderr.is_empty());
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn configured_severity_changes_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let dirty = dir.path().join("dirty.org");
+    std::fs::write(&dirty, ORG).unwrap();
+    let args = [
+        "--format",
+        "json",
+        "--fail-on-severity",
+        "warning",
+        "--org-file",
+        dirty.to_str().unwrap(),
+    ];
+
+    let output = org_linter(&args);
+    assert_eq!(output.status.code(), Some(1));
+    let findings: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(findings[0]["kind"], "duration-mismatch");
+    assert_eq!(findings[0]["severity"], "error");
+
+    let config = dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        "[severity]\nduration-mismatch = \"info\"\nzero-duration = \"info\"\n",
+    )
+    .unwrap();
+    let output = org_linter(&[&args[..], &["--config", config.to_str().unwrap()]].concat());
+    assert_eq!(output.status.code(), Some(0));
+    let findings: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(findings[0]["severity"], "info");
+    assert_eq!(findings[1]["severity"], "info");
+}
+
+#[test]
+fn count_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let dirty = dir.path().join("dirty.org");
+    std::fs::write(&dirty, ORG).unwrap();
+    let file = dirty.to_str().unwrap();
+
+    let output = org_linter(&["--format", "json", "--org-file", file]);
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(findings.len(), 2);
+
+    let output = org_linter(&["--count-only", "--org-file", file]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2\n");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = org_linter(&["--count-only", "--format", "json", "--org-file", file]);
+    let count: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(count, serde_json::json!({"count": findings.len()}));
+}
+
+#[test]
+fn ignore_file_excludes_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("archive")).unwrap();
+    std::fs::write(dir.path().join("work.org"), ORG).unwrap();
+    std::fs::write(dir.path().join("archive").join("2021.org"), ORG).unwrap();
+    std::fs::write(dir.path().join("archive").join("notes.txt"), ORG).unwrap();
+    let org_dir = dir.path().to_str().unwrap();
+
+    // subdirectories are only searched with --recursive
+    let output = org_linter(&["--format", "json", "--org-dir", org_dir]);
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(findings.len(), 2);
+    let recursive = ["--format", "json", "--recursive", "--org-dir", org_dir];
+    let output = org_linter(&recursive);
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(findings.len(), 4);
+
+    std::fs::write(
+        dir.path().join(".org-linter-ignore"),
+        "# old files\narchive/*.org\n",
+    )
+    .unwrap();
+    let output = org_linter(&recursive);
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(findings.len(), 2);
+    assert!(findings
+        .iter()
+        .all(|finding| finding["file"].as_str().unwrap().ends_with("work.org")));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr.lines().last(),
+        Some("org-linter: 2 findings across 1 file (exit 1)")
+    );
+}
+
+#[test]
+fn merge_findings_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.json");
+    let b = dir.path().join("b.json");
+    let finding = |file: &str, line: usize| {
+        serde_json::json!({
+            "kind": "zero-duration",
+            "severity": "warning",
+            "file": file,
+            "line": line,
+            "message": "ZERO DURATION",
+        })
+    };
+    let run_a = serde_json::json!([
+        finding("/home/me/org/a.org", 3),
+        finding("/home/me/org/a.org", 5)
+    ]);
+    let run_b = serde_json::json!({
+        "stats": {},
+        "findings": [finding("/Users/me/org/a.org", 5), finding("/Users/me/org/b.org", 1)],
+    });
+    std::fs::write(&a, run_a.to_string()).unwrap();
+    std::fs::write(&b, run_b.to_string()).unwrap();
+
+    let output = org_linter(&["merge", a.to_str().unwrap(), b.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    let merged: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(merged.len(), 3);
+    assert_eq!(merged[0]["line"], 3);
+    assert_eq!(merged[1]["line"], 5);
+    assert_eq!(merged[2]["file"], "/Users/me/org/b.org");
+}
+
+#[test]
+fn diff_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old");
+    let new = dir.path().join("new");
+    std::fs::create_dir_all(&old).unwrap();
+    std::fs::create_dir_all(&new).unwrap();
+    // the new version fixes the duration of line 2 but adds a zero clock on line 5
+    std::fs::write(old.join("a.org"), ORG).unwrap();
+    std::fs::write(
+        new.join("a.org"),
+        "* task
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+CLOCK: [2022-12-12 Mon 09:00]--[2022-12-12 Mon 09:00] =>  0:00
+* other
+CLOCK: [2022-12-13 Tue 09:00]--[2022-12-13 Tue 09:00] =>  0:00
+",
+    )
+    .unwrap();
+
+    let output = org_linter(&[
+        "--format",
+        "json",
+        "--org-dir",
+        dir.path().to_str().unwrap(),
+        "diff",
+        old.to_str().unwrap(),
+        new.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(diff["added"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["added"][0]["line"], 5);
+    assert_eq!(diff["added"][0]["kind"], "zero-duration");
+    assert_eq!(diff["removed"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["removed"][0]["line"], 2);
+    assert_eq!(diff["removed"][0]["kind"], "duration-mismatch");
+}
+
+#[test]
+fn timing_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a.org", "b.org", "c.org"] {
+        std::fs::write(dir.path().join(name), ORG).unwrap();
+    }
+
+    let output = org_linter(&["--timing", "--org-dir", dir.path().to_str().unwrap()]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let per_file = stderr
+        .lines()
+        .filter(|line| line.starts_with("timing: ") && line.contains(" read "))
+        .count();
+    assert_eq!(per_file, 3);
+    assert!(stderr.contains("timing: 3 files in "));
+    let slowest = stderr.lines().filter(|line| line.starts_with("  ")).count();
+    assert_eq!(slowest, 3);
+
+    let output = org_linter(&["--org-dir", dir.path().to_str().unwrap()]);
+    assert!(!String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("timing:"));
+}
+
+#[test]
+fn assert_no_running() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.org");
+    std::fs::write(&file, ORG).unwrap();
+    let args = ["--assert-no-running", "--org-file", file.to_str().unwrap()];
+
+    // the finished clocks have findings but aren't running
+    let output = org_linter(&args);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    std::fs::write(&file, format!("{ORG}CLOCK: [2022-12-13 Tue 09:00]\n")).unwrap();
+    let output = org_linter(&args);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[a.org:4] [2022-12-13 Tue 09:00]  task\n"
+    );
+}
+
+#[test]
+fn stale_running_clock_as_of_now() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("running.org");
+    std::fs::write(&file, "* task\nCLOCK: [2023-02-14 Tue 09:00]\n").unwrap();
+    let check = |now: &str| {
+        let output = org_linter(&[
+            "--format",
+            "json",
+            "--report-stale-running",
+            "12:00",
+            "--now",
+            now,
+            "--org-file",
+            file.to_str().unwrap(),
+        ]);
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(check("2023-02-14 18:00").trim(), "[]");
+    assert!(check("2023-02-15 09:30").contains("is running for 24:30"));
+}
+
+#[test]
+fn split_clock_at_time() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("split.org");
+    std::fs::write(
+        &file,
+        "* task\nCLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 12:00] =>  2:00\n",
+    )
+    .unwrap();
+    let location = format!("{}:2", file.display());
+    let split = |time| {
+        org_linter(&[
+            "fix",
+            "--split",
+            &location,
+            time,
+            "--org-file",
+            file.to_str().unwrap(),
+        ])
+    };
+    let output = split("10:45");
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "* task
+CLOCK: [2022-12-12 Mon 10:45]--[2022-12-12 Mon 12:00] =>  1:15
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 10:45] =>  0:45
+"
+    );
+
+    assert!(!split("13:00").status.success());
+}
+
+#[test]
+fn strict_fails_on_parse_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("orphan.org");
+    std::fs::write(
+        &file,
+        "CLOCK: [2023-01-10 Tue 09:00]--[2023-01-10 Tue 10:00] =>  1:00\n* task\n",
+    )
+    .unwrap();
+    let output = org_linter(&["--org-file", file.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = org_linter(&[
+        "--strict",
+        "--format",
+        "json",
+        "--org-file",
+        file.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\"parse-warning\""));
+}
+
+#[test]
+fn fixers_keep_json_output_clean() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("fix.org");
+    std::fs::write(
+        &file,
+        "* task
+CLOCK: [2022-12-12 Mon 11:00]--[2022-12-12 Mon 12:00] =>  1:00
+CLOCK: [2022-12-13 Mon 09:00]--[2022-12-13 Mon 10:00] =>  1:00
+CLOCK: [2022-12-12 Mon 10:00]--[2022-12-12 Mon 11:00] =>  1:00
+",
+    )
+    .unwrap();
+    let output = org_linter(&[
+        "--format",
+        "json",
+        "--merge-adjacent",
+        "--fix-sort-clocks",
+        "--fix-weekday",
+        "--org-file",
+        file.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    serde_json::from_str::<serde_json::Value>(&stdout).expect("stdout is only JSON");
+    assert!(std::fs::read_to_string(&file)
+        .unwrap()
+        .contains("[2022-12-13 Tue 09:00]"));
+}
+
+#[test]
+fn github_format_notes_limited_findings() {
+    let dir = tempfile::tempdir().unwrap();
+    let dirty = dir.path().join("dirty.org");
+    std::fs::write(&dirty, ORG).unwrap();
+
+    let output = org_linter(&[
+        "--format",
+        "github",
+        "--limit",
+        "1",
+        "--org-file",
+        dirty.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{stdout}");
+    assert!(lines[0].starts_with("::error file="), "{stdout}");
+    assert_eq!(lines[1], "::notice::... and 1 more");
+}
+
+#[test]
+fn explain_keeps_json_output_clean() {
+    let dir = tempfile::tempdir().unwrap();
+    let dirty = dir.path().join("dirty.org");
+    std::fs::write(&dirty, ORG).unwrap();
+
+    for format in ["json", "summary-json"] {
+        let output = org_linter(&[
+            "--format",
+            format,
+            "--explain",
+            "--org-file",
+            dirty.to_str().unwrap(),
+        ]);
+        let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(!stdout.is_null());
+    }
+
+    let output = org_linter(&["--explain", "--org-file", dirty.to_str().unwrap()]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("duration-mismatch:\n"), "{stdout}");
+}
+
+#[test]
+fn diff_nested_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old");
+    let new = dir.path().join("new");
+    std::fs::create_dir_all(old.join("sub")).unwrap();
+    std::fs::create_dir_all(new.join("sub")).unwrap();
+    std::fs::write(old.join("sub").join("a.org"), "* task\n").unwrap();
+    std::fs::write(new.join("sub").join("a.org"), ORG).unwrap();
+    // only the new tree lowers the severity
+    std::fs::write(
+        new.join(".org-linter.toml"),
+        "[severity]\nduration-mismatch = \"info\"\n",
+    )
+    .unwrap();
+
+    let args = [
+        "--format",
+        "json",
+        "diff",
+        old.to_str().unwrap(),
+        new.to_str().unwrap(),
+    ];
+    let output = org_linter(&args);
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(diff["added"].as_array().unwrap().is_empty());
+
+    let output = org_linter(&[&args[..], &["--recursive"]].concat());
+    assert_eq!(output.status.code(), Some(1));
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let added = diff["added"].as_array().unwrap();
+    assert_eq!(added.len(), 2);
+    assert_eq!(added[0]["kind"], "duration-mismatch");
+    assert_eq!(added[0]["severity"], "info");
+    assert!(diff["removed"].as_array().unwrap().is_empty());
+}